@@ -0,0 +1,107 @@
+//! Compares one-shot searches against a reused `PathfinderWorkspace`.
+//!
+//! Run with `cargo bench --bench workspace`. Reports wall time and heap
+//! allocations per query, counted by a wrapping global allocator.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use enemy_pathfinder::map::RuntimeMapAdapter;
+use enemy_pathfinder::pathfinding::{
+    DiagonalMovement, PathOptions, PathfinderWorkspace, SearchAlgorithm, find_path_with_map,
+};
+
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(new_size, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+const ITERATIONS: usize = 2_000;
+
+struct Sample {
+    elapsed: Duration,
+    allocations: usize,
+    bytes: usize,
+}
+
+fn measure(mut run: impl FnMut()) -> Sample {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let bytes = ALLOCATED_BYTES.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        run();
+    }
+    Sample {
+        elapsed: start.elapsed(),
+        allocations: ALLOCATIONS.load(Ordering::Relaxed) - allocations,
+        bytes: ALLOCATED_BYTES.load(Ordering::Relaxed) - bytes,
+    }
+}
+
+fn report(name: &str, sample: &Sample, queries_per_run: usize) {
+    let n = (ITERATIONS * queries_per_run) as f64;
+    println!(
+        "{name:<28} {:>9.2} us/query {:>7.1} allocs/query {:>10.0} bytes/query",
+        sample.elapsed.as_secs_f64() * 1e6 / n,
+        sample.allocations as f64 / n,
+        sample.bytes as f64 / n,
+    );
+}
+
+fn main() {
+    let map_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("assets")
+        .join("map.json");
+    let map = RuntimeMapAdapter::from_tiled_json_wall_layer(map_path)
+        .expect("assets/map.json Wall_Layer should parse");
+    let queries = [((2, 18), (26, 10)), ((1, 3), (12, 3)), ((1, 1), (5, 1))];
+
+    for algorithm in [
+        SearchAlgorithm::AStar,
+        SearchAlgorithm::JumpPoint,
+        SearchAlgorithm::ThetaStar,
+    ] {
+        let options =
+            PathOptions::eight_way(DiagonalMovement::OnlyWhenNoObstacles).with_algorithm(algorithm);
+        println!("{algorithm:?}");
+
+        let fresh = measure(|| {
+            for &(start, goal) in &queries {
+                black_box(find_path_with_map(&map, start, goal, &options));
+            }
+        });
+        report("  fresh buffers", &fresh, queries.len());
+
+        let mut workspace = PathfinderWorkspace::new();
+        let reused = measure(|| {
+            for &(start, goal) in &queries {
+                black_box(workspace.find_path_with_map(&map, start, goal, &options));
+            }
+        });
+        report("  reused workspace", &reused, queries.len());
+    }
+}
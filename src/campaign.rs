@@ -0,0 +1,193 @@
+use serde::Deserialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::map::{
+    RuntimeMapAdapter, RuntimeMapAdapterError, actor_spawn_from_tiled_json, actor_spawn_from_tmx,
+    end_zone_from_tiled_json, end_zone_from_tmx,
+};
+
+/// Score for clearing a level.
+pub const LEVEL_CLEAR_SCORE: u32 = 1000;
+
+/// Levels played in order, read from a JSON manifest such as
+///
+/// ```json
+/// { "levels": [{ "map": "map.json", "name": "Warehouse" }, { "map": "yard.json" }] }
+/// ```
+///
+/// Map paths are relative to the manifest.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Campaign {
+    levels: Vec<Level>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Level {
+    pub map_path: PathBuf,
+    pub name: Option<String>,
+}
+
+impl Campaign {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, CampaignError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|source| CampaignError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let manifest: ManifestJson =
+            serde_json::from_str(&text).map_err(|source| CampaignError::Json {
+                path: path.to_path_buf(),
+                source,
+            })?;
+        if manifest.levels.is_empty() {
+            return Err(CampaignError::NoLevels {
+                path: path.to_path_buf(),
+            });
+        }
+
+        let dir = path.parent().unwrap_or(Path::new(""));
+        Ok(Self {
+            levels: manifest
+                .levels
+                .into_iter()
+                .map(|level| Level {
+                    map_path: dir.join(level.map),
+                    name: level.name,
+                })
+                .collect(),
+        })
+    }
+
+    pub fn levels(&self) -> &[Level] {
+        &self.levels
+    }
+
+    pub fn level(&self, index: usize) -> Option<&Level> {
+        self.levels.get(index)
+    }
+}
+
+/// What a level's map file, Tiled `.json` or `.tmx`, holds for play. Spawns
+/// and the end zone are `None` when the map does not place them.
+#[derive(Clone, Debug)]
+pub struct LevelMap {
+    pub collision_map: RuntimeMapAdapter,
+    pub enemy_spawn: Option<(f32, f32)>,
+    pub player_spawn: Option<(f32, f32)>,
+    pub end_zone: Option<(f32, f32, f32, f32)>,
+}
+
+impl LevelMap {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, RuntimeMapAdapterError> {
+        let path = path.as_ref();
+        let is_tmx = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("tmx"));
+        if is_tmx {
+            Ok(Self {
+                collision_map: RuntimeMapAdapter::from_tmx_wall_layer(path)?,
+                enemy_spawn: actor_spawn_from_tmx(path, "Enemy")?,
+                player_spawn: actor_spawn_from_tmx(path, "Player")?,
+                end_zone: end_zone_from_tmx(path)?,
+            })
+        } else {
+            Ok(Self {
+                collision_map: RuntimeMapAdapter::from_tiled_json_wall_layer(path)?,
+                enemy_spawn: actor_spawn_from_tiled_json(path, "Enemy")?,
+                player_spawn: actor_spawn_from_tiled_json(path, "Player")?,
+                end_zone: end_zone_from_tiled_json(path)?,
+            })
+        }
+    }
+}
+
+/// Where the player is in a campaign, and what carries over between levels.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CampaignProgress {
+    pub level: usize,
+    pub score: u32,
+    /// Seconds spent playing, retries included.
+    pub elapsed: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LevelOutcome {
+    /// Index of the level to load next.
+    Next(usize),
+    /// The last level was cleared.
+    Finished,
+}
+
+impl CampaignProgress {
+    pub fn tick(&mut self, dt: f32) {
+        self.elapsed += dt;
+    }
+
+    /// Scores the current level and moves on to the next one, if any.
+    pub fn complete_level(&mut self, campaign: &Campaign) -> LevelOutcome {
+        self.score += LEVEL_CLEAR_SCORE;
+        if self.level + 1 < campaign.levels.len() {
+            self.level += 1;
+            LevelOutcome::Next(self.level)
+        } else {
+            LevelOutcome::Finished
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum CampaignError {
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    Json {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    NoLevels {
+        path: PathBuf,
+    },
+}
+
+impl fmt::Display for CampaignError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, source } => write!(f, "failed to read {}: {}", path.display(), source),
+            Self::Json { path, source } => {
+                write!(
+                    f,
+                    "failed to parse campaign manifest {}: {}",
+                    path.display(),
+                    source
+                )
+            }
+            Self::NoLevels { path } => {
+                write!(f, "campaign manifest {} lists no levels", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for CampaignError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            Self::Json { source, .. } => Some(source),
+            Self::NoLevels { .. } => None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ManifestJson {
+    levels: Vec<LevelJson>,
+}
+
+#[derive(Deserialize)]
+struct LevelJson {
+    map: String,
+    #[serde(default)]
+    name: Option<String>,
+}
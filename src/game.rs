@@ -1,12 +1,34 @@
 use macroquad::prelude::*;
 use macroquad_tiled_clone::Map as TiledMap;
+use std::path::Path;
 
 use crate::actor::Actor;
-use crate::map::{RuntimeMapAdapter, actor_spawn_from_tiled_map, end_zone_from_tiled_map};
-use crate::pathfinding::astar_with_map;
-use crate::physics::{move_with_slide_with_map, resolve_circle_map_with_map};
+use crate::campaign::{Campaign, CampaignProgress, LevelMap, LevelOutcome};
+use crate::map::RuntimeMapAdapter;
+use crate::pathfinding::{
+    DiagonalMovement, LandmarkTable, PathOptions, PathSearch, PathfinderWorkspace, SearchAlgorithm,
+    SearchStatus,
+};
+use crate::physics::{
+    move_with_slide_with_map, resolve_circle_map_with_map, terrain_speed_with_map,
+};
 use crate::render::draw_map;
-use crate::visibility::has_line_of_sight_with_map;
+use crate::visibility::{
+    has_line_of_sight_with_map, has_walkable_line_with_map, smooth_path_with_map,
+};
+
+/// Nodes the enemy's path search may expand per frame.
+const PATH_SEARCH_BUDGET: usize = 256;
+
+/// Landmarks behind the enemy's A* heuristic.
+const LANDMARK_COUNT: usize = 8;
+
+/// Subdirectory of the system temp dir that caches landmark tables, so the
+/// game never writes into `assets/`.
+const LANDMARK_CACHE_DIR: &str = "enemy_pathfinder_landmarks";
+
+/// Manifest listing the levels in play order.
+pub const CAMPAIGN_PATH: &str = "assets/campaign.json";
 
 #[derive(PartialEq)]
 enum GameState {
@@ -24,52 +46,103 @@ pub async fn run() {
     }
 }
 
-struct Game {
+/// Everything read from one level's map, a Tiled `.json` or `.tmx` file.
+struct LoadedLevel {
     collision_map: RuntimeMapAdapter,
-    _tiled_map: TiledMap,
-    state: GameState,
+    tiled_map: TiledMap,
     enemy_spawn_pos: (f32, f32),
     player_spawn_pos: (f32, f32),
     end_zone: Option<Rect>,
+}
+
+impl LoadedLevel {
+    async fn load(map_path: &Path, path_options: &PathOptions) -> Self {
+        let map_name = map_path.display();
+        let tiled_map = TiledMap::load(&map_path.to_string_lossy())
+            .await
+            .unwrap_or_else(|err| panic!("Failed to load Tiled map {map_name}: {err:?}"));
+        let LevelMap {
+            mut collision_map,
+            enemy_spawn,
+            player_spawn,
+            end_zone,
+        } = LevelMap::load(map_path)
+            .unwrap_or_else(|err| panic!("Failed to build collision grid from {map_name}: {err}"));
+
+        let enemy_spawn_pos = enemy_spawn.unwrap_or_else(|| collision_map.grid_to_world(1, 2));
+        let player_spawn_pos = player_spawn.unwrap_or_else(|| collision_map.grid_to_world(17, 12));
+        let end_zone = end_zone.map(|(x, y, w, h)| Rect::new(x, y, w, h));
+
+        let cache_path =
+            LandmarkTable::cache_path(std::env::temp_dir().join(LANDMARK_CACHE_DIR), map_path);
+        let landmarks =
+            LandmarkTable::load_or_build(cache_path, &collision_map, LANDMARK_COUNT, path_options);
+        collision_map
+            .set_landmarks(landmarks)
+            .expect("landmark table was built for this map");
+
+        Self {
+            collision_map,
+            tiled_map,
+            enemy_spawn_pos,
+            player_spawn_pos,
+            end_zone,
+        }
+    }
+}
+
+struct Game {
+    campaign: Campaign,
+    progress: CampaignProgress,
+    level: LoadedLevel,
+    state: GameState,
+    path_options: PathOptions,
+    path_workspace: PathfinderWorkspace,
+    path_search: Option<PathSearch>,
     enemy: Actor,
     player: Actor,
     path: Vec<(usize, usize)>,
+    waypoints: Vec<(f32, f32)>,
     last_start: (usize, usize),
     last_goal: (usize, usize),
-    path_index: usize,
+    waypoint_index: usize,
 }
 
 impl Game {
     async fn new() -> Self {
-        let tiled_map = TiledMap::load("assets/map.json")
-            .await
-            .expect("Failed to load Tiled map: assets/map.json");
-        let collision_map = RuntimeMapAdapter::from_tiled_json_wall_layer("assets/map.json")
-            .expect("Failed to build collision grid from Wall_Layer in assets/map.json");
-
-        let enemy_spawn_pos = actor_spawn_from_tiled_map(&tiled_map, "Enemy")
-            .unwrap_or_else(|| collision_map.grid_to_world(1, 2));
-        let player_spawn_pos = actor_spawn_from_tiled_map(&tiled_map, "Player")
-            .unwrap_or_else(|| collision_map.grid_to_world(17, 12));
-        let end_zone =
-            end_zone_from_tiled_map(&tiled_map).map(|(x, y, w, h)| Rect::new(x, y, w, h));
-
-        let enemy = Actor::new_world(enemy_spawn_pos, 180.0);
-        let player = Actor::new_world(player_spawn_pos, 140.0);
+        let campaign = Campaign::load(CAMPAIGN_PATH)
+            .unwrap_or_else(|err| panic!("Failed to load campaign: {err}"));
+        let first = campaign
+            .level(0)
+            .expect("campaigns have at least one level");
+
+        let mut enemy = Actor::new_world((0.0, 0.0), 180.0);
+        // `PathSearch` only slices A*, and the landmark table only tightens
+        // A*, so the enemy no longer uses Jump Point Search.
+        let path_options = PathOptions::eight_way(DiagonalMovement::OnlyWhenNoObstacles)
+            .with_algorithm(SearchAlgorithm::AStar)
+            .with_agent_radius(enemy.radius)
+            .with_closest_fallback(true);
+        let level = LoadedLevel::load(&first.map_path, &path_options).await;
+
+        enemy.pos = level.enemy_spawn_pos;
+        let player = Actor::new_world(level.player_spawn_pos, 140.0);
 
         Self {
-            collision_map,
-            _tiled_map: tiled_map,
+            campaign,
+            progress: CampaignProgress::default(),
+            level,
             state: GameState::StartScreen,
-            enemy_spawn_pos,
-            player_spawn_pos,
-            end_zone,
+            path_options,
+            path_workspace: PathfinderWorkspace::new(),
+            path_search: None,
             enemy,
             player,
             path: vec![],
+            waypoints: vec![],
             last_start: (0, 0),
             last_goal: (0, 0),
-            path_index: 0,
+            waypoint_index: 0,
         }
     }
 
@@ -106,14 +179,24 @@ impl Game {
 
     async fn frame_playing(&mut self) {
         let dt = get_frame_time();
+        self.progress.tick(dt);
 
         self.update_player(dt);
 
-        let los = has_line_of_sight_with_map(&self.collision_map, self.enemy.pos, self.player.pos);
+        // Seeing the player through a window is no reason to run at the glass.
+        let los =
+            has_line_of_sight_with_map(&self.level.collision_map, self.enemy.pos, self.player.pos)
+                && has_walkable_line_with_map(
+                    &self.level.collision_map,
+                    self.enemy.pos,
+                    self.player.pos,
+                );
         let start_cell = self
+            .level
             .collision_map
             .world_to_grid(self.enemy.pos.0, self.enemy.pos.1);
         let goal_cell = self
+            .level
             .collision_map
             .world_to_grid(self.player.pos.0, self.player.pos.1);
 
@@ -126,7 +209,14 @@ impl Game {
         }
 
         if self.player_reached_end_zone() {
-            self.state = GameState::Win;
+            match self.progress.complete_level(&self.campaign) {
+                LevelOutcome::Next(index) => {
+                    self.enter_level(index).await;
+                    next_frame().await;
+                    return;
+                }
+                LevelOutcome::Finished => self.state = GameState::Win,
+            }
         } else {
             self.update_game_over_collision();
         }
@@ -135,15 +225,30 @@ impl Game {
         next_frame().await;
     }
 
+    /// Swaps in the level's map and starts it; score and time carry over.
+    async fn enter_level(&mut self, index: usize) {
+        let level = self
+            .campaign
+            .level(index)
+            .expect("campaign progress stays within the campaign");
+        self.level = LoadedLevel::load(&level.map_path, &self.path_options).await;
+        self.reset();
+    }
+
     async fn frame_win_screen(&mut self) {
         clear_background(macroquad::color::BLACK);
 
         if is_key_pressed(KeyCode::R) {
-            self.reset();
+            self.progress = CampaignProgress::default();
+            self.enter_level(0).await;
         }
 
         let text = "YOU WIN";
         let sub = "Press R to Restart";
+        let stats = format!(
+            "Score: {}   Time: {:.1}s",
+            self.progress.score, self.progress.elapsed
+        );
 
         let font_size = 60.0;
         let text_dim = measure_text(text, None, font_size as u16, 1.0);
@@ -165,12 +270,22 @@ impl Game {
             WHITE,
         );
 
+        let stats_dim = measure_text(&stats, None, sub_size as u16, 1.0);
+        draw_text(
+            &stats,
+            screen_width() / 2.0 - stats_dim.width / 2.0,
+            screen_height() / 2.0 + 90.0,
+            sub_size,
+            WHITE,
+        );
+
         next_frame().await;
     }
 
     async fn frame_game_over(&mut self) {
         clear_background(macroquad::color::BLACK);
 
+        // Retrying keeps score and clock running; time includes retries.
         if is_key_pressed(KeyCode::R) {
             self.reset();
         }
@@ -204,10 +319,12 @@ impl Game {
     }
 
     fn reset(&mut self) {
-        self.enemy = Actor::new_world(self.enemy_spawn_pos, 180.0);
-        self.player = Actor::new_world(self.player_spawn_pos, 140.0);
+        self.enemy = Actor::new_world(self.level.enemy_spawn_pos, 180.0);
+        self.player = Actor::new_world(self.level.player_spawn_pos, 140.0);
+        self.cancel_path_search();
         self.path.clear();
-        self.path_index = 0;
+        self.waypoints.clear();
+        self.waypoint_index = 0;
         self.last_start = (0, 0);
         self.last_goal = (0, 0);
         self.state = GameState::Playing;
@@ -236,36 +353,64 @@ impl Game {
         dir.0 /= norm;
         dir.1 /= norm;
 
-        self.player.pos = (
-            self.player.pos.0 + dir.0 * self.player.speed * dt,
-            self.player.pos.1,
-        );
-        resolve_circle_map_with_map(&self.collision_map, &mut self.player);
+        let speed = terrain_speed_with_map(&self.level.collision_map, &self.player);
 
-        self.player.pos = (
-            self.player.pos.0,
-            self.player.pos.1 + dir.1 * self.player.speed * dt,
-        );
-        resolve_circle_map_with_map(&self.collision_map, &mut self.player);
+        self.player.pos = (self.player.pos.0 + dir.0 * speed * dt, self.player.pos.1);
+        resolve_circle_map_with_map(&self.level.collision_map, &mut self.player);
+
+        self.player.pos = (self.player.pos.0, self.player.pos.1 + dir.1 * speed * dt);
+        resolve_circle_map_with_map(&self.level.collision_map, &mut self.player);
     }
 
+    /// Advances the enemy's path search by one budgeted slice. The current
+    /// path stays in use until a search started from fresher cells finishes.
     fn update_path_cache(
         &mut self,
         los: bool,
         start_cell: (usize, usize),
         goal_cell: (usize, usize),
     ) {
-        if !los {
-            if start_cell != self.last_start || goal_cell != self.last_goal || self.path.is_empty()
-            {
-                self.path = astar_with_map(&self.collision_map, start_cell, goal_cell);
-                self.last_start = start_cell;
-                self.last_goal = goal_cell;
-                self.path_index = 0;
-            }
-        } else {
+        if los {
+            self.cancel_path_search();
             self.path.clear();
-            self.path_index = 0;
+            self.waypoints.clear();
+            self.waypoint_index = 0;
+            return;
+        }
+
+        let stale =
+            start_cell != self.last_start || goal_cell != self.last_goal || self.path.is_empty();
+        if stale && self.path_search.is_none() {
+            let workspace = std::mem::take(&mut self.path_workspace);
+            self.path_search = Some(PathSearch::with_workspace(
+                workspace,
+                &self.level.collision_map,
+                start_cell,
+                goal_cell,
+                &self.path_options,
+            ));
+        }
+
+        let Some(search) = self.path_search.as_mut() else {
+            return;
+        };
+        let result = match search.step(&self.level.collision_map, PATH_SEARCH_BUDGET) {
+            SearchStatus::InProgress => return,
+            SearchStatus::Found(result) | SearchStatus::Failed(result) => result,
+        };
+
+        self.last_start = search.start();
+        self.last_goal = search.goal();
+        self.cancel_path_search();
+        self.path = result.cells;
+        self.waypoints =
+            smooth_path_with_map(&self.level.collision_map, &self.path, self.enemy.radius);
+        self.waypoint_index = 1;
+    }
+
+    fn cancel_path_search(&mut self) {
+        if let Some(search) = self.path_search.take() {
+            self.path_workspace = search.into_workspace();
         }
     }
 
@@ -280,74 +425,35 @@ impl Game {
         }
 
         let dir = (to_player.0 / dist, to_player.1 / dist);
+        let speed = terrain_speed_with_map(&self.level.collision_map, &self.enemy);
         self.enemy.pos = (
-            self.enemy.pos.0 + dir.0 * self.enemy.speed * dt,
-            self.enemy.pos.1 + dir.1 * self.enemy.speed * dt,
+            self.enemy.pos.0 + dir.0 * speed * dt,
+            self.enemy.pos.1 + dir.1 * speed * dt,
         );
-        resolve_circle_map_with_map(&self.collision_map, &mut self.enemy);
+        resolve_circle_map_with_map(&self.level.collision_map, &mut self.enemy);
     }
 
+    /// Walks straight toward the next smoothed waypoint. The first waypoint
+    /// is the center of the cell the path was planned from, so it is skipped.
     fn follow_path(&mut self, dt: f32) {
-        if self.path.len() < 2 {
-            return;
-        }
-
-        let enemy_cell = self
-            .collision_map
-            .world_to_grid(self.enemy.pos.0, self.enemy.pos.1);
-        self.sync_path_index(enemy_cell);
-
-        while self.path_index + 1 < self.path.len()
-            && self
-                .collision_map
-                .world_to_grid(self.enemy.pos.0, self.enemy.pos.1)
-                == self.path[self.path_index + 1]
-        {
-            self.path_index += 1;
-        }
-
-        if self.path_index + 1 >= self.path.len() {
-            return;
-        }
+        let speed = terrain_speed_with_map(&self.level.collision_map, &self.enemy);
+        let arrive_dist = (speed * dt).max(1.0);
 
-        let target_cell = self.path[self.path_index + 1];
-        let tgt = self
-            .collision_map
-            .grid_to_world(target_cell.0, target_cell.1);
+        while let Some(&tgt) = self.waypoints.get(self.waypoint_index) {
+            let dx = tgt.0 - self.enemy.pos.0;
+            let dy = tgt.1 - self.enemy.pos.1;
+            let dist = (dx * dx + dy * dy).sqrt();
 
-        let dx = tgt.0 - self.enemy.pos.0;
-        let dy = tgt.1 - self.enemy.pos.1;
-        let dist = (dx * dx + dy * dy).sqrt();
+            if dist <= arrive_dist {
+                self.waypoint_index += 1;
+                continue;
+            }
 
-        if dist <= 0.001 {
+            let vx = dx / dist * speed * dt;
+            let vy = dy / dist * speed * dt;
+            move_with_slide_with_map(&self.level.collision_map, &mut self.enemy, vx, vy);
             return;
         }
-
-        let dirx = dx / dist;
-        let diry = dy / dist;
-
-        let vx = dirx * self.enemy.speed * dt;
-        let vy = diry * self.enemy.speed * dt;
-
-        move_with_slide_with_map(&self.collision_map, &mut self.enemy, vx, vy);
-
-        let new_cell = self
-            .collision_map
-            .world_to_grid(self.enemy.pos.0, self.enemy.pos.1);
-        if new_cell == target_cell && self.path_index + 1 < self.path.len() {
-            self.path_index += 1;
-        }
-    }
-
-    fn sync_path_index(&mut self, enemy_cell: (usize, usize)) {
-        if self.path_index < self.path.len() && self.path[self.path_index] != enemy_cell {
-            for i in 0..self.path.len() {
-                if self.path[i] == enemy_cell {
-                    self.path_index = i;
-                    break;
-                }
-            }
-        }
     }
 
     fn update_game_over_collision(&mut self) {
@@ -361,7 +467,7 @@ impl Game {
     }
 
     fn player_reached_end_zone(&self) -> bool {
-        let Some(zone) = self.end_zone else {
+        let Some(zone) = self.level.end_zone else {
             return false;
         };
         zone.contains(vec2(self.player.pos.0, self.player.pos.1))
@@ -370,14 +476,10 @@ impl Game {
     fn draw_playing(&mut self, los: bool) {
         clear_background(macroquad::color::BLACK);
 
-        draw_map(&mut self._tiled_map);
+        draw_map(&mut self.level.tiled_map);
 
-        if self.path.len() >= 2 {
-            for w in self.path.windows(2) {
-                let a = self.collision_map.grid_to_world(w[0].0, w[0].1);
-                let b = self.collision_map.grid_to_world(w[1].0, w[1].1);
-                draw_line(a.0, a.1, b.0, b.1, 3.0, SKYBLUE);
-            }
+        for w in self.waypoints.windows(2) {
+            draw_line(w[0].0, w[0].1, w[1].0, w[1].1, 3.0, SKYBLUE);
         }
 
         draw_line(
@@ -407,6 +509,7 @@ impl Game {
         );
 
         let (pcx, pcy) = self
+            .level
             .collision_map
             .world_to_grid(self.player.pos.0, self.player.pos.1);
         draw_text(
@@ -423,5 +526,26 @@ impl Game {
             30.0,
             GREEN,
         );
+
+        let level = self.progress.level;
+        let level_name = self
+            .campaign
+            .level(level)
+            .and_then(|level| level.name.as_deref())
+            .unwrap_or_default();
+        draw_text(
+            &format!(
+                "Level {}/{} {}  Score: {}  Time: {:.1}s",
+                level + 1,
+                self.campaign.levels().len(),
+                level_name,
+                self.progress.score,
+                self.progress.elapsed
+            ),
+            10.0,
+            100.0,
+            30.0,
+            WHITE,
+        );
     }
 }
@@ -1,4 +1,5 @@
 pub mod actor;
+pub mod campaign;
 pub mod game;
 pub mod map;
 pub mod pathfinding;
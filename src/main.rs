@@ -1,5 +1,8 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use enemy_pathfinder::campaign::Campaign;
+use enemy_pathfinder::game::CAMPAIGN_PATH;
+use enemy_pathfinder::map::RuntimeMapAdapter;
 use macroquad::prelude::Conf;
 
 fn window_conf() -> Conf {
@@ -8,11 +11,16 @@ fn window_conf() -> Conf {
         ..Default::default()
     };
 
-    if let Ok(map) =
-        enemy_pathfinder::map::RuntimeMapAdapter::from_tiled_json_wall_layer("assets/map.json")
-    {
-        conf.window_width = (map.width as f32 * map.tile_size).round() as i32;
-        conf.window_height = (map.height as f32 * map.tile_size).round() as i32;
+    // Sized for the first level; later ones are expected to fit.
+    let campaign = Campaign::load(CAMPAIGN_PATH);
+    let first_map = campaign
+        .as_ref()
+        .ok()
+        .and_then(|campaign| campaign.level(0))
+        .map(|level| RuntimeMapAdapter::from_tiled_json_wall_layer(&level.map_path));
+    if let Some(Ok(map)) = first_map {
+        conf.window_width = (map.width as f32 * map.tile_width).round() as i32;
+        conf.window_height = (map.height as f32 * map.tile_height).round() as i32;
     }
 
     conf
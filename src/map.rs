@@ -1,67 +1,168 @@
 use macroquad_tiled_clone::{IrObjectShape, Map as TiledMap};
 use serde::Deserialize;
+use serde::de::IgnoredAny;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::path::{Path, PathBuf};
-use std::sync::OnceLock;
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
-static ACTIVE_RUNTIME_MAP: OnceLock<RuntimeMapAdapter> = OnceLock::new();
+use crate::pathfinding::LandmarkTable;
 
+mod chunked;
+mod hex;
+mod layer_data;
+mod obstacles;
+mod tilesets;
+mod tmx;
+
+use chunked::{ChunkStore, TileRect};
+pub use hex::{HexLayout, StaggerAxis, StaggerIndex};
+use layer_data::{DecodeError, LayerData, decode_gids};
+pub use obstacles::Obstacle;
+use obstacles::layer_obstacles;
+use tilesets::{TileCollision, load_external_tilesets, tile_collision, tile_definition};
+
+/// The map behind the global helpers. Maps are swapped whole, so callers
+/// holding the previous one keep a consistent snapshot.
+static ACTIVE_RUNTIME_MAP: RwLock<Option<Arc<RuntimeMapAdapter>>> = RwLock::new(None);
+
+pub const DEFAULT_WALL_LAYER: &str = "Wall_Layer";
+pub const DEFAULT_COST_LAYER: &str = "Cost_Layer";
+/// Optional layer whose painted cells override what blocks sight.
+pub const DEFAULT_SIGHT_LAYER: &str = "Sight_Layer";
+/// Optional object layer whose rectangles, ellipses and polygons are
+/// obstacles.
+pub const DEFAULT_COLLISION_LAYER: &str = "Collision_Layer";
+pub const COST_PROPERTY: &str = "cost";
+/// Boolean tile property that makes a wall-layer tile block or not,
+/// whatever collision shapes it has.
+pub const SOLID_PROPERTY: &str = "solid";
+/// Boolean tile or collision-object property that makes it block sight or
+/// not, whatever it does to movement.
+pub const BLOCKS_SIGHT_PROPERTY: &str = "blocks_sight";
+
+const TILED_GID_MASK: u32 = 0x1fff_ffff;
+
+/// Clearance is only measured this many tiles out; roomier cells report the
+/// cap. Agents wider than this are not expected.
+pub const MAX_CLEARANCE_TILES: usize = 8;
+
+/// Largest area, in cells, an infinite map's chunks may span. The grid is
+/// dense, so chunks far apart would otherwise allocate everything between
+/// them.
+pub const MAX_INFINITE_MAP_CELLS: usize = 1 << 22;
+
+// A panic cannot leave a half-written map behind, so a poisoned lock is
+// still good to use.
+fn read_active_map() -> RwLockReadGuard<'static, Option<Arc<RuntimeMapAdapter>>> {
+    ACTIVE_RUNTIME_MAP
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+}
+
+fn write_active_map() -> RwLockWriteGuard<'static, Option<Arc<RuntimeMapAdapter>>> {
+    ACTIVE_RUNTIME_MAP
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Installs the map unless one is already installed, in which case the
+/// adapter is handed back. Use `replace_runtime_map` to switch maps.
+#[allow(clippy::result_large_err)]
 pub fn install_runtime_map(adapter: RuntimeMapAdapter) -> Result<(), RuntimeMapAdapter> {
-    ACTIVE_RUNTIME_MAP.set(adapter)
+    let mut active = write_active_map();
+    if active.is_some() {
+        return Err(adapter);
+    }
+    *active = Some(Arc::new(adapter));
+    Ok(())
 }
 
+/// Like `install_runtime_map`, but an installed map equal to the adapter
+/// counts as success.
 pub fn install_runtime_map_compatible(
     adapter: RuntimeMapAdapter,
 ) -> Result<(), RuntimeMapInstallError> {
-    match ACTIVE_RUNTIME_MAP.set(adapter) {
-        Ok(()) => Ok(()),
-        Err(adapter) => {
-            let existing = ACTIVE_RUNTIME_MAP
-                .get()
-                .expect("OnceLock set failed but existing runtime map missing");
-
-            if existing.is_compatible_with(&adapter) {
-                Ok(())
-            } else {
-                Err(RuntimeMapInstallError::IncompatibleExisting {
-                    existing: existing.summary(),
-                    attempted: adapter.summary(),
-                })
-            }
+    let mut active = write_active_map();
+    match active.as_deref() {
+        None => {
+            *active = Some(Arc::new(adapter));
+            Ok(())
         }
+        Some(existing) if existing.is_compatible_with(&adapter) => Ok(()),
+        Some(existing) => Err(RuntimeMapInstallError::IncompatibleExisting {
+            existing: existing.summary(),
+            attempted: adapter.summary(),
+        }),
     }
 }
 
-pub fn runtime_map() -> Option<&'static RuntimeMapAdapter> {
-    ACTIVE_RUNTIME_MAP.get()
+/// Installs the map in place of any installed one, e.g. on a level
+/// transition, and returns the one it replaced.
+pub fn replace_runtime_map(
+    adapter: impl Into<Arc<RuntimeMapAdapter>>,
+) -> Option<Arc<RuntimeMapAdapter>> {
+    write_active_map().replace(adapter.into())
+}
+
+/// Uninstalls the map, after which the global helpers panic and their
+/// `try_` variants return `None` until another is installed.
+pub fn clear_runtime_map() -> Option<Arc<RuntimeMapAdapter>> {
+    write_active_map().take()
+}
+
+/// The installed map. Later replacements do not affect the returned handle,
+/// so code that makes several queries should take the map once and use the
+/// `_with_map` functions.
+pub fn runtime_map() -> Option<Arc<RuntimeMapAdapter>> {
+    read_active_map().clone()
 }
 
 pub fn try_map_width() -> Option<usize> {
-    runtime_map().map(|m| m.width)
+    read_active_map().as_deref().map(|m| m.width)
 }
 
 pub fn try_map_height() -> Option<usize> {
-    runtime_map().map(|m| m.height)
+    read_active_map().as_deref().map(|m| m.height)
 }
 
-pub fn try_map_tile_size() -> Option<f32> {
-    runtime_map().map(|m| m.tile_size)
+/// Tile width and height.
+pub fn try_map_tile_size() -> Option<(f32, f32)> {
+    read_active_map()
+        .as_deref()
+        .map(|m| (m.tile_width, m.tile_height))
 }
 
 pub fn try_world_to_grid(x: f32, y: f32) -> Option<(usize, usize)> {
-    runtime_map().map(|m| m.world_to_grid(x, y))
+    read_active_map().as_deref().map(|m| m.world_to_grid(x, y))
 }
 
 pub fn try_grid_to_world(x: usize, y: usize) -> Option<(f32, f32)> {
-    runtime_map().map(|m| m.grid_to_world(x, y))
+    read_active_map().as_deref().map(|m| m.grid_to_world(x, y))
 }
 
 pub fn try_is_wall(x: usize, y: usize) -> Option<bool> {
-    runtime_map().map(|m| m.is_wall(x, y))
+    read_active_map().as_deref().map(|m| m.is_wall(x, y))
+}
+
+pub fn try_blocks_sight(x: usize, y: usize) -> Option<bool> {
+    read_active_map().as_deref().map(|m| m.blocks_sight(x, y))
 }
 
 pub fn try_blocked_for_agent(x: usize, y: usize) -> Option<bool> {
-    runtime_map().map(|m| m.blocked_for_agent(x, y))
+    read_active_map()
+        .as_deref()
+        .map(|m| m.blocked_for_agent(x, y))
+}
+
+pub fn try_blocked_for_radius(x: usize, y: usize, radius: f32) -> Option<bool> {
+    read_active_map()
+        .as_deref()
+        .map(|m| m.blocked_for_radius(x, y, radius))
+}
+
+pub fn try_traversal_cost(x: usize, y: usize) -> Option<f32> {
+    read_active_map().as_deref().map(|m| m.traversal_cost(x, y))
 }
 
 pub fn map_width() -> usize {
@@ -72,7 +173,7 @@ pub fn map_height() -> usize {
     try_map_height().expect("runtime map height unavailable")
 }
 
-pub fn map_tile_size() -> f32 {
+pub fn map_tile_size() -> (f32, f32) {
     try_map_tile_size().expect("runtime map tile size unavailable")
 }
 
@@ -88,10 +189,22 @@ pub fn is_wall(x: usize, y: usize) -> bool {
     try_is_wall(x, y).expect("runtime is_wall unavailable")
 }
 
+pub fn blocks_sight(x: usize, y: usize) -> bool {
+    try_blocks_sight(x, y).expect("runtime blocks_sight unavailable")
+}
+
 pub fn blocked_for_agent(x: usize, y: usize) -> bool {
     try_blocked_for_agent(x, y).expect("runtime blocked_for_agent unavailable")
 }
 
+pub fn blocked_for_radius(x: usize, y: usize, radius: f32) -> bool {
+    try_blocked_for_radius(x, y, radius).expect("runtime blocked_for_radius unavailable")
+}
+
+pub fn traversal_cost(x: usize, y: usize) -> f32 {
+    try_traversal_cost(x, y).expect("runtime traversal_cost unavailable")
+}
+
 pub fn actor_spawn_from_tiled_map(tiled_map: &TiledMap, actor_type: &str) -> Option<(f32, f32)> {
     let layer = tiled_map
         .object_layers()
@@ -137,17 +250,39 @@ pub fn actor_spawn_from_tiled_json(
     path: impl AsRef<Path>,
     actor_type: &str,
 ) -> Result<Option<(f32, f32)>, RuntimeMapAdapterError> {
-    let path = path.as_ref();
-    let map = read_tiled_json_map(path)?;
+    let map = read_tiled_json_map(path.as_ref())?;
+    Ok(actor_spawn_in(&map, actor_type))
+}
+
+pub fn actor_spawn_from_tmx(
+    path: impl AsRef<Path>,
+    actor_type: &str,
+) -> Result<Option<(f32, f32)>, RuntimeMapAdapterError> {
+    let map = tmx::read_tmx_map(path.as_ref())?;
+    Ok(actor_spawn_in(&map, actor_type))
+}
 
-    let layer = match map.layers.iter().find(|layer| {
+/// File-based counterpart of `end_zone_from_tiled_map`.
+pub fn end_zone_from_tiled_json(
+    path: impl AsRef<Path>,
+) -> Result<Option<(f32, f32, f32, f32)>, RuntimeMapAdapterError> {
+    let map = read_tiled_json_map(path.as_ref())?;
+    Ok(end_zone_in(&map))
+}
+
+pub fn end_zone_from_tmx(
+    path: impl AsRef<Path>,
+) -> Result<Option<(f32, f32, f32, f32)>, RuntimeMapAdapterError> {
+    let map = tmx::read_tmx_map(path.as_ref())?;
+    Ok(end_zone_in(&map))
+}
+
+fn actor_spawn_in(map: &TiledJsonMap, actor_type: &str) -> Option<(f32, f32)> {
+    let layer = map.layers.iter().find(|layer| {
         layer.kind == "objectgroup" && layer.name == "Actors_Layer" && layer.visible.unwrap_or(true)
-    }) {
-        Some(layer) => layer,
-        None => return Ok(None),
-    };
+    })?;
 
-    let obj = match layer.objects.iter().find(|obj| {
+    let obj = layer.objects.iter().find(|obj| {
         actor_spawn_candidate_matches(
             actor_type,
             obj.kind.as_deref().unwrap_or_default(),
@@ -155,15 +290,33 @@ pub fn actor_spawn_from_tiled_json(
             obj.visible.unwrap_or(true),
             obj.point,
         )
-    }) {
-        Some(obj) => obj,
-        None => return Ok(None),
-    };
+    })?;
+
+    Some((
+        obj.x.unwrap_or(0.0) + layer.offsetx.unwrap_or(0.0),
+        obj.y.unwrap_or(0.0) + layer.offsety.unwrap_or(0.0),
+    ))
+}
+
+fn end_zone_in(map: &TiledJsonMap) -> Option<(f32, f32, f32, f32)> {
+    let layer = map.layers.iter().find(|layer| {
+        layer.kind == "objectgroup" && layer.name == "End area" && layer.visible.unwrap_or(true)
+    })?;
+
+    let obj = layer.objects.iter().find(|obj| {
+        obj.visible.unwrap_or(true)
+            && obj.kind.as_deref() == Some("EndArea")
+            && obj.is_rectangle()
+            && obj.width > 0.0
+            && obj.height > 0.0
+    })?;
 
-    Ok(Some((
+    Some((
         obj.x.unwrap_or(0.0) + layer.offsetx.unwrap_or(0.0),
         obj.y.unwrap_or(0.0) + layer.offsety.unwrap_or(0.0),
-    )))
+        obj.width,
+        obj.height,
+    ))
 }
 
 fn actor_spawn_candidate_matches(
@@ -176,34 +329,209 @@ fn actor_spawn_candidate_matches(
     layer_visible && object_visible && is_point && candidate_actor_type == expected_actor_type
 }
 
+/// World-space rect as its `(min, max)` corners.
+pub type WorldRect = ((f32, f32), (f32, f32));
+
+/// Solid part of a cell that only partly blocks, relative to the cell's
+/// top-left corner, in world units.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CellRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl CellRect {
+    fn contains(&self, px: f32, py: f32) -> bool {
+        px > self.x && px < self.x + self.width && py > self.y && py < self.y + self.height
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct RuntimeMapAdapter {
-    pub tile_size: f32,
+    pub tile_width: f32,
+    pub tile_height: f32,
     pub width: usize,
     pub height: usize,
+    /// Cells that block movement.
     pub solid: Vec<bool>,
+    /// Cells that block line of sight. Usually the walls, but windows and
+    /// chasms block movement only, and smoke or foliage sight only.
+    pub opaque: Vec<bool>,
+    /// Traversal cost multiplier per cell; 1.0 is plain floor.
+    pub cost: Vec<f32>,
+    /// World-space distance from each cell center to the nearest wall,
+    /// capped at `MAX_CLEARANCE_TILES` tiles. Zero on walls.
+    pub clearance: Vec<f32>,
+    /// Set for hexagonal maps, whose cells are staggered hexes addressed by
+    /// Tiled's offset coordinates.
+    pub hex: Option<HexLayout>,
+    /// Tiled tile coordinate of cell (0, 0). Negative when an infinite map
+    /// has chunks left of or above the Tiled origin.
+    pub origin: (i32, i32),
+    /// Collision shapes of partly blocking cells, keyed by cell index.
+    shapes: HashMap<usize, Vec<CellRect>>,
+    /// Collision objects not tied to cells.
+    obstacles: Vec<Obstacle>,
+    /// Indices of cells that are walls only because an obstacle overlaps
+    /// them; they collide through the obstacle, not the tile.
+    obstacle_cells: HashSet<usize>,
+    landmarks: Option<Arc<LandmarkTable>>,
 }
 
 impl RuntimeMapAdapter {
+    pub fn from_solid_cells(tile_size: f32, width: usize, height: usize, solid: Vec<bool>) -> Self {
+        assert_eq!(
+            solid.len(),
+            width * height,
+            "solid cell count must match map dimensions"
+        );
+
+        let mut adapter = Self {
+            tile_width: tile_size,
+            tile_height: tile_size,
+            width,
+            height,
+            cost: vec![1.0; solid.len()],
+            opaque: solid.clone(),
+            solid,
+            clearance: Vec::new(),
+            hex: None,
+            origin: (0, 0),
+            shapes: HashMap::new(),
+            obstacles: Vec::new(),
+            obstacle_cells: HashSet::new(),
+            landmarks: None,
+        };
+        adapter.rebuild_clearance();
+        adapter
+    }
+
+    pub fn with_traversal_costs(mut self, cost: Vec<f32>) -> Self {
+        assert_eq!(
+            cost.len(),
+            self.width * self.height,
+            "traversal cost count must match map dimensions"
+        );
+        self.cost = cost;
+        self.landmarks = None;
+        self
+    }
+
+    /// Replaces which cells block sight, which otherwise follows `solid`.
+    pub fn with_opacity(mut self, opaque: Vec<bool>) -> Self {
+        assert_eq!(
+            opaque.len(),
+            self.width * self.height,
+            "opacity count must match map dimensions"
+        );
+        self.opaque = opaque;
+        self
+    }
+
+    /// Adds collision objects that block sight, rasterized into the grid;
+    /// see `rasterize_obstacles`.
+    pub fn with_obstacles(mut self, obstacles: Vec<Obstacle>) -> Self {
+        self.rasterize_obstacles(obstacles.into_iter().map(|obstacle| (obstacle, true)));
+        self.rebuild_clearance();
+        self
+    }
+
+    /// Gives the cells rectangular `tile_width x tile_height` extents.
+    pub fn with_tile_size(mut self, tile_width: f32, tile_height: f32) -> Self {
+        self.tile_width = tile_width;
+        self.tile_height = tile_height;
+        self.landmarks = None;
+        self.rebuild_clearance();
+        self
+    }
+
+    /// Treats the cells as staggered hexes laid out by `layout`.
+    pub fn with_hex_layout(mut self, layout: HexLayout) -> Self {
+        self.tile_width = layout.tile_width;
+        self.tile_height = layout.tile_height;
+        self.hex = Some(layout);
+        self.landmarks = None;
+        self.rebuild_clearance();
+        self
+    }
+
     pub fn from_tiled_json_wall_layer(
         path: impl AsRef<Path>,
     ) -> Result<Self, RuntimeMapAdapterError> {
-        Self::from_tiled_json_named_wall_layer(path, "Wall_Layer")
+        Self::from_tiled_json_named_wall_layer(path, DEFAULT_WALL_LAYER)
     }
 
     pub fn from_tiled_json_named_wall_layer(
         path: impl AsRef<Path>,
         wall_layer_name: &str,
+    ) -> Result<Self, RuntimeMapAdapterError> {
+        Self::from_tiled_json_named_layers(path, wall_layer_name, Some(DEFAULT_COST_LAYER))
+    }
+
+    /// Builds the adapter from a wall layer and an optional cost layer.
+    ///
+    /// A missing cost layer leaves every cell at cost 1.0. Cells painted on the
+    /// cost layer take the `cost` property of their tile, falling back to the
+    /// layer's own `cost` property.
+    ///
+    /// Which wall-layer tiles block, and how, comes from their tilesets; see
+    /// `SOLID_PROPERTY`. Walkable wall-layer tiles also apply their `cost`.
+    ///
+    /// Cells block sight when they block movement, unless their tile says
+    /// otherwise through `BLOCKS_SIGHT_PROPERTY`. Tiles painted on an
+    /// optional `Sight_Layer` override that: they block sight unless their
+    /// tile's `blocks_sight` is false.
+    ///
+    /// Area objects on an optional `Collision_Layer` object layer become
+    /// obstacles, which block sight unless their `blocks_sight` property is
+    /// false; see `rasterize_obstacles`.
+    ///
+    /// Infinite maps cover the chunks of both layers, and `origin` records
+    /// where that area starts.
+    pub fn from_tiled_json_named_layers(
+        path: impl AsRef<Path>,
+        wall_layer_name: &str,
+        cost_layer_name: Option<&str>,
     ) -> Result<Self, RuntimeMapAdapterError> {
         let path = path.as_ref();
-        let map = read_tiled_json_map(path)?;
+        let mut map = read_tiled_json_map(path)?;
+        load_external_tilesets(&mut map, path)?;
+        Self::from_tiled_layers(path, &map, wall_layer_name, cost_layer_name)
+    }
 
-        if map.tilewidth != map.tileheight {
-            return Err(RuntimeMapAdapterError::UnsupportedNonSquareTiles {
-                tilewidth: map.tilewidth,
-                tileheight: map.tileheight,
-            });
-        }
+    pub fn from_tmx_wall_layer(path: impl AsRef<Path>) -> Result<Self, RuntimeMapAdapterError> {
+        Self::from_tmx_named_layers(path, DEFAULT_WALL_LAYER, Some(DEFAULT_COST_LAYER))
+    }
+
+    /// TMX counterpart of `from_tiled_json_named_layers`.
+    pub fn from_tmx_named_layers(
+        path: impl AsRef<Path>,
+        wall_layer_name: &str,
+        cost_layer_name: Option<&str>,
+    ) -> Result<Self, RuntimeMapAdapterError> {
+        let path = path.as_ref();
+        let mut map = tmx::read_tmx_map(path)?;
+        load_external_tilesets(&mut map, path)?;
+        Self::from_tiled_layers(path, &map, wall_layer_name, cost_layer_name)
+    }
+
+    fn from_tiled_layers(
+        path: &Path,
+        map: &TiledJsonMap,
+        wall_layer_name: &str,
+        cost_layer_name: Option<&str>,
+    ) -> Result<Self, RuntimeMapAdapterError> {
+        let hex = match map.orientation.as_deref() {
+            None | Some("orthogonal") => None,
+            Some("hexagonal") => Some(hex_layout(map)?),
+            Some(other) => {
+                return Err(RuntimeMapAdapterError::UnsupportedOrientation {
+                    orientation: other.to_owned(),
+                });
+            }
+        };
 
         let wall_layer = map
             .layers
@@ -213,49 +541,181 @@ impl RuntimeMapAdapter {
                 path: path.to_path_buf(),
                 layer_name: wall_layer_name.to_owned(),
             })?;
+        let find_tile_layer = |name: &str| {
+            map.layers
+                .iter()
+                .find(|layer| layer.kind == "tilelayer" && layer.name == name)
+        };
+        let cost_layer = cost_layer_name.and_then(find_tile_layer);
+        let sight_layer = find_tile_layer(DEFAULT_SIGHT_LAYER);
 
-        if wall_layer.width != map.width || wall_layer.height != map.height {
-            return Err(RuntimeMapAdapterError::LayerDimensionsMismatch {
-                layer_name: wall_layer.name.clone(),
-                map_width: map.width,
-                map_height: map.height,
-                layer_width: wall_layer.width,
-                layer_height: wall_layer.height,
-            });
-        }
+        let extent = if map.infinite {
+            let mut stores = vec![chunk_store(wall_layer)?];
+            for layer in cost_layer.iter().chain(&sight_layer) {
+                stores.push(chunk_store(layer)?);
+            }
+            let extent = chunked_extent(&stores, hex.is_some());
+            if extent.width.saturating_mul(extent.height) > MAX_INFINITE_MAP_CELLS {
+                return Err(RuntimeMapAdapterError::InfiniteMapTooLarge {
+                    width: extent.width,
+                    height: extent.height,
+                });
+            }
+            extent
+        } else {
+            TileRect {
+                x: 0,
+                y: 0,
+                width: map.width,
+                height: map.height,
+            }
+        };
 
-        let expected_len = map.width * map.height;
-        if wall_layer.data.len() != expected_len {
-            return Err(RuntimeMapAdapterError::InvalidLayerDataLen {
-                layer_name: wall_layer.name.clone(),
-                expected: expected_len,
-                actual: wall_layer.data.len(),
+        let cell = (map.tilewidth as f32, map.tileheight as f32);
+        let wall_gids = layer_gids(map, wall_layer, extent)?;
+        let mut collisions = HashMap::new();
+        let mut solid = Vec::with_capacity(wall_gids.len());
+        let mut shapes = HashMap::new();
+        for (i, &gid) in wall_gids.iter().enumerate() {
+            let collision = collisions
+                .entry(gid)
+                .or_insert_with(|| tile_collision(map, gid, cell));
+            solid.push(match collision {
+                TileCollision::Open => false,
+                TileCollision::Solid => true,
+                TileCollision::Shapes(rects) => {
+                    shapes.insert(i, rects.clone());
+                    shapes_cover_center(rects, cell)
+                }
             });
         }
 
-        let solid = wall_layer.data.iter().map(|gid| *gid != 0).collect();
+        let blocks_sight = |gid: u32| tile_property_bool(map, gid, BLOCKS_SIGHT_PROPERTY);
+        let opaque: Vec<bool> = wall_gids
+            .iter()
+            .zip(&solid)
+            .map(|(&gid, &solid)| {
+                if gid == 0 {
+                    return solid;
+                }
+                blocks_sight(gid).unwrap_or(solid)
+            })
+            .collect();
+        let sight_gids = sight_layer
+            .map(|layer| layer_gids(map, layer, extent))
+            .transpose()?;
+
+        let mut cost = vec![1.0; extent.width * extent.height];
+        for (i, &gid) in wall_gids.iter().enumerate() {
+            if gid != 0
+                && !solid[i]
+                && let Some(tile_cost) = tile_property_f32(map, gid, COST_PROPERTY)
+            {
+                cost[i] = checked_cost(wall_layer, i, extent.width, tile_cost)?;
+            }
+        }
+        if let Some(layer) = cost_layer {
+            let gids = layer_gids(map, layer, extent)?;
+            paint_cost_layer(map, layer, &gids, extent.width, &mut cost)?;
+        }
 
-        Ok(Self {
-            tile_size: map.tilewidth as f32,
-            width: map.width,
-            height: map.height,
+        let mut adapter = Self {
+            tile_width: map.tilewidth as f32,
+            tile_height: map.tileheight as f32,
+            width: extent.width,
+            height: extent.height,
             solid,
-        })
+            opaque,
+            cost,
+            clearance: Vec::new(),
+            hex,
+            origin: (extent.x, extent.y),
+            shapes,
+            obstacles: Vec::new(),
+            obstacle_cells: HashSet::new(),
+            landmarks: None,
+        };
+        let collision_layer = map.layers.iter().find(|layer| {
+            layer.kind == "objectgroup"
+                && layer.name == DEFAULT_COLLISION_LAYER
+                && layer.visible.unwrap_or(true)
+        });
+        if let Some(layer) = collision_layer {
+            adapter.rasterize_obstacles(layer_obstacles(layer));
+        }
+        for (i, &gid) in sight_gids.iter().flatten().enumerate() {
+            if gid != 0 {
+                adapter.opaque[i] = blocks_sight(gid).unwrap_or(true);
+            }
+        }
+        adapter.rebuild_clearance();
+        Ok(adapter)
     }
 
+    /// Cell containing the world point. Points left of or above the map
+    /// clamp to column or row 0.
     #[inline]
     pub fn world_to_grid(&self, x: f32, y: f32) -> (usize, usize) {
-        ((x / self.tile_size) as usize, (y / self.tile_size) as usize)
+        let (tx, ty) = match &self.hex {
+            Some(hex) => hex.cell_at(x, y),
+            None => (
+                (x / self.tile_width).floor() as i32,
+                (y / self.tile_height).floor() as i32,
+            ),
+        };
+        (
+            (tx - self.origin.0).max(0) as usize,
+            (ty - self.origin.1).max(0) as usize,
+        )
     }
 
+    /// World position of the cell's center.
     #[inline]
     pub fn grid_to_world(&self, x: usize, y: usize) -> (f32, f32) {
+        let (tx, ty) = self.cell_to_tile(x, y);
+        if let Some(hex) = &self.hex {
+            return hex.cell_center(tx, ty);
+        }
         (
-            (x as f32) * self.tile_size + self.tile_size / 2.0,
-            (y as f32) * self.tile_size + self.tile_size / 2.0,
+            tx as f32 * self.tile_width + self.tile_width / 2.0,
+            ty as f32 * self.tile_height + self.tile_height / 2.0,
         )
     }
 
+    /// World-space corners `(min, max)` of the cell's tile rectangle.
+    #[inline]
+    pub fn cell_bounds(&self, x: usize, y: usize) -> WorldRect {
+        let (tx, ty) = self.cell_to_tile(x, y);
+        let min = (tx as f32 * self.tile_width, ty as f32 * self.tile_height);
+        (min, (min.0 + self.tile_width, min.1 + self.tile_height))
+    }
+
+    /// Tiled tile coordinate of the cell.
+    #[inline]
+    pub fn cell_to_tile(&self, x: usize, y: usize) -> (i32, i32) {
+        (x as i32 + self.origin.0, y as i32 + self.origin.1)
+    }
+
+    /// Cell at a Tiled tile coordinate, if the map covers it.
+    pub fn tile_to_cell(&self, tx: i32, ty: i32) -> Option<(usize, usize)> {
+        let x = tx - self.origin.0;
+        let y = ty - self.origin.1;
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return None;
+        }
+        Some((x as usize, y as usize))
+    }
+
+    /// World position of the top-left corner of cell (0, 0) on orthogonal
+    /// maps.
+    pub fn world_origin(&self) -> (f32, f32) {
+        (
+            self.origin.0 as f32 * self.tile_width,
+            self.origin.1 as f32 * self.tile_height,
+        )
+    }
+
+    /// Whether the cell blocks movement. Out-of-bounds cells do.
     #[inline]
     pub fn is_wall(&self, x: usize, y: usize) -> bool {
         if x >= self.width || y >= self.height {
@@ -264,35 +724,382 @@ impl RuntimeMapAdapter {
         self.solid[self.idx(x, y)]
     }
 
+    /// Whether the cell blocks line of sight. Out-of-bounds cells do.
+    #[inline]
+    pub fn blocks_sight(&self, x: usize, y: usize) -> bool {
+        if x >= self.width || y >= self.height {
+            return true;
+        }
+        self.opaque[self.idx(x, y)]
+    }
+
+    /// Changes whether a cell blocks sight, leaving movement alone, e.g. for
+    /// smoke. Out-of-bounds cells are ignored.
+    pub fn set_opaque(&mut self, x: usize, y: usize, opaque: bool) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let i = self.idx(x, y);
+        self.opaque[i] = opaque;
+    }
+
     pub fn blocked_for_agent(&self, x: usize, y: usize) -> bool {
+        self.blocked_for_radius(x, y, 0.0)
+    }
+
+    /// Whether an agent of `radius` world units cannot stand centered on the
+    /// cell without overlapping a wall.
+    #[inline]
+    pub fn blocked_for_radius(&self, x: usize, y: usize, radius: f32) -> bool {
         if self.is_wall(x, y) {
             return true;
         }
+        self.clearance[self.idx(x, y)] < radius
+    }
 
-        let r: isize = 0;
-        let xi = x as isize;
-        let yi = y as isize;
+    #[inline]
+    pub fn clearance(&self, x: usize, y: usize) -> f32 {
+        if x >= self.width || y >= self.height {
+            return 0.0;
+        }
+        self.clearance[self.idx(x, y)]
+    }
 
-        for dy in -r..=r {
-            for dx in -r..=r {
-                let nx = xi + dx;
-                let ny = yi + dy;
-                if nx < 0 || ny < 0 {
-                    return true;
+    /// Cells whose centers lie inside the world-space rect, e.g. a Tiled
+    /// area object given as `(x, y, width, height)`.
+    pub fn cells_in_world_rect(&self, x: f32, y: f32, w: f32, h: f32) -> Vec<(usize, usize)> {
+        let mut cells = Vec::new();
+        for cy in 0..self.height {
+            for cx in 0..self.width {
+                let (wx, wy) = self.grid_to_world(cx, cy);
+                if wx >= x && wx < x + w && wy >= y && wy < y + h {
+                    cells.push((cx, cy));
                 }
+            }
+        }
+        cells
+    }
+
+    /// Changes a cell's solidity at runtime, e.g. for doors or destructible
+    /// walls, and refreshes the clearance of cells around it. Sight is left
+    /// alone; see `set_opaque`. Replaces any collision shapes the cell had,
+    /// and detaches the landmark table. Out-of-bounds cells are ignored.
+    pub fn set_solid(&mut self, x: usize, y: usize, solid: bool) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let i = self.idx(x, y);
+        self.solid[i] = solid;
+        self.shapes.remove(&i);
+        self.obstacle_cells.remove(&i);
+        self.landmarks = None;
+        self.refresh_clearance_around(x, y);
+    }
+
+    /// Collision shapes of a cell that only partly blocks; empty for open
+    /// cells and for cells that block as a whole.
+    pub fn cell_shapes(&self, x: usize, y: usize) -> &[CellRect] {
+        if x >= self.width || y >= self.height {
+            return &[];
+        }
+        self.shapes
+            .get(&self.idx(x, y))
+            .map_or(&[], |shapes| shapes.as_slice())
+    }
+
+    /// Makes the cell block only where `shapes` are, e.g. a half-height
+    /// wall tile. Pathfinding treats the cell as a wall when the shapes
+    /// cover its center; physics and clearance use the shapes themselves.
+    /// Sight is left alone, as with `set_solid`. No shapes opens the cell.
+    /// Out-of-bounds cells are ignored.
+    pub fn set_cell_shapes(&mut self, x: usize, y: usize, shapes: Vec<CellRect>) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let i = self.idx(x, y);
+        self.solid[i] = shapes_cover_center(&shapes, (self.tile_width, self.tile_height));
+        self.obstacle_cells.remove(&i);
+        if shapes.is_empty() {
+            self.shapes.remove(&i);
+        } else {
+            self.shapes.insert(i, shapes);
+        }
+        self.landmarks = None;
+        self.refresh_clearance_around(x, y);
+    }
 
-                let nx = nx as usize;
-                let ny = ny as usize;
-                if nx >= self.width || ny >= self.height {
-                    return true;
+    /// Appends the world-space `(min, max)` rects the cell blocks: its
+    /// shapes if it has any, else the whole tile if it is a wall. Cells
+    /// walled off by obstacles alone add nothing; see `obstacles`.
+    pub fn collision_rects(&self, x: usize, y: usize, out: &mut Vec<WorldRect>) {
+        let shapes = self.cell_shapes(x, y);
+        if shapes.is_empty() {
+            if self.is_wall(x, y) && !self.is_obstacle_cell(x, y) {
+                out.push(self.cell_bounds(x, y));
+            }
+            return;
+        }
+
+        let (min, _) = self.cell_bounds(x, y);
+        out.extend(shapes.iter().map(|shape| {
+            let lo = (min.0 + shape.x, min.1 + shape.y);
+            (lo, (lo.0 + shape.width, lo.1 + shape.height))
+        }));
+    }
+
+    /// Collision objects not tied to cells, such as those of a
+    /// `Collision_Layer`.
+    pub fn obstacles(&self) -> &[Obstacle] {
+        &self.obstacles
+    }
+
+    /// Stores the obstacles and makes a wall of every cell one overlaps at
+    /// all, so that pathfinding routes around them. Obstacles paired with
+    /// `true` also make those cells block sight. Physics and clearance use
+    /// the obstacles themselves. Callers rebuild clearance afterwards.
+    fn rasterize_obstacles(&mut self, obstacles: impl IntoIterator<Item = (Obstacle, bool)>) {
+        for (obstacle, blocks_sight) in obstacles {
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    if !obstacle.overlaps_convex(&self.cell_outline(x, y)) {
+                        continue;
+                    }
+                    let i = self.idx(x, y);
+                    if blocks_sight {
+                        self.opaque[i] = true;
+                    }
+                    if !self.solid[i] {
+                        self.solid[i] = true;
+                        self.obstacle_cells.insert(i);
+                    }
                 }
-                if self.solid[self.idx(nx, ny)] {
-                    return true;
+            }
+            self.obstacles.push(obstacle);
+        }
+        self.landmarks = None;
+    }
+
+    /// World-space corners of the cell: its tile rectangle, or its hex.
+    fn cell_outline(&self, x: usize, y: usize) -> Vec<(f32, f32)> {
+        if let Some(hex) = &self.hex {
+            let (cx, cy) = self.grid_to_world(x, y);
+            return hex
+                .corner_offsets()
+                .iter()
+                .map(|&(dx, dy)| (cx + dx, cy + dy))
+                .collect();
+        }
+        let (min, max) = self.cell_bounds(x, y);
+        vec![min, (max.0, min.1), max, (min.0, max.1)]
+    }
+
+    fn is_obstacle_cell(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height && self.obstacle_cells.contains(&self.idx(x, y))
+    }
+
+    fn refresh_clearance_around(&mut self, x: usize, y: usize) {
+        let reach = MAX_CLEARANCE_TILES;
+        for cy in y.saturating_sub(reach)..(y + reach + 1).min(self.height) {
+            for cx in x.saturating_sub(reach)..(x + reach + 1).min(self.width) {
+                let ci = self.idx(cx, cy);
+                self.clearance[ci] = self.measure_clearance(cx, cy);
+            }
+        }
+    }
+
+    fn rebuild_clearance(&mut self) {
+        self.clearance = (0..self.width * self.height)
+            .map(|i| self.measure_clearance(i % self.width, i / self.width))
+            .collect();
+    }
+
+    /// Scans rings of growing Chebyshev radius and stops once no farther
+    /// ring can hold a closer wall. Out-of-bounds cells count as walls.
+    /// Distances are measured in world units, so rectangular tiles weigh
+    /// horizontal and vertical gaps differently. Cells with collision shapes
+    /// are measured to the shapes rather than the tile, and obstacles count
+    /// wherever they are.
+    fn measure_clearance(&self, x: usize, y: usize) -> f32 {
+        if self.is_wall(x, y) {
+            return 0.0;
+        }
+        if let Some(hex) = &self.hex {
+            return self.measure_hex_clearance(hex, x, y);
+        }
+
+        let (tw, th) = (self.tile_width, self.tile_height);
+        let short_side = tw.min(th);
+        let center = self.grid_to_world(x, y);
+        let mut best = self
+            .obstacles
+            .iter()
+            .map(|obstacle| obstacle.distance_to(center))
+            .fold(MAX_CLEARANCE_TILES as f32 * short_side, f32::min)
+            .min(self.shape_distance(x, y, center));
+        for k in 1..=MAX_CLEARANCE_TILES as isize {
+            if (k as f32 - 0.5) * short_side >= best {
+                break;
+            }
+            for dy in -k..=k {
+                let step = if dy.abs() == k { 1 } else { 2 * k as usize };
+                for dx in (-k..=k).step_by(step) {
+                    let nx = x as isize + dx;
+                    let ny = y as isize + dy;
+                    if nx >= 0 && ny >= 0 {
+                        let (nx, ny) = (nx as usize, ny as usize);
+                        if !self.cell_shapes(nx, ny).is_empty() {
+                            best = best.min(self.shape_distance(nx, ny, center));
+                            continue;
+                        }
+                        if !self.is_wall(nx, ny) || self.is_obstacle_cell(nx, ny) {
+                            continue;
+                        }
+                    }
+
+                    let gap_x = (dx.abs() as f32 - 0.5).max(0.0) * tw;
+                    let gap_y = (dy.abs() as f32 - 0.5).max(0.0) * th;
+                    best = best.min((gap_x * gap_x + gap_y * gap_y).sqrt());
                 }
             }
         }
 
-        false
+        best
+    }
+
+    /// World distance from the point to the nearest collision shape of the
+    /// cell; infinite without shapes.
+    fn shape_distance(&self, x: usize, y: usize, point: (f32, f32)) -> f32 {
+        let shapes = self.cell_shapes(x, y);
+        if shapes.is_empty() {
+            return f32::INFINITY;
+        }
+
+        let mut rects = Vec::with_capacity(shapes.len());
+        self.collision_rects(x, y, &mut rects);
+        rects
+            .iter()
+            .map(|&(min, max)| {
+                let dx = (min.0 - point.0).max(point.0 - max.0).max(0.0);
+                let dy = (min.1 - point.1).max(point.1 - max.1).max(0.0);
+                (dx * dx + dy * dy).sqrt()
+            })
+            .fold(f32::INFINITY, f32::min)
+    }
+
+    /// Hex version of `measure_clearance`: a wall `k` steps away leaves
+    /// `k - 0.5` neighbor spacings of room. Offset coordinates never differ
+    /// by more than the step count, so a square window is enough.
+    fn measure_hex_clearance(&self, hex: &HexLayout, x: usize, y: usize) -> f32 {
+        let reach = MAX_CLEARANCE_TILES as isize;
+        let mut best = MAX_CLEARANCE_TILES as f32;
+        for dy in -reach..=reach {
+            for dx in -reach..=reach {
+                let nx = x as isize + dx;
+                let ny = y as isize + dy;
+                if nx >= 0 && ny >= 0 && !self.is_wall(nx as usize, ny as usize) {
+                    continue;
+                }
+                let (ax, ay) = hex.to_axial(x as i32, y as i32);
+                let (bx, by) = hex.to_axial(nx as i32, ny as i32);
+                let (dq, dr) = (ax - bx, ay - by);
+                let steps = (dq.abs() + dr.abs() + (dq + dr).abs()) / 2;
+                best = best.min(steps as f32 - 0.5);
+            }
+        }
+        best * hex.neighbor_spacing()
+    }
+
+    /// Cost multiplier for entering the cell. Out-of-bounds cells are walls
+    /// and report the plain floor cost.
+    #[inline]
+    pub fn traversal_cost(&self, x: usize, y: usize) -> f32 {
+        if x >= self.width || y >= self.height {
+            return 1.0;
+        }
+        self.cost[self.idx(x, y)]
+    }
+
+    pub fn min_traversal_cost(&self) -> f32 {
+        let min = self.cost.iter().copied().fold(f32::INFINITY, f32::min);
+        if min.is_finite() { min } else { 1.0 }
+    }
+
+    pub fn has_uniform_cost(&self) -> bool {
+        self.cost.iter().all(|&c| c == 1.0)
+    }
+
+    /// Attaches precomputed landmark distances, which A* then uses to tighten
+    /// its heuristic for queries the table supports. A table built for a
+    /// different map is handed back.
+    ///
+    /// Every edit that changes movement detaches the table again, since its
+    /// distances no longer hold: `set_solid`, `set_cell_shapes` and the
+    /// `with_*` builders. Check `landmarks()` after such edits and attach a
+    /// rebuilt table if the heuristic matters.
+    pub fn set_landmarks(&mut self, table: LandmarkTable) -> Result<(), LandmarkTable> {
+        if !table.matches_map(self) {
+            return Err(table);
+        }
+        self.landmarks = Some(Arc::new(table));
+        Ok(())
+    }
+
+    /// Detaches the landmark table. Needed after editing `solid` or `cost`
+    /// directly; `set_solid` and `with_traversal_costs` do it themselves.
+    pub fn clear_landmarks(&mut self) {
+        self.landmarks = None;
+    }
+
+    pub fn landmarks(&self) -> Option<&LandmarkTable> {
+        self.landmarks.as_deref()
+    }
+
+    /// Feeds `feed` everything walls, costs and clearance are derived from,
+    /// for fingerprinting.
+    pub(crate) fn feed_collision_inputs(&self, feed: &mut dyn FnMut(&[u8])) {
+        feed(&(self.width as u64).to_le_bytes());
+        feed(&(self.height as u64).to_le_bytes());
+        feed(&self.tile_width.to_bits().to_le_bytes());
+        feed(&self.tile_height.to_bits().to_le_bytes());
+        feed(&self.origin.0.to_le_bytes());
+        feed(&self.origin.1.to_le_bytes());
+        match &self.hex {
+            None => feed(&[0]),
+            Some(hex) => {
+                feed(&[1, hex.stagger_axis as u8, hex.stagger_index as u8]);
+                for v in [hex.tile_width, hex.tile_height, hex.side_length] {
+                    feed(&v.to_bits().to_le_bytes());
+                }
+            }
+        }
+        for &solid in &self.solid {
+            feed(&[solid as u8]);
+        }
+        for cost in &self.cost {
+            feed(&cost.to_bits().to_le_bytes());
+        }
+
+        let mut shaped: Vec<_> = self.shapes.iter().collect();
+        shaped.sort_unstable_by_key(|(i, _)| **i);
+        for (i, shapes) in shaped {
+            feed(&(*i as u64).to_le_bytes());
+            for shape in shapes {
+                for v in [shape.x, shape.y, shape.width, shape.height] {
+                    feed(&v.to_bits().to_le_bytes());
+                }
+            }
+        }
+        for obstacle in &self.obstacles {
+            let points = match obstacle {
+                Obstacle::Rect((min, max)) => vec![*min, *max],
+                Obstacle::Polygon(points) => points.clone(),
+            };
+            feed(&(points.len() as u64).to_le_bytes());
+            for (x, y) in points {
+                feed(&x.to_bits().to_le_bytes());
+                feed(&y.to_bits().to_le_bytes());
+            }
+        }
     }
 
     #[inline]
@@ -303,13 +1110,21 @@ impl RuntimeMapAdapter {
     pub fn is_compatible_with(&self, other: &Self) -> bool {
         self.width == other.width
             && self.height == other.height
-            && self.tile_size.to_bits() == other.tile_size.to_bits()
+            && self.tile_width.to_bits() == other.tile_width.to_bits()
+            && self.tile_height.to_bits() == other.tile_height.to_bits()
+            && self.hex == other.hex
+            && self.origin == other.origin
             && self.solid == other.solid
+            && self.opaque == other.opaque
+            && self.shapes == other.shapes
+            && self.obstacles == other.obstacles
+            && self.cost == other.cost
     }
 
     pub fn summary(&self) -> RuntimeMapAdapterSummary {
         RuntimeMapAdapterSummary {
-            tile_size: self.tile_size,
+            tile_width: self.tile_width,
+            tile_height: self.tile_height,
             width: self.width,
             height: self.height,
             solid_cells: self.solid.iter().filter(|&&v| v).count(),
@@ -317,6 +1132,218 @@ impl RuntimeMapAdapter {
     }
 }
 
+fn validate_tile_layer(
+    map: &TiledJsonMap,
+    layer: &TiledJsonLayer,
+    gids: &[u32],
+) -> Result<(), RuntimeMapAdapterError> {
+    if layer.width != map.width || layer.height != map.height {
+        return Err(RuntimeMapAdapterError::LayerDimensionsMismatch {
+            layer_name: layer.name.clone(),
+            map_width: map.width,
+            map_height: map.height,
+            layer_width: layer.width,
+            layer_height: layer.height,
+        });
+    }
+
+    let expected_len = map.width * map.height;
+    if gids.len() != expected_len {
+        return Err(RuntimeMapAdapterError::InvalidLayerDataLen {
+            layer_name: layer.name.clone(),
+            expected: expected_len,
+            actual: gids.len(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Collects an infinite map layer's chunks, checking each one's data length.
+fn chunk_store(layer: &TiledJsonLayer) -> Result<ChunkStore, RuntimeMapAdapterError> {
+    let mut store = ChunkStore::default();
+    for chunk in &layer.chunks {
+        let gids = layer_data_gids(layer, &chunk.data)?;
+        let expected_len = chunk.width * chunk.height;
+        if gids.len() != expected_len {
+            return Err(RuntimeMapAdapterError::InvalidLayerDataLen {
+                layer_name: layer.name.clone(),
+                expected: expected_len,
+                actual: gids.len(),
+            });
+        }
+        store.insert(chunk.x, chunk.y, chunk.width, chunk.height, gids);
+    }
+    Ok(store)
+}
+
+/// Area covering every chunk of the layers. Hex maps start on an even line
+/// so that cells keep the stagger parity of their Tiled coordinates.
+fn chunked_extent(stores: &[ChunkStore], hex: bool) -> TileRect {
+    let Some(mut extent) = stores
+        .iter()
+        .filter_map(ChunkStore::bounds)
+        .reduce(TileRect::union)
+    else {
+        return TileRect {
+            x: 0,
+            y: 0,
+            width: 0,
+            height: 0,
+        };
+    };
+
+    if hex {
+        let aligned = TileRect {
+            x: extent.x.div_euclid(2) * 2,
+            y: extent.y.div_euclid(2) * 2,
+            width: 0,
+            height: 0,
+        };
+        extent = extent.union(aligned);
+    }
+    extent
+}
+
+/// The layer's gids over `extent`, row by row.
+fn layer_gids(
+    map: &TiledJsonMap,
+    layer: &TiledJsonLayer,
+    extent: TileRect,
+) -> Result<Vec<u32>, RuntimeMapAdapterError> {
+    if map.infinite {
+        return Ok(chunk_store(layer)?.rasterize(extent));
+    }
+    let gids = layer_data_gids(layer, &layer.data)?;
+    validate_tile_layer(map, layer, &gids)?;
+    Ok(gids)
+}
+
+/// Decodes `data`, which belongs to `layer` or one of its chunks.
+fn layer_data_gids(
+    layer: &TiledJsonLayer,
+    data: &LayerData,
+) -> Result<Vec<u32>, RuntimeMapAdapterError> {
+    decode_gids(
+        data,
+        layer.encoding.as_deref(),
+        layer.compression.as_deref(),
+    )
+    .map_err(|err| match err {
+        DecodeError::Unsupported(detail) => RuntimeMapAdapterError::UnsupportedLayerEncoding {
+            layer_name: layer.name.clone(),
+            detail,
+        },
+        DecodeError::Invalid(detail) => RuntimeMapAdapterError::InvalidLayerData {
+            layer_name: layer.name.clone(),
+            detail,
+        },
+    })
+}
+
+/// Whether a cell with these shapes blocks pathfinding.
+fn shapes_cover_center(shapes: &[CellRect], cell: (f32, f32)) -> bool {
+    let (cx, cy) = (cell.0 / 2.0, cell.1 / 2.0);
+    shapes.iter().any(|shape| shape.contains(cx, cy))
+}
+
+/// Overwrites `cost` on every cell painted on the cost layer.
+fn paint_cost_layer(
+    map: &TiledJsonMap,
+    layer: &TiledJsonLayer,
+    gids: &[u32],
+    width: usize,
+    cost: &mut [f32],
+) -> Result<(), RuntimeMapAdapterError> {
+    let layer_cost = property_f32(&layer.properties, COST_PROPERTY);
+
+    for (i, &gid) in gids.iter().enumerate() {
+        if gid == 0 {
+            continue;
+        }
+        let tile_cost = tile_property_f32(map, gid, COST_PROPERTY)
+            .or(layer_cost)
+            .unwrap_or(1.0);
+        cost[i] = checked_cost(layer, i, width, tile_cost)?;
+    }
+    Ok(())
+}
+
+fn checked_cost(
+    layer: &TiledJsonLayer,
+    i: usize,
+    width: usize,
+    cost: f32,
+) -> Result<f32, RuntimeMapAdapterError> {
+    if !cost.is_finite() || cost <= 0.0 {
+        return Err(RuntimeMapAdapterError::InvalidTraversalCost {
+            layer_name: layer.name.clone(),
+            x: i % width,
+            y: i / width,
+            cost,
+        });
+    }
+    Ok(cost)
+}
+
+fn tile_property_bool(map: &TiledJsonMap, gid: u32, name: &str) -> Option<bool> {
+    let (_, tile) = tile_definition(map, gid)?;
+    property_bool(&tile?.properties, name)
+}
+
+fn tile_property_f32(map: &TiledJsonMap, gid: u32, name: &str) -> Option<f32> {
+    let (_, tile) = tile_definition(map, gid)?;
+    property_f32(&tile?.properties, name)
+}
+
+fn property_bool(properties: &[TiledJsonProperty], name: &str) -> Option<bool> {
+    properties
+        .iter()
+        .find(|property| property.name == name)
+        .and_then(|property| property.value.as_bool())
+}
+
+fn property_f32(properties: &[TiledJsonProperty], name: &str) -> Option<f32> {
+    properties
+        .iter()
+        .find(|property| property.name == name)
+        .and_then(|property| property.value.as_f64())
+        .map(|value| value as f32)
+}
+
+/// Tiled omits the stagger settings it considers default, so missing ones
+/// fall back to Tiled's own defaults.
+fn hex_layout(map: &TiledJsonMap) -> Result<HexLayout, RuntimeMapAdapterError> {
+    let stagger_axis = match map.staggeraxis.as_deref() {
+        None | Some("y") => StaggerAxis::Y,
+        Some("x") => StaggerAxis::X,
+        Some(other) => {
+            return Err(RuntimeMapAdapterError::InvalidHexParameter {
+                name: "staggeraxis",
+                value: other.to_owned(),
+            });
+        }
+    };
+    let stagger_index = match map.staggerindex.as_deref() {
+        None | Some("odd") => StaggerIndex::Odd,
+        Some("even") => StaggerIndex::Even,
+        Some(other) => {
+            return Err(RuntimeMapAdapterError::InvalidHexParameter {
+                name: "staggerindex",
+                value: other.to_owned(),
+            });
+        }
+    };
+
+    Ok(HexLayout {
+        tile_width: map.tilewidth as f32,
+        tile_height: map.tileheight as f32,
+        side_length: map.hexsidelength.unwrap_or(0) as f32,
+        stagger_axis,
+        stagger_index,
+    })
+}
+
 fn read_tiled_json_map(path: &Path) -> Result<TiledJsonMap, RuntimeMapAdapterError> {
     let text = std::fs::read_to_string(path).map_err(|source| RuntimeMapAdapterError::Io {
         path: path.to_path_buf(),
@@ -338,13 +1365,26 @@ pub enum RuntimeMapAdapterError {
         path: PathBuf,
         source: serde_json::Error,
     },
+    Xml {
+        path: PathBuf,
+        source: roxmltree::Error,
+    },
+    /// Well-formed XML that is not a TMX map this loader understands.
+    MalformedTmx {
+        path: PathBuf,
+        detail: String,
+    },
     MissingWallLayer {
         path: PathBuf,
         layer_name: String,
     },
-    UnsupportedNonSquareTiles {
-        tilewidth: u32,
-        tileheight: u32,
+    /// Only orthogonal and hexagonal maps are understood.
+    UnsupportedOrientation {
+        orientation: String,
+    },
+    InvalidHexParameter {
+        name: &'static str,
+        value: String,
     },
     LayerDimensionsMismatch {
         layer_name: String,
@@ -358,6 +1398,28 @@ pub enum RuntimeMapAdapterError {
         expected: usize,
         actual: usize,
     },
+    /// Layer data in an encoding or compression that cannot be read.
+    UnsupportedLayerEncoding {
+        layer_name: String,
+        detail: String,
+    },
+    /// Layer data that does not decode as its encoding claims.
+    InvalidLayerData {
+        layer_name: String,
+        detail: String,
+    },
+    InvalidTraversalCost {
+        layer_name: String,
+        x: usize,
+        y: usize,
+        cost: f32,
+    },
+    /// The chunks of an infinite map span more than
+    /// `MAX_INFINITE_MAP_CELLS`.
+    InfiniteMapTooLarge {
+        width: usize,
+        height: usize,
+    },
 }
 
 impl fmt::Display for RuntimeMapAdapterError {
@@ -372,20 +1434,24 @@ impl fmt::Display for RuntimeMapAdapterError {
                     source
                 )
             }
+            Self::Xml { path, source } => {
+                write!(f, "failed to parse tmx {}: {}", path.display(), source)
+            }
+            Self::MalformedTmx { path, detail } => {
+                write!(f, "malformed tmx {}: {}", path.display(), detail)
+            }
             Self::MissingWallLayer { path, layer_name } => write!(
                 f,
                 "missing tile layer '{}' in {}",
                 layer_name,
                 path.display()
             ),
-            Self::UnsupportedNonSquareTiles {
-                tilewidth,
-                tileheight,
-            } => write!(
-                f,
-                "non-square tiles are not supported yet ({}x{})",
-                tilewidth, tileheight
-            ),
+            Self::UnsupportedOrientation { orientation } => {
+                write!(f, "map orientation '{}' is not supported", orientation)
+            }
+            Self::InvalidHexParameter { name, value } => {
+                write!(f, "invalid hex map parameter {} = '{}'", name, value)
+            }
             Self::LayerDimensionsMismatch {
                 layer_name,
                 map_width,
@@ -406,6 +1472,27 @@ impl fmt::Display for RuntimeMapAdapterError {
                 "layer '{}' data length mismatch: expected {}, got {}",
                 layer_name, expected, actual
             ),
+            Self::UnsupportedLayerEncoding { layer_name, detail } => {
+                write!(f, "layer '{}' uses unsupported {}", layer_name, detail)
+            }
+            Self::InvalidLayerData { layer_name, detail } => {
+                write!(f, "layer '{}' has invalid data: {}", layer_name, detail)
+            }
+            Self::InvalidTraversalCost {
+                layer_name,
+                x,
+                y,
+                cost,
+            } => write!(
+                f,
+                "layer '{}' has invalid traversal cost {} at ({}, {}); costs must be positive",
+                layer_name, cost, x, y
+            ),
+            Self::InfiniteMapTooLarge { width, height } => write!(
+                f,
+                "infinite map chunks span {}x{} tiles, more than {} cells",
+                width, height, MAX_INFINITE_MAP_CELLS
+            ),
         }
     }
 }
@@ -415,6 +1502,7 @@ impl std::error::Error for RuntimeMapAdapterError {
         match self {
             Self::Io { source, .. } => Some(source),
             Self::Json { source, .. } => Some(source),
+            Self::Xml { source, .. } => Some(source),
             _ => None,
         }
     }
@@ -422,7 +1510,8 @@ impl std::error::Error for RuntimeMapAdapterError {
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct RuntimeMapAdapterSummary {
-    pub tile_size: f32,
+    pub tile_width: f32,
+    pub tile_height: f32,
     pub width: usize,
     pub height: usize,
     pub solid_cells: usize,
@@ -444,14 +1533,16 @@ impl fmt::Display for RuntimeMapInstallError {
                 attempted,
             } => write!(
                 f,
-                "runtime map already installed with incompatible data (existing: {}x{} @ {}, solid {}; attempted: {}x{} @ {}, solid {})",
+                "runtime map already installed with incompatible data (existing: {}x{} @ {}x{}, solid {}; attempted: {}x{} @ {}x{}, solid {})",
                 existing.width,
                 existing.height,
-                existing.tile_size,
+                existing.tile_width,
+                existing.tile_height,
                 existing.solid_cells,
                 attempted.width,
                 attempted.height,
-                attempted.tile_size,
+                attempted.tile_width,
+                attempted.tile_height,
                 attempted.solid_cells
             ),
         }
@@ -466,7 +1557,19 @@ struct TiledJsonMap {
     height: usize,
     tilewidth: u32,
     tileheight: u32,
+    #[serde(default)]
+    infinite: bool,
+    #[serde(default)]
+    orientation: Option<String>,
+    #[serde(default)]
+    hexsidelength: Option<u32>,
+    #[serde(default)]
+    staggeraxis: Option<String>,
+    #[serde(default)]
+    staggerindex: Option<String>,
     layers: Vec<TiledJsonLayer>,
+    #[serde(default)]
+    tilesets: Vec<TiledJsonTileset>,
 }
 
 #[derive(Deserialize)]
@@ -485,11 +1588,67 @@ struct TiledJsonLayer {
     #[serde(default)]
     height: usize,
     #[serde(default)]
-    data: Vec<u32>,
+    encoding: Option<String>,
+    #[serde(default)]
+    compression: Option<String>,
+    #[serde(default)]
+    data: LayerData,
+    /// Tile data of infinite maps, which leave `data` empty.
+    #[serde(default)]
+    chunks: Vec<TiledJsonChunk>,
+    #[serde(default)]
+    objects: Vec<TiledJsonObject>,
+    #[serde(default)]
+    properties: Vec<TiledJsonProperty>,
+}
+
+#[derive(Deserialize)]
+struct TiledJsonChunk {
+    x: i32,
+    y: i32,
+    width: usize,
+    height: usize,
+    data: LayerData,
+}
+
+/// Also the shape of external tileset files, which have no `firstgid`.
+#[derive(Deserialize)]
+struct TiledJsonTileset {
+    #[serde(default)]
+    firstgid: u32,
+    #[serde(default)]
+    source: Option<String>,
+    #[serde(default)]
+    tilewidth: Option<u32>,
+    #[serde(default)]
+    tileheight: Option<u32>,
+    #[serde(default)]
+    tiles: Vec<TiledJsonTile>,
+}
+
+#[derive(Deserialize)]
+struct TiledJsonTile {
+    id: u32,
+    #[serde(default)]
+    properties: Vec<TiledJsonProperty>,
+    /// Collision shapes drawn in Tiled's tile collision editor.
+    #[serde(default)]
+    objectgroup: Option<TiledJsonObjectGroup>,
+}
+
+#[derive(Deserialize)]
+struct TiledJsonObjectGroup {
     #[serde(default)]
     objects: Vec<TiledJsonObject>,
 }
 
+#[derive(Deserialize)]
+struct TiledJsonProperty {
+    name: String,
+    #[serde(default)]
+    value: serde_json::Value,
+}
+
 #[derive(Deserialize)]
 struct TiledJsonObject {
     #[serde(default, rename = "type")]
@@ -499,9 +1658,37 @@ struct TiledJsonObject {
     #[serde(default)]
     y: Option<f32>,
     #[serde(default)]
+    width: f32,
+    #[serde(default)]
+    height: f32,
+    #[serde(default)]
     point: bool,
     #[serde(default)]
+    ellipse: bool,
+    /// Vertices relative to the object's position.
+    #[serde(default)]
+    polygon: Option<Vec<TiledJsonPoint>>,
+    #[serde(default)]
+    polyline: Option<IgnoredAny>,
+    /// Degrees clockwise about `(x, y)`.
+    #[serde(default)]
+    rotation: f32,
+    #[serde(default)]
     visible: Option<bool>,
+    #[serde(default)]
+    properties: Vec<TiledJsonProperty>,
+}
+
+#[derive(Deserialize)]
+struct TiledJsonPoint {
+    x: f32,
+    y: f32,
+}
+
+impl TiledJsonObject {
+    fn is_rectangle(&self) -> bool {
+        !self.point && !self.ellipse && self.polygon.is_none() && self.polyline.is_none()
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,107 @@
+//! Chunked tile layers of Tiled's infinite maps, rasterized over the
+//! rectangle covering all chunks.
+
+use std::collections::HashMap;
+
+/// Rectangle of Tiled tile coordinates.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(super) struct TileRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl TileRect {
+    /// Smallest rectangle covering both.
+    pub fn union(self, other: Self) -> Self {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        let right = self.right().max(other.right());
+        let bottom = self.bottom().max(other.bottom());
+        Self {
+            x,
+            y,
+            width: (right as i64 - x as i64) as usize,
+            height: (bottom as i64 - y as i64) as usize,
+        }
+    }
+
+    fn right(&self) -> i32 {
+        self.x + self.width as i32
+    }
+
+    fn bottom(&self) -> i32 {
+        self.y + self.height as i32
+    }
+}
+
+/// The chunks of one layer, keyed by their top-left tile.
+#[derive(Debug, Default)]
+pub(super) struct ChunkStore {
+    chunks: HashMap<(i32, i32), Chunk>,
+    bounds: Option<TileRect>,
+}
+
+#[derive(Debug)]
+struct Chunk {
+    width: usize,
+    height: usize,
+    gids: Vec<u32>,
+}
+
+impl ChunkStore {
+    /// Adds a chunk of `width * height` gids in row-major order. Empty chunks
+    /// are kept out of the bounds.
+    pub fn insert(&mut self, x: i32, y: i32, width: usize, height: usize, gids: Vec<u32>) {
+        debug_assert_eq!(gids.len(), width * height);
+        if width == 0 || height == 0 {
+            return;
+        }
+        let rect = TileRect {
+            x,
+            y,
+            width,
+            height,
+        };
+        self.bounds = Some(match self.bounds {
+            Some(bounds) => bounds.union(rect),
+            None => rect,
+        });
+        self.chunks.insert(
+            (x, y),
+            Chunk {
+                width,
+                height,
+                gids,
+            },
+        );
+    }
+
+    pub fn bounds(&self) -> Option<TileRect> {
+        self.bounds
+    }
+
+    /// Gids covering `rect` in row-major order; tiles outside every chunk
+    /// are empty.
+    pub fn rasterize(&self, rect: TileRect) -> Vec<u32> {
+        let mut gids = vec![0; rect.width * rect.height];
+        for (&(cx, cy), chunk) in &self.chunks {
+            for row in 0..chunk.height {
+                let y = cy + row as i32 - rect.y;
+                if y < 0 || y as usize >= rect.height {
+                    continue;
+                }
+                for col in 0..chunk.width {
+                    let x = cx + col as i32 - rect.x;
+                    if x < 0 || x as usize >= rect.width {
+                        continue;
+                    }
+                    gids[y as usize * rect.width + x as usize] =
+                        chunk.gids[row * chunk.width + col];
+                }
+            }
+        }
+        gids
+    }
+}
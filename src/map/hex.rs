@@ -0,0 +1,220 @@
+//! Staggered hexagonal grids in Tiled's offset coordinates; axial `(q, r)`
+//! coordinates are used for distance and line math.
+
+/// Which axis alternate lines of hexes are shifted along.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StaggerAxis {
+    /// Every other column is shifted down by half a tile (flat-topped hexes).
+    X,
+    /// Every other row is shifted right by half a tile (pointy-topped hexes).
+    Y,
+}
+
+/// Whether the odd or the even lines are the shifted ones.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StaggerIndex {
+    Odd,
+    Even,
+}
+
+/// Axial directions of the six neighbors.
+const AXIAL_DIRS: [(i32, i32); 6] = [(1, 0), (-1, 0), (0, -1), (1, -1), (0, 1), (-1, 1)];
+
+const ROW_SHIFTED_STEPS: [(i32, i32); 6] = [(1, 0), (-1, 0), (0, -1), (1, -1), (0, 1), (1, 1)];
+const ROW_UNSHIFTED_STEPS: [(i32, i32); 6] = [(1, 0), (-1, 0), (-1, -1), (0, -1), (-1, 1), (0, 1)];
+const COLUMN_SHIFTED_STEPS: [(i32, i32); 6] = [(0, 1), (0, -1), (-1, 0), (-1, 1), (1, 0), (1, 1)];
+const COLUMN_UNSHIFTED_STEPS: [(i32, i32); 6] =
+    [(0, 1), (0, -1), (-1, -1), (-1, 0), (1, -1), (1, 0)];
+
+/// Geometry of a Tiled hexagonal map.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HexLayout {
+    pub tile_width: f32,
+    pub tile_height: f32,
+    /// Length of the hex sides parallel to the stagger axis' cross axis,
+    /// Tiled's `hexsidelength`.
+    pub side_length: f32,
+    pub stagger_axis: StaggerAxis,
+    pub stagger_index: StaggerIndex,
+}
+
+impl HexLayout {
+    /// Offsets from `(x, y)` to its six neighbors. They depend on whether
+    /// the cell's row (or column) is a shifted one.
+    pub fn neighbor_offsets(&self, x: usize, y: usize) -> &'static [(i32, i32)] {
+        match self.stagger_axis {
+            StaggerAxis::Y if self.is_shifted(y as i32) => &ROW_SHIFTED_STEPS,
+            StaggerAxis::Y => &ROW_UNSHIFTED_STEPS,
+            StaggerAxis::X if self.is_shifted(x as i32) => &COLUMN_SHIFTED_STEPS,
+            StaggerAxis::X => &COLUMN_UNSHIFTED_STEPS,
+        }
+    }
+
+    pub fn to_axial(&self, x: i32, y: i32) -> (i32, i32) {
+        match self.stagger_axis {
+            StaggerAxis::Y => (x - self.shift_count(y), y),
+            StaggerAxis::X => (x, y - self.shift_count(x)),
+        }
+    }
+
+    pub fn from_axial(&self, q: i32, r: i32) -> (i32, i32) {
+        match self.stagger_axis {
+            StaggerAxis::Y => (q + self.shift_count(r), r),
+            StaggerAxis::X => (q, r + self.shift_count(q)),
+        }
+    }
+
+    /// Number of steps between two cells.
+    pub fn distance(&self, a: (usize, usize), b: (usize, usize)) -> usize {
+        let (aq, ar) = self.to_axial(a.0 as i32, a.1 as i32);
+        let (bq, br) = self.to_axial(b.0 as i32, b.1 as i32);
+        let (dq, dr) = (aq - bq, ar - br);
+        ((dq.abs() + dr.abs() + (dq + dr).abs()) / 2) as usize
+    }
+
+    /// World position of the cell's center. Takes Tiled tile coordinates,
+    /// which may be negative on infinite maps.
+    pub fn cell_center(&self, x: i32, y: i32) -> (f32, f32) {
+        let (half_w, half_h) = (self.tile_width / 2.0, self.tile_height / 2.0);
+        match self.stagger_axis {
+            StaggerAxis::Y => {
+                let shift = if self.is_shifted(y) { half_w } else { 0.0 };
+                (
+                    x as f32 * self.tile_width + half_w + shift,
+                    y as f32 * self.line_spacing() + half_h,
+                )
+            }
+            StaggerAxis::X => {
+                let shift = if self.is_shifted(x) { half_h } else { 0.0 };
+                (
+                    x as f32 * self.line_spacing() + half_w,
+                    y as f32 * self.tile_height + half_h + shift,
+                )
+            }
+        }
+    }
+
+    /// Cell whose center is nearest to the world point. May lie outside the
+    /// map, including at negative coordinates.
+    pub fn cell_at(&self, wx: f32, wy: f32) -> (i32, i32) {
+        let (step_x, step_y) = match self.stagger_axis {
+            StaggerAxis::Y => (self.tile_width, self.line_spacing()),
+            StaggerAxis::X => (self.line_spacing(), self.tile_height),
+        };
+        let guess_x = (wx / step_x).floor() as i32;
+        let guess_y = (wy / step_y).floor() as i32;
+
+        let mut best = (f32::INFINITY, (guess_x, guess_y));
+        for y in guess_y - 1..=guess_y + 1 {
+            for x in guess_x - 1..=guess_x + 1 {
+                let (cx, cy) = self.cell_center(x, y);
+                let d = (cx - wx).powi(2) + (cy - wy).powi(2);
+                if d < best.0 {
+                    best = (d, (x, y));
+                }
+            }
+        }
+        best.1
+    }
+
+    /// Corners of a hex relative to its center, clockwise from the top (or
+    /// left, when columns are staggered).
+    pub fn corner_offsets(&self) -> [(f32, f32); 6] {
+        let (half_w, half_h) = (self.tile_width / 2.0, self.tile_height / 2.0);
+        let half_side = self.side_length / 2.0;
+        match self.stagger_axis {
+            StaggerAxis::Y => [
+                (0.0, -half_h),
+                (half_w, -half_side),
+                (half_w, half_side),
+                (0.0, half_h),
+                (-half_w, half_side),
+                (-half_w, -half_side),
+            ],
+            StaggerAxis::X => [
+                (-half_w, 0.0),
+                (-half_side, -half_h),
+                (half_side, -half_h),
+                (half_w, 0.0),
+                (half_side, half_h),
+                (-half_side, half_h),
+            ],
+        }
+    }
+
+    /// Distance between the centers of the two closest neighbors.
+    pub fn neighbor_spacing(&self) -> f32 {
+        let (ax, ay) = self.cell_center(0, 0);
+        AXIAL_DIRS
+            .iter()
+            .map(|&(dq, dr)| {
+                let (q, r) = self.to_axial(0, 0);
+                let (nx, ny) = self.from_axial(q + dq, r + dr);
+                let (bx, by) = self.cell_center(nx, ny);
+                ((bx - ax).powi(2) + (by - ay).powi(2)).sqrt()
+            })
+            .fold(f32::INFINITY, f32::min)
+    }
+
+    /// Cells crossed by the straight line between two cell centers, both
+    /// ends included, one cell per step.
+    pub fn line(&self, a: (usize, usize), b: (usize, usize)) -> Vec<(i32, i32)> {
+        let n = self.distance(a, b);
+        let (aq, ar) = self.to_axial(a.0 as i32, a.1 as i32);
+        let (bq, br) = self.to_axial(b.0 as i32, b.1 as i32);
+        if n == 0 {
+            return vec![(a.0 as i32, a.1 as i32)];
+        }
+
+        // The nudge keeps a line running exactly along cell edges from
+        // rounding differently at every step.
+        let (aq, ar) = (aq as f32 + 1e-4, ar as f32 + 1e-4);
+        let (bq, br) = (bq as f32 + 1e-4, br as f32 + 1e-4);
+        (0..=n)
+            .map(|i| {
+                let t = i as f32 / n as f32;
+                let (q, r) = axial_round(aq + (bq - aq) * t, ar + (br - ar) * t);
+                self.from_axial(q, r)
+            })
+            .collect()
+    }
+
+    fn is_shifted(&self, line: i32) -> bool {
+        let odd = line.rem_euclid(2) == 1;
+        match self.stagger_index {
+            StaggerIndex::Odd => odd,
+            StaggerIndex::Even => !odd,
+        }
+    }
+
+    /// Shifted lines before `line`, counting from line 0: how far the axial
+    /// coordinate drifts from the offset one.
+    fn shift_count(&self, line: i32) -> i32 {
+        match self.stagger_index {
+            StaggerIndex::Odd => line.div_euclid(2),
+            StaggerIndex::Even => (line + 1).div_euclid(2),
+        }
+    }
+
+    /// Distance between consecutive rows (stagger axis Y) or columns
+    /// (stagger axis X).
+    fn line_spacing(&self) -> f32 {
+        match self.stagger_axis {
+            StaggerAxis::Y => (self.tile_height + self.side_length) / 2.0,
+            StaggerAxis::X => (self.tile_width + self.side_length) / 2.0,
+        }
+    }
+}
+
+/// Rounds fractional axial coordinates to the containing hex.
+fn axial_round(q: f32, r: f32) -> (i32, i32) {
+    let s = -q - r;
+    let (mut rq, mut rr, rs) = (q.round(), r.round(), s.round());
+    let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+    if dq > dr && dq > ds {
+        rq = -rr - rs;
+    } else if dr > ds {
+        rr = -rq - rs;
+    }
+    (rq as i32, rr as i32)
+}
@@ -0,0 +1,101 @@
+//! Tile layer data in the encodings Tiled can save: plain gid arrays, or
+//! base64 strings of little-endian gids, optionally zlib, gzip or zstd
+//! compressed.
+
+use std::io::Read;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use flate2::read::{GzDecoder, ZlibDecoder};
+use serde::Deserialize;
+
+/// A layer's (or chunk's) `data` field as it appears in the JSON.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub(super) enum LayerData {
+    Gids(Vec<u32>),
+    Encoded(String),
+}
+
+impl Default for LayerData {
+    fn default() -> Self {
+        Self::Gids(Vec::new())
+    }
+}
+
+pub(super) enum DecodeError {
+    /// An `encoding` or `compression` this loader does not know.
+    Unsupported(String),
+    Invalid(String),
+}
+
+/// Gids of `data`, decoded according to the layer's `encoding` and
+/// `compression` fields.
+pub(super) fn decode_gids(
+    data: &LayerData,
+    encoding: Option<&str>,
+    compression: Option<&str>,
+) -> Result<Vec<u32>, DecodeError> {
+    let text = match encoding {
+        None | Some("csv") => {
+            return match data {
+                LayerData::Gids(gids) => Ok(gids.clone()),
+                LayerData::Encoded(_) => Err(DecodeError::Invalid(
+                    "string data without base64 encoding".to_owned(),
+                )),
+            };
+        }
+        Some("base64") => match data {
+            LayerData::Encoded(text) => text,
+            LayerData::Gids(_) => {
+                return Err(DecodeError::Invalid(
+                    "base64 data must be a string".to_owned(),
+                ));
+            }
+        },
+        Some(other) => {
+            return Err(DecodeError::Unsupported(format!("encoding '{other}'")));
+        }
+    };
+
+    let bytes = STANDARD
+        .decode(text.trim())
+        .map_err(|err| DecodeError::Invalid(format!("bad base64: {err}")))?;
+    let bytes = decompress(&bytes, compression)?;
+    if bytes.len() % 4 != 0 {
+        return Err(DecodeError::Invalid(format!(
+            "{} bytes is not a whole number of gids",
+            bytes.len()
+        )));
+    }
+
+    Ok(bytes
+        .chunks_exact(4)
+        .map(|gid| u32::from_le_bytes([gid[0], gid[1], gid[2], gid[3]]))
+        .collect())
+}
+
+fn decompress(bytes: &[u8], compression: Option<&str>) -> Result<Vec<u8>, DecodeError> {
+    let mut out = Vec::new();
+    let result = match compression {
+        None | Some("") => return Ok(bytes.to_vec()),
+        Some("zlib") => ZlibDecoder::new(bytes).read_to_end(&mut out),
+        Some("gzip") => GzDecoder::new(bytes).read_to_end(&mut out),
+        Some("zstd") => {
+            let mut source = bytes;
+            let mut decoder = ruzstd::decoding::StreamingDecoder::new(&mut source)
+                .map_err(|err| DecodeError::Invalid(format!("bad zstd frame: {err}")))?;
+            decoder.read_to_end(&mut out)
+        }
+        Some(other) => {
+            return Err(DecodeError::Unsupported(format!("compression '{other}'")));
+        }
+    };
+    result.map_err(|err| {
+        DecodeError::Invalid(format!(
+            "bad {} data: {err}",
+            compression.unwrap_or_default()
+        ))
+    })?;
+    Ok(out)
+}
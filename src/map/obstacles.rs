@@ -0,0 +1,229 @@
+//! Collision objects from object layers, in world space. Ellipses and rotated
+//! rectangles become polygons.
+
+use super::{BLOCKS_SIGHT_PROPERTY, TiledJsonLayer, TiledJsonObject, WorldRect, property_bool};
+
+const ELLIPSE_SEGMENTS: usize = 16;
+
+/// Overlap, as a fraction of the cell's area, below which a cell counts as
+/// merely touched.
+const OVERLAP_EPSILON: f32 = 1e-4;
+
+/// A collision object in world space.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Obstacle {
+    /// Axis-aligned box as its `(min, max)` corners.
+    Rect(WorldRect),
+    /// Simple polygon, in either winding.
+    Polygon(Vec<(f32, f32)>),
+}
+
+impl Obstacle {
+    /// Bounding box as `(min, max)` corners.
+    pub fn bounds(&self) -> WorldRect {
+        match self {
+            Self::Rect(rect) => *rect,
+            Self::Polygon(points) => points.iter().fold(
+                (
+                    (f32::INFINITY, f32::INFINITY),
+                    (f32::NEG_INFINITY, f32::NEG_INFINITY),
+                ),
+                |(min, max), &(x, y)| ((min.0.min(x), min.1.min(y)), (max.0.max(x), max.1.max(y))),
+            ),
+        }
+    }
+
+    /// Whether the point lies strictly inside.
+    pub fn contains(&self, (px, py): (f32, f32)) -> bool {
+        match self {
+            Self::Rect((min, max)) => px > min.0 && px < max.0 && py > min.1 && py < max.1,
+            Self::Polygon(points) => {
+                let mut inside = false;
+                for (a, b) in edges(points) {
+                    if (a.1 > py) != (b.1 > py) {
+                        let x = a.0 + (py - a.1) / (b.1 - a.1) * (b.0 - a.0);
+                        if px < x {
+                            inside = !inside;
+                        }
+                    }
+                }
+                inside
+            }
+        }
+    }
+
+    /// Nearest point on the outline.
+    pub fn closest_boundary_point(&self, point: (f32, f32)) -> (f32, f32) {
+        match self {
+            Self::Rect((min, max)) => {
+                let clamped = (point.0.clamp(min.0, max.0), point.1.clamp(min.1, max.1));
+                if clamped != point {
+                    return clamped;
+                }
+                // Inside: leave through the nearest side.
+                let sides = [
+                    (point.0 - min.0, (min.0, point.1)),
+                    (max.0 - point.0, (max.0, point.1)),
+                    (point.1 - min.1, (point.0, min.1)),
+                    (max.1 - point.1, (point.0, max.1)),
+                ];
+                sides
+                    .into_iter()
+                    .min_by(|a, b| a.0.total_cmp(&b.0))
+                    .map(|(_, side)| side)
+                    .unwrap_or(point)
+            }
+            Self::Polygon(points) => edges(points)
+                .map(|(a, b)| closest_on_segment(a, b, point))
+                .min_by(|a, b| distance_sq(*a, point).total_cmp(&distance_sq(*b, point)))
+                .unwrap_or(point),
+        }
+    }
+
+    /// Whether the obstacle covers part of the convex polygon `cell`. Shapes
+    /// that only touch along an edge or at a corner do not overlap.
+    pub fn overlaps_convex(&self, cell: &[(f32, f32)]) -> bool {
+        let (min, max) = self.bounds();
+        let outside = cell.iter().all(|p| p.0 <= min.0)
+            || cell.iter().all(|p| p.0 >= max.0)
+            || cell.iter().all(|p| p.1 <= min.1)
+            || cell.iter().all(|p| p.1 >= max.1);
+        if outside {
+            return false;
+        }
+
+        let outline = match self {
+            Self::Rect((min, max)) => vec![*min, (max.0, min.1), *max, (min.0, max.1)],
+            Self::Polygon(points) => points.clone(),
+        };
+        let overlap = area(&clip_to_convex(outline, cell));
+        overlap > OVERLAP_EPSILON * area(cell)
+    }
+
+    /// World distance from the point to the obstacle; zero inside it.
+    pub fn distance_to(&self, point: (f32, f32)) -> f32 {
+        if self.contains(point) {
+            return 0.0;
+        }
+        distance_sq(self.closest_boundary_point(point), point).sqrt()
+    }
+}
+
+/// The layer's visible area objects as obstacles, shifted by the layer
+/// offset, each with whether it blocks sight: its `blocks_sight` property,
+/// true when unset. Points and polylines enclose nothing and are skipped.
+pub(super) fn layer_obstacles(layer: &TiledJsonLayer) -> Vec<(Obstacle, bool)> {
+    let offset = (layer.offsetx.unwrap_or(0.0), layer.offsety.unwrap_or(0.0));
+    layer
+        .objects
+        .iter()
+        .filter(|object| object.visible.unwrap_or(true))
+        .filter_map(|object| {
+            let blocks_sight =
+                property_bool(&object.properties, BLOCKS_SIGHT_PROPERTY).unwrap_or(true);
+            Some((object_obstacle(object, offset)?, blocks_sight))
+        })
+        .collect()
+}
+
+/// Tiled positions ellipses and rectangles by their top-left corner and
+/// polygons by their first vertex, and rotates all of them clockwise about
+/// that position.
+fn object_obstacle(object: &TiledJsonObject, offset: (f32, f32)) -> Option<Obstacle> {
+    if object.point || object.polyline.is_some() {
+        return None;
+    }
+    let origin = (
+        object.x.unwrap_or(0.0) + offset.0,
+        object.y.unwrap_or(0.0) + offset.1,
+    );
+    let (w, h) = (object.width, object.height);
+
+    let local: Vec<(f32, f32)> = if let Some(points) = &object.polygon {
+        if points.len() < 3 {
+            return None;
+        }
+        points.iter().map(|point| (point.x, point.y)).collect()
+    } else if w <= 0.0 || h <= 0.0 {
+        return None;
+    } else if object.ellipse {
+        (0..ELLIPSE_SEGMENTS)
+            .map(|i| {
+                let angle = i as f32 / ELLIPSE_SEGMENTS as f32 * std::f32::consts::TAU;
+                (w / 2.0 * (1.0 + angle.cos()), h / 2.0 * (1.0 + angle.sin()))
+            })
+            .collect()
+    } else if object.rotation == 0.0 {
+        return Some(Obstacle::Rect((origin, (origin.0 + w, origin.1 + h))));
+    } else {
+        vec![(0.0, 0.0), (w, 0.0), (w, h), (0.0, h)]
+    };
+
+    let (sin, cos) = object.rotation.to_radians().sin_cos();
+    Some(Obstacle::Polygon(
+        local
+            .into_iter()
+            .map(|(x, y)| (origin.0 + x * cos - y * sin, origin.1 + x * sin + y * cos))
+            .collect(),
+    ))
+}
+
+fn edges(points: &[(f32, f32)]) -> impl Iterator<Item = ((f32, f32), (f32, f32))> + '_ {
+    points
+        .iter()
+        .copied()
+        .zip(points.iter().copied().cycle().skip(1))
+}
+
+/// Sutherland-Hodgman: the part of `subject` inside the convex `clip`. A
+/// concave subject may come out with edges doubling back along the clip
+/// boundary, which leaves its area right.
+fn clip_to_convex(subject: Vec<(f32, f32)>, clip: &[(f32, f32)]) -> Vec<(f32, f32)> {
+    let winding = signed_area(clip).signum();
+    let mut output = subject;
+    for (a, b) in edges(clip) {
+        if output.is_empty() {
+            break;
+        }
+        let side =
+            |p: (f32, f32)| winding * ((b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0));
+        let input = std::mem::take(&mut output);
+        for (p, q) in edges(&input) {
+            let (sp, sq) = (side(p), side(q));
+            if sp >= 0.0 {
+                output.push(p);
+            }
+            if (sp >= 0.0) != (sq >= 0.0) {
+                let t = sp / (sp - sq);
+                output.push((p.0 + (q.0 - p.0) * t, p.1 + (q.1 - p.1) * t));
+            }
+        }
+    }
+    output
+}
+
+fn signed_area(points: &[(f32, f32)]) -> f32 {
+    edges(points)
+        .map(|(a, b)| a.0 * b.1 - b.0 * a.1)
+        .sum::<f32>()
+        / 2.0
+}
+
+fn area(points: &[(f32, f32)]) -> f32 {
+    signed_area(points).abs()
+}
+
+fn closest_on_segment(a: (f32, f32), b: (f32, f32), p: (f32, f32)) -> (f32, f32) {
+    let ab = (b.0 - a.0, b.1 - a.1);
+    let len_sq = ab.0 * ab.0 + ab.1 * ab.1;
+    if len_sq == 0.0 {
+        return a;
+    }
+    let t = (((p.0 - a.0) * ab.0 + (p.1 - a.1) * ab.1) / len_sq).clamp(0.0, 1.0);
+    (a.0 + ab.0 * t, a.1 + ab.1 * t)
+}
+
+fn distance_sq(a: (f32, f32), b: (f32, f32)) -> f32 {
+    let d = (a.0 - b.0, a.1 - b.1);
+    d.0 * d.0 + d.1 * d.1
+}
@@ -0,0 +1,190 @@
+//! External tilesets and wall-layer tile collision. A `solid` property wins,
+//! then collision shapes; other tiles are open only if their tileset
+//! describes collision for some tile.
+
+use std::path::Path;
+
+use super::{
+    CellRect, RuntimeMapAdapterError, SOLID_PROPERTY, TILED_GID_MASK, TiledJsonMap,
+    TiledJsonObject, TiledJsonTile, TiledJsonTileset, property_bool, tmx,
+};
+
+const FLIPPED_HORIZONTALLY: u32 = 0x8000_0000;
+const FLIPPED_VERTICALLY: u32 = 0x4000_0000;
+const FLIPPED_DIAGONALLY: u32 = 0x2000_0000;
+
+/// Replaces every tileset that points at a `source` file with the file's
+/// contents. Sources are relative to the map; `.tsx` files are XML, anything
+/// else JSON.
+pub(super) fn load_external_tilesets(
+    map: &mut TiledJsonMap,
+    map_path: &Path,
+) -> Result<(), RuntimeMapAdapterError> {
+    let dir = map_path.parent().unwrap_or(Path::new(""));
+    for tileset in &mut map.tilesets {
+        let Some(source) = tileset.source.take() else {
+            continue;
+        };
+        let path = dir.join(source);
+        let loaded = if path.extension().is_some_and(|ext| ext == "tsx") {
+            tmx::read_tsx_tileset(&path)?
+        } else {
+            read_json_tileset(&path)?
+        };
+
+        tileset.tilewidth = loaded.tilewidth;
+        tileset.tileheight = loaded.tileheight;
+        tileset.tiles = loaded.tiles;
+    }
+    Ok(())
+}
+
+fn read_json_tileset(path: &Path) -> Result<TiledJsonTileset, RuntimeMapAdapterError> {
+    let text = std::fs::read_to_string(path).map_err(|source| RuntimeMapAdapterError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    serde_json::from_str(&text).map_err(|source| RuntimeMapAdapterError::Json {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// What a wall-layer tile does to the cell it is drawn on.
+#[derive(Clone, Debug)]
+pub(super) enum TileCollision {
+    Open,
+    Solid,
+    Shapes(Vec<CellRect>),
+}
+
+/// Collision of the tile `gid` on a `cell`-sized cell. Collision shapes are
+/// scaled from the tileset's tile size to the cell and follow the gid's flip
+/// flags.
+pub(super) fn tile_collision(map: &TiledJsonMap, gid: u32, cell: (f32, f32)) -> TileCollision {
+    if gid & TILED_GID_MASK == 0 {
+        return TileCollision::Open;
+    }
+    let Some((tileset, tile)) = tile_definition(map, gid) else {
+        return TileCollision::Solid;
+    };
+
+    let shapes = tile
+        .map(|tile| tile_shapes(map, tileset, tile, gid, cell))
+        .unwrap_or_default();
+    let solid = tile.and_then(|tile| property_bool(&tile.properties, SOLID_PROPERTY));
+    match solid {
+        Some(false) => TileCollision::Open,
+        _ if !shapes.is_empty() => TileCollision::Shapes(shapes),
+        Some(true) => TileCollision::Solid,
+        None if describes_collision(tileset) => TileCollision::Open,
+        None => TileCollision::Solid,
+    }
+}
+
+/// The tileset holding `gid`, and the tile's own definition if it has one.
+pub(super) fn tile_definition(
+    map: &TiledJsonMap,
+    gid: u32,
+) -> Option<(&TiledJsonTileset, Option<&TiledJsonTile>)> {
+    let gid = gid & TILED_GID_MASK;
+    let tileset = map
+        .tilesets
+        .iter()
+        .filter(|tileset| tileset.firstgid <= gid)
+        .max_by_key(|tileset| tileset.firstgid)?;
+    let local_id = gid - tileset.firstgid;
+    Some((
+        tileset,
+        tileset.tiles.iter().find(|tile| tile.id == local_id),
+    ))
+}
+
+fn describes_collision(tileset: &TiledJsonTileset) -> bool {
+    tileset.tiles.iter().any(|tile| {
+        property_bool(&tile.properties, SOLID_PROPERTY).is_some()
+            || tile
+                .objectgroup
+                .as_ref()
+                .is_some_and(|group| !group.objects.is_empty())
+    })
+}
+
+fn tile_shapes(
+    map: &TiledJsonMap,
+    tileset: &TiledJsonTileset,
+    tile: &TiledJsonTile,
+    gid: u32,
+    cell: (f32, f32),
+) -> Vec<CellRect> {
+    let Some(group) = &tile.objectgroup else {
+        return Vec::new();
+    };
+    let scale = (
+        cell.0 / tileset.tilewidth.unwrap_or(map.tilewidth) as f32,
+        cell.1 / tileset.tileheight.unwrap_or(map.tileheight) as f32,
+    );
+
+    group
+        .objects
+        .iter()
+        .filter(|object| object.visible.unwrap_or(true))
+        .filter_map(object_bounds)
+        .map(|rect| {
+            let rect = CellRect {
+                x: rect.x * scale.0,
+                y: rect.y * scale.1,
+                width: rect.width * scale.0,
+                height: rect.height * scale.1,
+            };
+            flip(rect, gid, cell)
+        })
+        .collect()
+}
+
+/// Bounding box of an area object; points and polylines have no area.
+fn object_bounds(object: &TiledJsonObject) -> Option<CellRect> {
+    let (x, y) = (object.x.unwrap_or(0.0), object.y.unwrap_or(0.0));
+    if object.point || object.polyline.is_some() {
+        return None;
+    }
+    if let Some(points) = &object.polygon {
+        let min_x = points.iter().map(|p| p.x).fold(f32::INFINITY, f32::min);
+        let min_y = points.iter().map(|p| p.y).fold(f32::INFINITY, f32::min);
+        let max_x = points.iter().map(|p| p.x).fold(f32::NEG_INFINITY, f32::max);
+        let max_y = points.iter().map(|p| p.y).fold(f32::NEG_INFINITY, f32::max);
+        return (max_x > min_x && max_y > min_y).then_some(CellRect {
+            x: x + min_x,
+            y: y + min_y,
+            width: max_x - min_x,
+            height: max_y - min_y,
+        });
+    }
+    (object.width > 0.0 && object.height > 0.0).then_some(CellRect {
+        x,
+        y,
+        width: object.width,
+        height: object.height,
+    })
+}
+
+/// Applies Tiled's flip flags the way Tiled renders them: the diagonal flip
+/// first, then the horizontal and vertical ones.
+fn flip(mut rect: CellRect, gid: u32, cell: (f32, f32)) -> CellRect {
+    let (w, h) = cell;
+    if gid & FLIPPED_DIAGONALLY != 0 {
+        rect = CellRect {
+            x: rect.y * w / h,
+            y: rect.x * h / w,
+            width: rect.height * w / h,
+            height: rect.width * h / w,
+        };
+    }
+    if gid & FLIPPED_HORIZONTALLY != 0 {
+        rect.x = w - rect.x - rect.width;
+    }
+    if gid & FLIPPED_VERTICALLY != 0 {
+        rect.y = h - rect.y - rect.height;
+    }
+    rect
+}
@@ -0,0 +1,313 @@
+//! Tiled's XML map format. TMX files are read into the same model the JSON
+//! loader deserializes, so everything downstream of parsing is shared.
+
+use std::path::Path;
+use std::str::FromStr;
+
+use roxmltree::{Document, Node};
+use serde::de::IgnoredAny;
+
+use super::layer_data::LayerData;
+use super::{
+    RuntimeMapAdapterError, TiledJsonChunk, TiledJsonLayer, TiledJsonMap, TiledJsonObject,
+    TiledJsonObjectGroup, TiledJsonPoint, TiledJsonProperty, TiledJsonTile, TiledJsonTileset,
+};
+
+pub(super) fn read_tmx_map(path: &Path) -> Result<TiledJsonMap, RuntimeMapAdapterError> {
+    read_xml(path, parse_map)
+}
+
+/// Reads an external `.tsx` tileset.
+pub(super) fn read_tsx_tileset(path: &Path) -> Result<TiledJsonTileset, RuntimeMapAdapterError> {
+    read_xml(path, |node| {
+        if !node.has_tag_name("tileset") {
+            return Err(format!(
+                "root element is <{}>, not <tileset>",
+                node.tag_name().name()
+            ));
+        }
+        parse_tileset(node)
+    })
+}
+
+fn read_xml<T>(
+    path: &Path,
+    parse: impl FnOnce(Node) -> Result<T, String>,
+) -> Result<T, RuntimeMapAdapterError> {
+    let text = std::fs::read_to_string(path).map_err(|source| RuntimeMapAdapterError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let doc = Document::parse(&text).map_err(|source| RuntimeMapAdapterError::Xml {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    parse(doc.root_element()).map_err(|detail| RuntimeMapAdapterError::MalformedTmx {
+        path: path.to_path_buf(),
+        detail,
+    })
+}
+
+fn parse_map(node: Node) -> Result<TiledJsonMap, String> {
+    if !node.has_tag_name("map") {
+        return Err(format!(
+            "root element is <{}>, not <map>",
+            node.tag_name().name()
+        ));
+    }
+
+    let mut layers = Vec::new();
+    let mut tilesets = Vec::new();
+    for child in node.children().filter(Node::is_element) {
+        match child.tag_name().name() {
+            "layer" => layers.push(parse_tile_layer(child)?),
+            "objectgroup" => layers.push(parse_object_group(child)?),
+            "tileset" => tilesets.push(parse_tileset(child)?),
+            _ => {}
+        }
+    }
+
+    Ok(TiledJsonMap {
+        width: required(node, "width")?,
+        height: required(node, "height")?,
+        tilewidth: required(node, "tilewidth")?,
+        tileheight: required(node, "tileheight")?,
+        infinite: attr::<u8>(node, "infinite")?.unwrap_or(0) != 0,
+        orientation: node.attribute("orientation").map(str::to_owned),
+        hexsidelength: attr(node, "hexsidelength")?,
+        staggeraxis: node.attribute("staggeraxis").map(str::to_owned),
+        staggerindex: node.attribute("staggerindex").map(str::to_owned),
+        layers,
+        tilesets,
+    })
+}
+
+fn parse_tile_layer(node: Node) -> Result<TiledJsonLayer, String> {
+    let mut layer = layer_header(node, "tilelayer")?;
+    layer.width = attr(node, "width")?.unwrap_or(0);
+    layer.height = attr(node, "height")?.unwrap_or(0);
+
+    let Some(data) = child(node, "data") else {
+        return Ok(layer);
+    };
+    // CSV and `<tile>` elements both become plain gid arrays; only base64
+    // is left for the shared decoder.
+    let encoding = data.attribute("encoding");
+    if encoding == Some("base64") {
+        layer.encoding = Some("base64".to_owned());
+        layer.compression = data.attribute("compression").map(str::to_owned);
+    }
+
+    let chunks: Vec<Node> = data
+        .children()
+        .filter(|chunk| chunk.has_tag_name("chunk"))
+        .collect();
+    if chunks.is_empty() {
+        layer.data = data_contents(data, encoding, &layer.name)?;
+    }
+    for chunk in chunks {
+        layer.chunks.push(TiledJsonChunk {
+            x: required(chunk, "x")?,
+            y: required(chunk, "y")?,
+            width: required(chunk, "width")?,
+            height: required(chunk, "height")?,
+            data: data_contents(chunk, encoding, &layer.name)?,
+        });
+    }
+    Ok(layer)
+}
+
+/// The gids held by a `<data>` or `<chunk>` element.
+fn data_contents(
+    node: Node,
+    encoding: Option<&str>,
+    layer_name: &str,
+) -> Result<LayerData, String> {
+    let text = node.text().unwrap_or_default().trim();
+    match encoding {
+        Some("base64") => Ok(LayerData::Encoded(text.to_owned())),
+        Some("csv") => text
+            .split(',')
+            .map(str::trim)
+            .filter(|gid| !gid.is_empty())
+            .map(|gid| {
+                gid.parse()
+                    .map_err(|_| format!("layer '{layer_name}' has bad csv gid '{gid}'"))
+            })
+            .collect::<Result<_, _>>()
+            .map(LayerData::Gids),
+        None => node
+            .children()
+            .filter(|tile| tile.has_tag_name("tile"))
+            .map(|tile| attr(tile, "gid").map(Option::unwrap_or_default))
+            .collect::<Result<_, _>>()
+            .map(LayerData::Gids),
+        Some(other) => Err(format!(
+            "layer '{layer_name}' uses unsupported encoding '{other}'"
+        )),
+    }
+}
+
+fn parse_object_group(node: Node) -> Result<TiledJsonLayer, String> {
+    let mut layer = layer_header(node, "objectgroup")?;
+    layer.objects = parse_objects(node)?;
+    Ok(layer)
+}
+
+fn parse_objects(node: Node) -> Result<Vec<TiledJsonObject>, String> {
+    node.children()
+        .filter(|object| object.has_tag_name("object"))
+        .map(parse_object)
+        .collect()
+}
+
+fn parse_object(object: Node) -> Result<TiledJsonObject, String> {
+    let shape = |name| child(object, name).is_some();
+    let polygon = child(object, "polygon")
+        .map(|polygon| parse_points(polygon.attribute("points").unwrap_or_default()))
+        .transpose()?;
+
+    Ok(TiledJsonObject {
+        // Tiled 1.9 renamed the object `type` attribute to `class`.
+        kind: object
+            .attribute("type")
+            .or_else(|| object.attribute("class"))
+            .map(str::to_owned),
+        x: attr(object, "x")?,
+        y: attr(object, "y")?,
+        width: attr(object, "width")?.unwrap_or(0.0),
+        height: attr(object, "height")?.unwrap_or(0.0),
+        point: shape("point"),
+        ellipse: shape("ellipse"),
+        polygon,
+        polyline: shape("polyline").then_some(IgnoredAny),
+        rotation: attr(object, "rotation")?.unwrap_or(0.0),
+        visible: visibility(object)?,
+        properties: parse_properties(object)?,
+    })
+}
+
+/// Parses a `points` attribute: space-separated `x,y` pairs.
+fn parse_points(points: &str) -> Result<Vec<TiledJsonPoint>, String> {
+    points
+        .split_whitespace()
+        .map(|pair| {
+            let (x, y) = pair
+                .split_once(',')
+                .ok_or_else(|| format!("bad polygon point '{pair}'"))?;
+            match (x.parse(), y.parse()) {
+                (Ok(x), Ok(y)) => Ok(TiledJsonPoint { x, y }),
+                _ => Err(format!("bad polygon point '{pair}'")),
+            }
+        })
+        .collect()
+}
+
+/// Attributes every layer kind shares.
+fn layer_header(node: Node, kind: &str) -> Result<TiledJsonLayer, String> {
+    Ok(TiledJsonLayer {
+        name: node.attribute("name").unwrap_or_default().to_owned(),
+        kind: kind.to_owned(),
+        visible: visibility(node)?,
+        offsetx: attr(node, "offsetx")?,
+        offsety: attr(node, "offsety")?,
+        width: 0,
+        height: 0,
+        encoding: None,
+        compression: None,
+        data: LayerData::default(),
+        chunks: Vec::new(),
+        objects: Vec::new(),
+        properties: parse_properties(node)?,
+    })
+}
+
+fn parse_tileset(node: Node) -> Result<TiledJsonTileset, String> {
+    let tiles = node
+        .children()
+        .filter(|tile| tile.has_tag_name("tile"))
+        .map(|tile| {
+            let objectgroup = child(tile, "objectgroup")
+                .map(|group| {
+                    Ok::<_, String>(TiledJsonObjectGroup {
+                        objects: parse_objects(group)?,
+                    })
+                })
+                .transpose()?;
+            Ok(TiledJsonTile {
+                id: required(tile, "id")?,
+                properties: parse_properties(tile)?,
+                objectgroup,
+            })
+        })
+        .collect::<Result<_, String>>()?;
+
+    // External tilesets leave `firstgid` to the map that uses them.
+    Ok(TiledJsonTileset {
+        firstgid: attr(node, "firstgid")?.unwrap_or(0),
+        source: node.attribute("source").map(str::to_owned),
+        tilewidth: attr(node, "tilewidth")?,
+        tileheight: attr(node, "tileheight")?,
+        tiles,
+    })
+}
+
+/// The element's `<properties>`, with values typed the way Tiled's JSON
+/// export types them.
+fn parse_properties(node: Node) -> Result<Vec<TiledJsonProperty>, String> {
+    let Some(properties) = child(node, "properties") else {
+        return Ok(Vec::new());
+    };
+
+    properties
+        .children()
+        .filter(|property| property.has_tag_name("property"))
+        .map(|property| {
+            let name = property.attribute("name").unwrap_or_default().to_owned();
+            // Multi-line strings are stored as text rather than an attribute.
+            let raw = property
+                .attribute("value")
+                .or_else(|| property.text())
+                .unwrap_or_default();
+            let value = match property.attribute("type") {
+                Some("int") | Some("float") | Some("object") => raw
+                    .parse::<f64>()
+                    .ok()
+                    .and_then(serde_json::Number::from_f64)
+                    .map(serde_json::Value::Number)
+                    .ok_or_else(|| format!("property '{name}' is not a number: '{raw}'"))?,
+                Some("bool") => serde_json::Value::Bool(raw == "true"),
+                _ => serde_json::Value::String(raw.to_owned()),
+            };
+            Ok(TiledJsonProperty { name, value })
+        })
+        .collect()
+}
+
+fn visibility(node: Node) -> Result<Option<bool>, String> {
+    Ok(attr::<u8>(node, "visible")?.map(|visible| visible != 0))
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| child.has_tag_name(name))
+}
+
+fn attr<T: FromStr>(node: Node, name: &str) -> Result<Option<T>, String> {
+    node.attribute(name)
+        .map(|value| {
+            value.parse().map_err(|_| {
+                format!(
+                    "<{}> attribute {} has bad value '{}'",
+                    node.tag_name().name(),
+                    name,
+                    value
+                )
+            })
+        })
+        .transpose()
+}
+
+fn required<T: FromStr>(node: Node, name: &str) -> Result<T, String> {
+    attr(node, name)?
+        .ok_or_else(|| format!("<{}> is missing attribute {}", node.tag_name().name(), name))
+}
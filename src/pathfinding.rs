@@ -1,14 +1,33 @@
 use std::cmp::Ordering;
-use std::collections::BinaryHeap;
 
-use crate::map::{RuntimeMapAdapter, blocked_for_agent, is_wall, map_height, map_width};
+use crate::map::{HexLayout, RuntimeMapAdapter, runtime_map};
+use crate::visibility::los_grid_for_radius;
+
+mod bidirectional;
+mod dstar_lite;
+mod flow_field;
+mod hpa;
+mod jps;
+mod landmarks;
+mod search_space;
+mod theta_star;
+mod time_sliced;
+mod workspace;
+
+pub use dstar_lite::DStarLite;
+pub use flow_field::FlowField;
+pub use hpa::HpaGraph;
+pub use landmarks::{LandmarkError, LandmarkTable};
+pub use search_space::{GridSpace, SearchSpace, astar_over_space};
+pub use time_sliced::{PathSearch, SearchStatus};
+pub use workspace::PathfinderWorkspace;
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 struct Node {
     f: i32,
     g: i32,
-    x: usize,
-    y: usize,
+    /// Dense node index; `y * width + x` on grids.
+    idx: usize,
 }
 
 impl Ord for Node {
@@ -17,8 +36,7 @@ impl Ord for Node {
             .f
             .cmp(&self.f)
             .then_with(|| other.g.cmp(&self.g))
-            .then_with(|| other.y.cmp(&self.y))
-            .then_with(|| other.x.cmp(&self.x))
+            .then_with(|| other.idx.cmp(&self.idx))
     }
 }
 
@@ -28,148 +46,836 @@ impl PartialOrd for Node {
     }
 }
 
+pub const STRAIGHT_STEP_COST: i32 = 100;
+pub const DIAGONAL_STEP_COST: i32 = 141;
+
+/// Grid connectivity used by the search, named after the usual
+/// "diagonal movement" settings of grid pathfinders.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum DiagonalMovement {
+    /// 4-connected search; only orthogonal steps.
+    #[default]
+    Never,
+    /// Diagonal steps are always allowed, even between two walls that touch
+    /// at a corner.
+    Always,
+    /// Diagonal steps may cut past one blocked orthogonal neighbor but not
+    /// squeeze between two.
+    IfAtMostOneObstacle,
+    /// Diagonal steps require both orthogonal neighbors to be open.
+    OnlyWhenNoObstacles,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum SearchAlgorithm {
+    #[default]
+    AStar,
+    /// Jump Point Search. Only valid on uniform-cost square grids; maps with
+    /// weighted terrain or hex cells fall back to A*.
+    JumpPoint,
+    /// Any-angle Theta*. Returns only the turning points of the path, so
+    /// consecutive cells need not be adjacent; such results have
+    /// `PathResult::any_angle` set. Uniform-cost square grids only, like
+    /// `JumpPoint`.
+    ThetaStar,
+    /// A* from both ends at once. Same path costs as `AStar`, usually with
+    /// fewer expansions along long corridors. Square grids only; hex maps
+    /// fall back to A*.
+    Bidirectional,
+}
+
+/// Per-query search settings. The default is the original 4-way A* search.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PathOptions {
+    pub diagonal: DiagonalMovement,
+    pub algorithm: SearchAlgorithm,
+    /// Agent radius in world units. Cells whose clearance is smaller are
+    /// treated as blocked.
+    pub agent_radius: f32,
+    /// When the goal cannot be reached, return the path to the reachable
+    /// cell closest to it instead of no path.
+    pub closest_fallback: bool,
+}
+
+impl PathOptions {
+    pub fn four_way() -> Self {
+        Self {
+            diagonal: DiagonalMovement::Never,
+            ..Self::default()
+        }
+    }
+
+    pub fn eight_way(diagonal: DiagonalMovement) -> Self {
+        Self {
+            diagonal,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_algorithm(mut self, algorithm: SearchAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    pub fn with_agent_radius(mut self, agent_radius: f32) -> Self {
+        self.agent_radius = agent_radius;
+        self
+    }
+
+    pub fn with_closest_fallback(mut self, closest_fallback: bool) -> Self {
+        self.closest_fallback = closest_fallback;
+        self
+    }
+}
+
+/// Why a search did not reach its goal.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PathFailure {
+    EmptyMap,
+    StartInWall,
+    GoalInWall,
+    Unreachable,
+}
+
+/// Outcome of a grid search.
+///
+/// On failure `cells` is empty, unless the search ran with
+/// [`PathOptions::closest_fallback`], in which case it leads to the reachable
+/// cell closest to the goal and `failure` still says why the goal itself was
+/// missed.
+///
+/// Searches over a custom [`SearchSpace`] report their own node type in
+/// `cells`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PathResult<N = (usize, usize)> {
+    /// Start and end cell included.
+    pub cells: Vec<N>,
+    /// Cost of `cells`, in search cost units.
+    pub cost: i32,
+    /// Nodes taken off the open list.
+    pub expanded: usize,
+    pub failure: Option<PathFailure>,
+    /// Set by Theta*: `cells` are turning points joined by straight lines,
+    /// and consecutive cells need not be adjacent.
+    pub any_angle: bool,
+}
+
+impl<N> Default for PathResult<N> {
+    fn default() -> Self {
+        Self {
+            cells: Vec::new(),
+            cost: 0,
+            expanded: 0,
+            failure: None,
+            any_angle: false,
+        }
+    }
+}
+
+impl<N> PathResult<N> {
+    fn failed(failure: PathFailure) -> Self {
+        Self {
+            failure: Some(failure),
+            ..Self::default()
+        }
+    }
+
+    /// Whether `cells` ends at the goal.
+    pub fn is_found(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+/// Answers the queries every search handles the same way: empty maps, walled
+/// endpoints and `start == goal`. A walled goal is left to the search when a
+/// closest-cell fallback was asked for.
+fn trivial_result<N, FPassable>(
+    start: N,
+    goal: N,
+    node_count: usize,
+    closest_fallback: bool,
+    passable_fn: FPassable,
+) -> Option<PathResult<N>>
+where
+    N: Copy + Eq,
+    FPassable: Fn(N) -> bool,
+{
+    if node_count == 0 {
+        return Some(PathResult::failed(PathFailure::EmptyMap));
+    }
+    if !passable_fn(start) {
+        return Some(PathResult::failed(PathFailure::StartInWall));
+    }
+    if !passable_fn(goal) && !closest_fallback {
+        return Some(PathResult::failed(PathFailure::GoalInWall));
+    }
+    if start == goal {
+        return Some(PathResult {
+            cells: vec![start],
+            ..PathResult::default()
+        });
+    }
+    None
+}
+
+/// Walks `came_from` links back from node index `end` to `start`.
+fn reconstruct_indices(workspace: &PathfinderWorkspace, start: usize, end: usize) -> Vec<usize> {
+    let mut path = vec![end];
+    let mut cur = end;
+    while cur != start {
+        match workspace.came_from(cur) {
+            Some(prev) => {
+                cur = prev;
+                path.push(cur);
+            }
+            None => return vec![],
+        }
+    }
+    path.reverse();
+    path
+}
+
+fn reconstruct_path(
+    workspace: &PathfinderWorkspace,
+    width: usize,
+    start: (usize, usize),
+    end: (usize, usize),
+) -> Vec<(usize, usize)> {
+    reconstruct_indices(workspace, start.1 * width + start.0, end.1 * width + end.0)
+        .into_iter()
+        .map(|i| (i % width, i / width))
+        .collect()
+}
+
 pub fn manhattan(a: (usize, usize), b: (usize, usize)) -> i32 {
-    let dx = if a.0 > b.0 { a.0 - b.0 } else { b.0 - a.0 };
-    let dy = if a.1 > b.1 { a.1 - b.1 } else { b.1 - a.1 };
-    (dx + dy) as i32
+    (a.0.abs_diff(b.0) + a.1.abs_diff(b.1)) as i32
+}
+
+/// Octile distance in step-cost units: diagonal moves for the shared extent,
+/// straight moves for the remainder.
+pub fn octile(a: (usize, usize), b: (usize, usize)) -> i32 {
+    let dx = a.0.abs_diff(b.0) as i32;
+    let dy = a.1.abs_diff(b.1) as i32;
+    let (lo, hi) = if dx < dy { (dx, dy) } else { (dy, dx) };
+    DIAGONAL_STEP_COST * lo + STRAIGHT_STEP_COST * (hi - lo)
+}
+
+/// Distance estimate priced at the cheapest traversal cost on the map so it
+/// stays admissible when some terrain is cheaper than plain floor. Steps are
+/// priced after rounding, exactly as the searches charge them.
+fn heuristic(
+    a: (usize, usize),
+    b: (usize, usize),
+    diagonal: DiagonalMovement,
+    cost_floor: f32,
+) -> i32 {
+    let (straight, diagonal_step) = floor_step_costs(cost_floor);
+    let dx = a.0.abs_diff(b.0) as i32;
+    let dy = a.1.abs_diff(b.1) as i32;
+    match diagonal {
+        DiagonalMovement::Never => (dx + dy) * straight,
+        _ => {
+            let (lo, hi) = if dx < dy { (dx, dy) } else { (dy, dx) };
+            diagonal_step * lo + straight * (hi - lo)
+        }
+    }
 }
 
-fn neighbors4(x: usize, y: usize) -> [(i32, i32); 4] {
-    [
-        (x as i32 + 1, y as i32),
-        (x as i32 - 1, y as i32),
-        (x as i32, y as i32 + 1),
-        (x as i32, y as i32 - 1),
-    ]
+/// Cheapest possible straight and diagonal step costs on a map whose lowest
+/// traversal cost is `cost_floor`.
+fn floor_step_costs(cost_floor: f32) -> (i32, i32) {
+    let straight = weighted_step_cost(STRAIGHT_STEP_COST, cost_floor);
+    let diagonal = weighted_step_cost(DIAGONAL_STEP_COST, cost_floor).min(2 * straight);
+    (straight, diagonal)
 }
 
-pub fn astar(start: (usize, usize), goal: (usize, usize)) -> Vec<(usize, usize)> {
-    astar_impl(
+/// The distance heuristic, tightened by a landmark table when there is one.
+fn landmark_heuristic(
+    a: (usize, usize),
+    b: (usize, usize),
+    diagonal: DiagonalMovement,
+    hex: Option<&HexLayout>,
+    cost_floor: f32,
+    landmarks: Option<&LandmarkTable>,
+) -> i32 {
+    let h = cell_heuristic(a, b, diagonal, hex, cost_floor);
+    match landmarks {
+        Some(table) => h.max(table.lower_bound(a, b)),
+        None => h,
+    }
+}
+
+/// `heuristic` for either cell shape; on hex maps every step is a straight
+/// one.
+fn cell_heuristic(
+    a: (usize, usize),
+    b: (usize, usize),
+    diagonal: DiagonalMovement,
+    hex: Option<&HexLayout>,
+    cost_floor: f32,
+) -> i32 {
+    match hex {
+        Some(hex) => hex.distance(a, b) as i32 * floor_step_costs(cost_floor).0,
+        None => heuristic(a, b, diagonal, cost_floor),
+    }
+}
+
+fn weighted_step_cost(base: i32, traversal_cost: f32) -> i32 {
+    ((base as f32 * traversal_cost).round() as i32).max(1)
+}
+
+/// Total cost of walking `path` on `map`, in the same units the searches use.
+pub fn path_cost_with_map(map: &RuntimeMapAdapter, path: &[(usize, usize)]) -> i32 {
+    path.windows(2)
+        .map(|w| {
+            let base = if map.hex.is_none() && w[0].0 != w[1].0 && w[0].1 != w[1].1 {
+                DIAGONAL_STEP_COST
+            } else {
+                STRAIGHT_STEP_COST
+            };
+            weighted_step_cost(base, map.traversal_cost(w[1].0, w[1].1))
+        })
+        .sum()
+}
+
+const NEIGHBOR_DIRS: [(i32, i32); 8] = [
+    (1, 0),
+    (-1, 0),
+    (0, 1),
+    (0, -1),
+    (1, 1),
+    (-1, 1),
+    (1, -1),
+    (-1, -1),
+];
+
+fn neighbor_dirs(diagonal: DiagonalMovement) -> &'static [(i32, i32)] {
+    match diagonal {
+        DiagonalMovement::Never => &NEIGHBOR_DIRS[..4],
+        _ => &NEIGHBOR_DIRS,
+    }
+}
+
+/// Offsets to the cells one step from `cell`: the grid directions `diagonal`
+/// allows, or the six hex neighbors on hex maps.
+fn cell_steps(
+    cell: (usize, usize),
+    diagonal: DiagonalMovement,
+    hex: Option<&HexLayout>,
+) -> &'static [(i32, i32)] {
+    match hex {
+        Some(hex) => hex.neighbor_offsets(cell.0, cell.1),
+        None => neighbor_dirs(diagonal),
+    }
+}
+
+fn diagonal_step_allowed<FBlocked>(
+    from: (usize, usize),
+    to: (usize, usize),
+    width: usize,
+    height: usize,
+    diagonal: DiagonalMovement,
+    blocked_fn: &FBlocked,
+) -> bool
+where
+    FBlocked: Fn(usize, usize) -> bool,
+{
+    let side_blocked = |x: usize, y: usize| x >= width || y >= height || blocked_fn(x, y);
+    let a = side_blocked(to.0, from.1);
+    let b = side_blocked(from.0, to.1);
+
+    match diagonal {
+        DiagonalMovement::Never => false,
+        DiagonalMovement::Always => true,
+        DiagonalMovement::IfAtMostOneObstacle => !(a && b),
+        DiagonalMovement::OnlyWhenNoObstacles => !a && !b,
+    }
+}
+
+/// Runs the search selected by `options.algorithm`. Each call allocates its
+/// own search buffers; see [`PathfinderWorkspace`] for repeated queries.
+pub fn find_path(start: (usize, usize), goal: (usize, usize), options: &PathOptions) -> PathResult {
+    let map = runtime_map().expect("runtime map unavailable");
+    find_path_with_map(&map, start, goal, options)
+}
+
+pub fn find_path_with_map(
+    map: &RuntimeMapAdapter,
+    start: (usize, usize),
+    goal: (usize, usize),
+    options: &PathOptions,
+) -> PathResult {
+    PathfinderWorkspace::new().find_path_with_map(map, start, goal, options)
+}
+
+pub fn jps_with_map(
+    map: &RuntimeMapAdapter,
+    start: (usize, usize),
+    goal: (usize, usize),
+    options: &PathOptions,
+) -> PathResult {
+    jps_in(&mut PathfinderWorkspace::new(), map, start, goal, options)
+}
+
+fn jps_in(
+    workspace: &mut PathfinderWorkspace,
+    map: &RuntimeMapAdapter,
+    start: (usize, usize),
+    goal: (usize, usize),
+    options: &PathOptions,
+) -> PathResult {
+    if !map.has_uniform_cost() || map.hex.is_some() {
+        return astar_in(workspace, map, start, goal, options);
+    }
+
+    let result = jps::jps_impl(
+        workspace,
         start,
         goal,
-        map_width(),
-        map_height(),
-        |x, y| is_wall(x, y),
-        |x, y| blocked_for_agent(x, y),
-    )
+        map.width,
+        map.height,
+        options,
+        |x, y| map.is_wall(x, y),
+        |x, y| map.blocked_for_radius(x, y, options.agent_radius),
+    );
+
+    // Jump points are too sparse to pick a closest cell from; let A* do it.
+    let missed_goal = matches!(
+        result.failure,
+        Some(PathFailure::GoalInWall | PathFailure::Unreachable)
+    );
+    if missed_goal && options.closest_fallback {
+        return astar_in(workspace, map, start, goal, options);
+    }
+    result
 }
 
-pub fn astar_with_map(
+pub fn theta_star_with_map(
     map: &RuntimeMapAdapter,
     start: (usize, usize),
     goal: (usize, usize),
-) -> Vec<(usize, usize)> {
-    astar_impl(
+    options: &PathOptions,
+) -> PathResult {
+    theta_star_in(&mut PathfinderWorkspace::new(), map, start, goal, options)
+}
+
+fn theta_star_in(
+    workspace: &mut PathfinderWorkspace,
+    map: &RuntimeMapAdapter,
+    start: (usize, usize),
+    goal: (usize, usize),
+    options: &PathOptions,
+) -> PathResult {
+    if !map.has_uniform_cost() || map.hex.is_some() {
+        return astar_in(workspace, map, start, goal, options);
+    }
+
+    theta_star::theta_star_impl(
+        workspace,
         start,
         goal,
         map.width,
         map.height,
+        options,
         |x, y| map.is_wall(x, y),
-        |x, y| map.blocked_for_agent(x, y),
+        |x, y| map.blocked_for_radius(x, y, options.agent_radius),
+        |a, b| los_grid_for_radius(map, a, b, options.agent_radius),
     )
 }
 
-fn astar_impl<FIsWall, FBlocked>(
+pub fn bidirectional_astar_with_map(
+    map: &RuntimeMapAdapter,
     start: (usize, usize),
     goal: (usize, usize),
-    width: usize,
-    height: usize,
-    is_wall_fn: FIsWall,
-    blocked_fn: FBlocked,
-) -> Vec<(usize, usize)>
+    options: &PathOptions,
+) -> PathResult {
+    bidirectional_in(
+        &mut PathfinderWorkspace::new(),
+        &mut PathfinderWorkspace::new(),
+        map,
+        start,
+        goal,
+        options,
+    )
+}
+
+fn bidirectional_in(
+    forward: &mut PathfinderWorkspace,
+    backward: &mut PathfinderWorkspace,
+    map: &RuntimeMapAdapter,
+    start: (usize, usize),
+    goal: (usize, usize),
+    options: &PathOptions,
+) -> PathResult {
+    if map.hex.is_some() {
+        return astar_in(forward, map, start, goal, options);
+    }
+    let result = bidirectional::bidirectional_in(forward, backward, map, start, goal, options);
+
+    // Meeting fronts say nothing about which cell came closest; let A* do it.
+    let missed_goal = matches!(
+        result.failure,
+        Some(PathFailure::GoalInWall | PathFailure::Unreachable)
+    );
+    if missed_goal && options.closest_fallback {
+        return astar_in(forward, map, start, goal, options);
+    }
+    result
+}
+
+pub fn astar(start: (usize, usize), goal: (usize, usize)) -> PathResult {
+    astar_with_options(start, goal, &PathOptions::default())
+}
+
+pub fn astar_with_options(
+    start: (usize, usize),
+    goal: (usize, usize),
+    options: &PathOptions,
+) -> PathResult {
+    let map = runtime_map().expect("runtime map unavailable");
+    astar_with_map_options(&map, start, goal, options)
+}
+
+pub fn astar_with_map(
+    map: &RuntimeMapAdapter,
+    start: (usize, usize),
+    goal: (usize, usize),
+) -> PathResult {
+    astar_with_map_options(map, start, goal, &PathOptions::default())
+}
+
+pub fn astar_with_map_options(
+    map: &RuntimeMapAdapter,
+    start: (usize, usize),
+    goal: (usize, usize),
+    options: &PathOptions,
+) -> PathResult {
+    astar_in(&mut PathfinderWorkspace::new(), map, start, goal, options)
+}
+
+fn astar_in(
+    workspace: &mut PathfinderWorkspace,
+    map: &RuntimeMapAdapter,
+    start: (usize, usize),
+    goal: (usize, usize),
+    options: &PathOptions,
+) -> PathResult {
+    let space = GridSpace::new(map, options);
+    astar_impl(workspace, &space, start, goal, options.closest_fallback)
+}
+
+/// Cheapest path from `start` to whichever of `goals` is nearest. Fails with
+/// `GoalInWall` only when every goal is a wall.
+pub fn astar_to_any(
+    start: (usize, usize),
+    goals: &[(usize, usize)],
+    options: &PathOptions,
+) -> PathResult {
+    let map = runtime_map().expect("runtime map unavailable");
+    astar_to_any_with_map(&map, start, goals, options)
+}
+
+pub fn astar_to_any_with_map(
+    map: &RuntimeMapAdapter,
+    start: (usize, usize),
+    goals: &[(usize, usize)],
+    options: &PathOptions,
+) -> PathResult {
+    let in_bounds: Vec<(usize, usize)> = goals
+        .iter()
+        .copied()
+        .filter(|&(x, y)| x < map.width && y < map.height)
+        .collect();
+    let open: Vec<(usize, usize)> = in_bounds
+        .iter()
+        .copied()
+        .filter(|&(x, y)| !map.is_wall(x, y))
+        .collect();
+    let goal_in_wall = open.is_empty() && !in_bounds.is_empty();
+
+    let mut is_goal = vec![false; map.width * map.height];
+    for &(x, y) in &open {
+        is_goal[y * map.width + x] = true;
+    }
+
+    // Aim at the walled goals when nothing else is left, so the closest-cell
+    // fallback still approaches them.
+    let targets = if open.is_empty() { &in_bounds } else { &open };
+    let cost_floor = map.min_traversal_cost();
+    astar_to_target(
+        &mut PathfinderWorkspace::new(),
+        map,
+        start,
+        options,
+        goal_in_wall,
+        |(x, y)| is_goal[y * map.width + x],
+        |cell| {
+            targets
+                .iter()
+                .map(|&goal| {
+                    cell_heuristic(cell, goal, options.diagonal, map.hex.as_ref(), cost_floor)
+                })
+                .min()
+                .unwrap_or(0)
+        },
+    )
+}
+
+/// Cheapest path from `start` to any cell `is_goal` accepts, e.g. every
+/// cell inside an area or every cell that sees the player. With nothing to
+/// aim at the search spreads evenly like Dijkstra, and the closest-cell
+/// fallback can only offer `start` itself.
+pub fn astar_to_matching<FGoal>(
+    start: (usize, usize),
+    is_goal: FGoal,
+    options: &PathOptions,
+) -> PathResult
 where
-    FIsWall: Fn(usize, usize) -> bool,
-    FBlocked: Fn(usize, usize) -> bool,
+    FGoal: Fn(usize, usize) -> bool,
+{
+    let map = runtime_map().expect("runtime map unavailable");
+    astar_to_matching_with_map(&map, start, is_goal, options)
+}
+
+pub fn astar_to_matching_with_map<FGoal>(
+    map: &RuntimeMapAdapter,
+    start: (usize, usize),
+    is_goal: FGoal,
+    options: &PathOptions,
+) -> PathResult
+where
+    FGoal: Fn(usize, usize) -> bool,
+{
+    astar_to_target(
+        &mut PathfinderWorkspace::new(),
+        map,
+        start,
+        options,
+        false,
+        |(x, y)| is_goal(x, y),
+        |_| 0,
+    )
+}
+
+/// Shared driver for the multi-goal searches. `estimate_fn` must not
+/// overestimate the cost to the nearest goal.
+fn astar_to_target<FGoal, FEstimate>(
+    workspace: &mut PathfinderWorkspace,
+    map: &RuntimeMapAdapter,
+    start: (usize, usize),
+    options: &PathOptions,
+    goal_in_wall: bool,
+    is_goal_fn: FGoal,
+    estimate_fn: FEstimate,
+) -> PathResult
+where
+    FGoal: Fn((usize, usize)) -> bool,
+    FEstimate: Fn((usize, usize)) -> i32,
 {
-    if is_wall_fn(start.0, start.1) || is_wall_fn(goal.0, goal.1) {
-        return vec![];
+    let space = GridSpace::new(map, options);
+    if space.node_count() == 0 {
+        return PathResult::failed(PathFailure::EmptyMap);
     }
-    if start == goal {
-        return vec![start];
+    if !space.passable(start) {
+        return PathResult::failed(PathFailure::StartInWall);
+    }
+    if goal_in_wall && !options.closest_fallback {
+        return PathResult::failed(PathFailure::GoalInWall);
     }
-    if width == 0 || height == 0 {
-        return vec![];
+    if is_goal_fn(start) {
+        return PathResult {
+            cells: vec![start],
+            ..PathResult::default()
+        };
     }
 
-    let idx = |x: usize, y: usize| -> usize { y * width + x };
+    let mut run = AStarRun::begin(
+        workspace,
+        &space,
+        start,
+        estimate_fn(start),
+        options.closest_fallback,
+        goal_in_wall,
+    );
+    run.resume(workspace, &space, usize::MAX, is_goal_fn, estimate_fn)
+        .expect("unbudgeted search always finishes")
+}
 
-    let mut open = BinaryHeap::<Node>::new();
-    let inf: i32 = i32::MAX / 4;
+/// A* from `start` to `goal` over any search space, guided by the space's
+/// own heuristic.
+fn astar_impl<S: SearchSpace>(
+    workspace: &mut PathfinderWorkspace,
+    space: &S,
+    start: S::Node,
+    goal: S::Node,
+    closest_fallback: bool,
+) -> PathResult<S::Node> {
+    if let Some(result) =
+        trivial_result(start, goal, space.node_count(), closest_fallback, |node| {
+            space.passable(node)
+        })
+    {
+        return result;
+    }
 
-    let cell_count = width * height;
-    let mut g_score = vec![inf; cell_count];
-    let mut came_from = vec![None::<(usize, usize)>; cell_count];
-    let mut closed = vec![false; cell_count];
+    let estimate_fn = |node| space.heuristic(node, goal);
+    let mut run = AStarRun::begin(
+        workspace,
+        space,
+        start,
+        estimate_fn(start),
+        closest_fallback,
+        !space.passable(goal),
+    );
+    run.resume(
+        workspace,
+        space,
+        usize::MAX,
+        |node| node == goal,
+        estimate_fn,
+    )
+    .expect("unbudgeted search always finishes")
+}
 
-    g_score[idx(start.0, start.1)] = 0;
+/// A* state kept outside the workspace, so a search can be driven a budget
+/// of expansions at a time. The goal is supplied per call as a predicate
+/// plus an admissible estimate, which lets one run serve single-node,
+/// many-node and predicate targets alike.
+#[derive(Clone, Debug)]
+struct AStarRun<N> {
+    start: N,
+    closest_fallback: bool,
+    goal_in_wall: bool,
+    expanded: usize,
+    /// Best `(estimate, g)` so far and the index of its node.
+    closest: ((i32, i32), usize),
+}
 
-    open.push(Node {
-        x: start.0,
-        y: start.1,
-        g: 0,
-        f: manhattan(start, goal),
-    });
+impl<N: Copy + Eq> AStarRun<N> {
+    /// Resets `workspace` and seeds it with `start`. The caller has already
+    /// ruled out the cases `trivial_result` answers.
+    fn begin<S: SearchSpace<Node = N>>(
+        workspace: &mut PathfinderWorkspace,
+        space: &S,
+        start: N,
+        start_estimate: i32,
+        closest_fallback: bool,
+        goal_in_wall: bool,
+    ) -> Self {
+        let inf: i32 = i32::MAX / 4;
+        let start_idx = space.index(start);
 
-    while let Some(current) = open.pop() {
-        let cx = current.x;
-        let cy = current.y;
-        let current_idx = idx(cx, cy);
+        workspace.begin(space.node_count());
+        workspace.set(start_idx, 0, None);
+        workspace.open.push(Node {
+            idx: start_idx,
+            g: 0,
+            f: start_estimate,
+        });
 
-        if closed[current_idx] {
-            continue;
-        }
-        closed[current_idx] = true;
-
-        if (cx, cy) == goal {
-            let mut path = vec![(cx, cy)];
-            let mut cur = (cx, cy);
-            while cur != start {
-                if let Some(prev) = came_from[idx(cur.0, cur.1)] {
-                    cur = prev;
-                    path.push(cur);
-                } else {
-                    return vec![];
-                }
-            }
-            path.reverse();
-            return path;
+        Self {
+            start,
+            closest_fallback,
+            goal_in_wall,
+            expanded: 0,
+            closest: ((inf, inf), start_idx),
         }
+    }
 
-        let current_g = g_score[current_idx];
+    /// Expands up to `budget` nodes. Returns `None` if the budget ran out
+    /// before the search finished.
+    fn resume<S, FGoal, FEstimate>(
+        &mut self,
+        workspace: &mut PathfinderWorkspace,
+        space: &S,
+        budget: usize,
+        is_goal_fn: FGoal,
+        estimate_fn: FEstimate,
+    ) -> Option<PathResult<N>>
+    where
+        S: SearchSpace<Node = N>,
+        FGoal: Fn(N) -> bool,
+        FEstimate: Fn(N) -> i32,
+    {
+        let start_idx = space.index(self.start);
+        let path_to = |workspace: &PathfinderWorkspace, end: usize| -> Vec<N> {
+            reconstruct_indices(workspace, start_idx, end)
+                .into_iter()
+                .map(|i| space.node(i))
+                .collect()
+        };
+        let mut neighbors = Vec::new();
+        let mut spent = 0;
 
-        for (nx, ny) in neighbors4(cx, cy) {
-            if nx < 0 || ny < 0 {
+        while let Some(current) = workspace.open.pop() {
+            let current_idx = current.idx;
+            if workspace.is_closed(current_idx) {
                 continue;
             }
-            let nx = nx as usize;
-            let ny = ny as usize;
-            if nx >= width || ny >= height {
-                continue;
+            if spent == budget {
+                workspace.open.push(current);
+                return None;
             }
-            let neighbor_idx = idx(nx, ny);
+            spent += 1;
+            workspace.close(current_idx);
+            self.expanded += 1;
 
-            if blocked_fn(nx, ny) {
-                continue;
+            let node = space.node(current_idx);
+            let current_g = workspace.g(current_idx);
+            if is_goal_fn(node) {
+                return Some(PathResult {
+                    cells: path_to(workspace, current_idx),
+                    cost: current_g,
+                    expanded: self.expanded,
+                    failure: None,
+                    any_angle: false,
+                });
             }
-            if closed[neighbor_idx] {
-                continue;
+
+            let closeness = (estimate_fn(node), current_g);
+            if closeness < self.closest.0 {
+                self.closest = (closeness, current_idx);
             }
 
-            let tentative_g = current_g + 1;
-            if tentative_g < g_score[neighbor_idx] {
-                came_from[neighbor_idx] = Some((cx, cy));
-                g_score[neighbor_idx] = tentative_g;
-
-                let h = manhattan((nx, ny), goal);
-                open.push(Node {
-                    x: nx,
-                    y: ny,
-                    g: tentative_g,
-                    f: tentative_g + h,
-                });
+            neighbors.clear();
+            space.neighbors(node, &mut neighbors);
+            for &next in &neighbors {
+                let next_idx = space.index(next);
+                if workspace.is_closed(next_idx) {
+                    continue;
+                }
+
+                let tentative_g = current_g + space.step_cost(node, next);
+                if tentative_g < workspace.g(next_idx) {
+                    workspace.set(next_idx, tentative_g, Some(current_idx));
+                    workspace.open.push(Node {
+                        idx: next_idx,
+                        g: tentative_g,
+                        f: tentative_g + estimate_fn(next),
+                    });
+                }
             }
         }
-    }
 
-    vec![]
+        let failure = if self.goal_in_wall {
+            PathFailure::GoalInWall
+        } else {
+            PathFailure::Unreachable
+        };
+        if !self.closest_fallback {
+            return Some(PathResult {
+                expanded: self.expanded,
+                ..PathResult::failed(failure)
+            });
+        }
+
+        let (_, end) = self.closest;
+        Some(PathResult {
+            cells: path_to(workspace, end),
+            cost: workspace.g(end),
+            expanded: self.expanded,
+            failure: Some(failure),
+            any_angle: false,
+        })
+    }
 }
@@ -0,0 +1,184 @@
+//! Bidirectional A*. The best meeting cost `mu` is final once the smallest
+//! `f` on either open list reaches it.
+
+use super::{
+    DIAGONAL_STEP_COST, Node, PathFailure, PathOptions, PathResult, PathfinderWorkspace,
+    STRAIGHT_STEP_COST, diagonal_step_allowed, heuristic, neighbor_dirs, reconstruct_path,
+    trivial_result, weighted_step_cost,
+};
+use crate::map::RuntimeMapAdapter;
+
+const INF: i32 = i32::MAX / 4;
+
+/// Smallest `f` still waiting on `workspace`'s open list, dropping entries
+/// for nodes that were closed since they were pushed.
+fn top_f(workspace: &mut PathfinderWorkspace) -> Option<i32> {
+    while let Some(top) = workspace.open.peek() {
+        if !workspace.is_closed(top.idx) {
+            return Some(top.f);
+        }
+        workspace.open.pop();
+    }
+    None
+}
+
+pub(super) fn bidirectional_in(
+    forward: &mut PathfinderWorkspace,
+    backward: &mut PathfinderWorkspace,
+    map: &RuntimeMapAdapter,
+    start: (usize, usize),
+    goal: (usize, usize),
+    options: &PathOptions,
+) -> PathResult {
+    let (width, height) = (map.width, map.height);
+    let is_wall_fn = |x: usize, y: usize| map.is_wall(x, y);
+    if let Some(result) = trivial_result(start, goal, width * height, false, |(x, y)| {
+        !is_wall_fn(x, y)
+    }) {
+        return result;
+    }
+
+    let blocked_fn = |x: usize, y: usize| map.blocked_for_radius(x, y, options.agent_radius);
+    let cost_floor = map.min_traversal_cost();
+    let h_forward = |cell: (usize, usize)| heuristic(cell, goal, options.diagonal, cost_floor);
+    let h_backward = |cell: (usize, usize)| heuristic(start, cell, options.diagonal, cost_floor);
+    let idx = |cell: (usize, usize)| cell.1 * width + cell.0;
+
+    forward.begin(width * height);
+    backward.begin(width * height);
+    forward.set(idx(start), 0, None);
+    forward.open.push(Node {
+        idx: idx(start),
+        g: 0,
+        f: h_forward(start),
+    });
+    backward.set(idx(goal), 0, None);
+    backward.open.push(Node {
+        idx: idx(goal),
+        g: 0,
+        f: h_backward(goal),
+    });
+
+    let mut mu = INF;
+    let mut meet = None;
+    let mut expanded = 0;
+
+    // Once either side runs dry, every path it could still take part in has
+    // been seen.
+    while let (Some(top_forward), Some(top_backward)) = (top_f(forward), top_f(backward)) {
+        if top_forward >= mu || top_backward >= mu {
+            break;
+        }
+
+        // Grow the side with the smaller frontier.
+        let is_forward = forward.open.len() <= backward.open.len();
+        let (this, other) = if is_forward {
+            (&mut *forward, &mut *backward)
+        } else {
+            (&mut *backward, &mut *forward)
+        };
+
+        let Some(current) = this.open.pop() else {
+            break;
+        };
+        let cell = (current.idx % width, current.idx / width);
+        this.close(current.idx);
+        expanded += 1;
+
+        // Backwards, `cell` is the head of every edge being relaxed, so a
+        // cell the agent may not enter has no incoming edges at all.
+        if !is_forward && blocked_fn(cell.0, cell.1) {
+            continue;
+        }
+
+        let current_g = this.g(idx(cell));
+        for &(dx, dy) in neighbor_dirs(options.diagonal) {
+            let nx = cell.0 as i32 + dx;
+            let ny = cell.1 as i32 + dy;
+            if nx < 0 || ny < 0 || nx as usize >= width || ny as usize >= height {
+                continue;
+            }
+            let next = (nx as usize, ny as usize);
+            let next_idx = idx(next);
+            if this.is_closed(next_idx) {
+                continue;
+            }
+
+            // The edge always runs `from -> to` in walking direction.
+            let (from, to) = if is_forward {
+                (cell, next)
+            } else {
+                (next, cell)
+            };
+            if blocked_fn(to.0, to.1) {
+                continue;
+            }
+            if from != start && blocked_fn(from.0, from.1) {
+                continue;
+            }
+
+            let is_diagonal = dx != 0 && dy != 0;
+            if is_diagonal
+                && !diagonal_step_allowed(from, to, width, height, options.diagonal, &blocked_fn)
+            {
+                continue;
+            }
+
+            let base = if is_diagonal {
+                DIAGONAL_STEP_COST
+            } else {
+                STRAIGHT_STEP_COST
+            };
+            let tentative_g = current_g + weighted_step_cost(base, map.traversal_cost(to.0, to.1));
+            if tentative_g >= this.g(next_idx) {
+                continue;
+            }
+
+            this.set(next_idx, tentative_g, Some(current.idx));
+            let h = if is_forward {
+                h_forward(next)
+            } else {
+                h_backward(next)
+            };
+            this.open.push(Node {
+                idx: next_idx,
+                g: tentative_g,
+                f: tentative_g + h,
+            });
+
+            let other_g = other.g(next_idx);
+            if other_g < INF && tentative_g + other_g < mu {
+                mu = tentative_g + other_g;
+                meet = Some(next);
+            }
+        }
+    }
+
+    let Some(meet) = meet else {
+        return PathResult {
+            expanded,
+            ..PathResult::failed(PathFailure::Unreachable)
+        };
+    };
+
+    let mut cells = reconstruct_path(forward, width, start, meet);
+    let mut cur = meet;
+    while cur != goal {
+        match backward.came_from(idx(cur)) {
+            Some(next) => {
+                let next = (next % width, next / width);
+                cells.push(next);
+                cur = next;
+            }
+            None => return PathResult::failed(PathFailure::Unreachable),
+        }
+    }
+
+    PathResult {
+        cells,
+        cost: mu,
+        expanded,
+        failure: None,
+        any_angle: false,
+    }
+}
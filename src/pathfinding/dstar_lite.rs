@@ -0,0 +1,375 @@
+//! Incremental replanning with D* Lite (Koenig & Likhachev).
+//!
+//! The search runs backwards from the goal, so the start may move freely; the
+//! key modifier `km` keeps old queue entries valid when it does. A goal move
+//! gives the new goal `rhs = 0`, recomputes the old goal's `rhs` from its
+//! neighbours, and lets the usual repair propagate the change.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use super::{
+    DIAGONAL_STEP_COST, PathFailure, PathOptions, PathResult, STRAIGHT_STEP_COST, cell_heuristic,
+    cell_steps, diagonal_step_allowed, trivial_result, weighted_step_cost,
+};
+use crate::map::{HexLayout, MAX_CLEARANCE_TILES, RuntimeMapAdapter};
+
+const INF: i32 = i32::MAX / 4;
+
+type Key = (i32, i32);
+
+/// Persistent D* Lite planner for one agent on one map. Call
+/// [`DStarLite::update_cell`] for every cell changed on the map.
+#[derive(Clone, Debug)]
+pub struct DStarLite {
+    width: usize,
+    height: usize,
+    hex: Option<HexLayout>,
+    options: PathOptions,
+    cost_floor: f32,
+    wall: Vec<bool>,
+    blocked: Vec<bool>,
+    cost: Vec<f32>,
+    g: Vec<i32>,
+    rhs: Vec<i32>,
+    open_key: Vec<Option<Key>>,
+    open: BinaryHeap<Reverse<(Key, usize)>>,
+    start: (usize, usize),
+    last_start: (usize, usize),
+    goal: (usize, usize),
+    km: i32,
+    expanded: usize,
+}
+
+impl DStarLite {
+    pub fn new(
+        map: &RuntimeMapAdapter,
+        start: (usize, usize),
+        goal: (usize, usize),
+        options: &PathOptions,
+    ) -> Self {
+        let cell_count = map.width * map.height;
+        let mut wall = Vec::with_capacity(cell_count);
+        let mut blocked = Vec::with_capacity(cell_count);
+        for y in 0..map.height {
+            for x in 0..map.width {
+                wall.push(map.is_wall(x, y));
+                blocked.push(map.blocked_for_radius(x, y, options.agent_radius));
+            }
+        }
+
+        let mut planner = Self {
+            width: map.width,
+            height: map.height,
+            hex: map.hex,
+            options: *options,
+            cost_floor: map.min_traversal_cost(),
+            wall,
+            blocked,
+            cost: map.cost.clone(),
+            g: vec![INF; cell_count],
+            rhs: vec![INF; cell_count],
+            open_key: vec![None; cell_count],
+            open: BinaryHeap::new(),
+            start,
+            last_start: start,
+            goal,
+            km: 0,
+            expanded: 0,
+        };
+
+        if planner.in_bounds(goal) {
+            let goal_idx = planner.idx(goal);
+            planner.rhs[goal_idx] = 0;
+            planner.push(goal);
+        }
+        planner
+    }
+
+    pub fn start(&self) -> (usize, usize) {
+        self.start
+    }
+
+    pub fn goal(&self) -> (usize, usize) {
+        self.goal
+    }
+
+    /// Moves the agent. Cheap: only shifts the key modifier.
+    pub fn set_start(&mut self, start: (usize, usize)) {
+        if start == self.start {
+            return;
+        }
+        self.km += self.h(self.last_start, start);
+        self.last_start = start;
+        self.start = start;
+    }
+
+    /// Moves the goal. The old goal loses its zero cost-to-goal and the new
+    /// one gains it; the next [`DStarLite::compute_path`] repairs the rest.
+    pub fn set_goal(&mut self, goal: (usize, usize)) {
+        if goal == self.goal {
+            return;
+        }
+
+        let old_goal = self.goal;
+        self.goal = goal;
+        if self.in_bounds(old_goal) {
+            self.update_vertex(old_goal);
+        }
+        if self.in_bounds(goal) {
+            let goal_idx = self.idx(goal);
+            self.rhs[goal_idx] = 0;
+            self.update_vertex(goal);
+        }
+    }
+
+    /// Re-reads one cell's solidity and traversal cost from `map` and
+    /// invalidates every edge that depends on it, including diagonal steps
+    /// that squeeze past its corner. With an agent radius, a new or removed
+    /// wall changes clearance up to `MAX_CLEARANCE_TILES` cells away, so that
+    /// whole window is re-read.
+    pub fn update_cell(&mut self, map: &RuntimeMapAdapter, x: usize, y: usize) {
+        if !self.in_bounds((x, y)) {
+            return;
+        }
+
+        let reach = if self.options.agent_radius > 0.0 {
+            MAX_CLEARANCE_TILES
+        } else {
+            0
+        };
+        for cy in y.saturating_sub(reach)..(y + reach + 1).min(self.height) {
+            for cx in x.saturating_sub(reach)..(x + reach + 1).min(self.width) {
+                self.refresh_cell(map, (cx, cy));
+            }
+        }
+    }
+
+    fn refresh_cell(&mut self, map: &RuntimeMapAdapter, cell: (usize, usize)) {
+        let (x, y) = cell;
+        let i = self.idx(cell);
+        let wall = map.is_wall(x, y);
+        let blocked = map.blocked_for_radius(x, y, self.options.agent_radius);
+        let cost = map.traversal_cost(x, y);
+        if self.wall[i] == wall && self.blocked[i] == blocked && self.cost[i] == cost {
+            return;
+        }
+        self.wall[i] = wall;
+        self.blocked[i] = blocked;
+        self.cost[i] = cost;
+
+        if cost < self.cost_floor {
+            self.cost_floor = cost;
+            self.rekey_open();
+        }
+
+        self.update_vertex(cell);
+        for &(dx, dy) in cell_steps(cell, self.options.diagonal, self.hex.as_ref()) {
+            if let Some(n) = self.offset(cell, dx, dy) {
+                self.update_vertex(n);
+            }
+        }
+    }
+
+    /// Repairs the search for the current start and goal. `expanded` counts
+    /// only the cells this repair touched.
+    pub fn compute_path(&mut self) -> PathResult {
+        self.expanded = 0;
+
+        let passable = |cell: (usize, usize)| self.in_bounds(cell) && !self.wall[self.idx(cell)];
+        if let Some(result) = trivial_result(
+            self.start,
+            self.goal,
+            self.width * self.height,
+            false,
+            passable,
+        ) {
+            return result;
+        }
+
+        self.compute_shortest_path();
+        let cells = self.extract_path();
+        if cells.is_empty() {
+            return PathResult {
+                expanded: self.expanded,
+                ..PathResult::failed(PathFailure::Unreachable)
+            };
+        }
+        PathResult {
+            cells,
+            cost: self.g[self.idx(self.start)],
+            expanded: self.expanded,
+            ..PathResult::default()
+        }
+    }
+
+    fn compute_shortest_path(&mut self) {
+        let start = self.start;
+
+        while let Some(Reverse((key, u))) = self.open.peek().copied() {
+            let start_idx = self.idx(start);
+            if key >= self.calc_key(start) && self.rhs[start_idx] == self.g[start_idx] {
+                break;
+            }
+
+            self.open.pop();
+            if self.open_key[u] != Some(key) {
+                continue;
+            }
+
+            let cell = (u % self.width, u / self.width);
+            let new_key = self.calc_key(cell);
+            if key < new_key {
+                self.push(cell);
+                continue;
+            }
+
+            self.open_key[u] = None;
+            self.expanded += 1;
+
+            if self.g[u] > self.rhs[u] {
+                self.g[u] = self.rhs[u];
+            } else {
+                self.g[u] = INF;
+                self.update_vertex(cell);
+            }
+
+            for &(dx, dy) in cell_steps(cell, self.options.diagonal, self.hex.as_ref()) {
+                if let Some(pred) = self.offset(cell, dx, dy) {
+                    self.update_vertex(pred);
+                }
+            }
+        }
+    }
+
+    fn extract_path(&self) -> Vec<(usize, usize)> {
+        if self.g[self.idx(self.start)] >= INF {
+            return vec![];
+        }
+
+        let mut path = vec![self.start];
+        let mut cur = self.start;
+        while cur != self.goal {
+            if path.len() > self.width * self.height {
+                return vec![];
+            }
+
+            let next = self
+                .successors(cur)
+                .map(|(next, step)| (next, step.saturating_add(self.g[self.idx(next)])))
+                .min_by_key(|&(next, total)| (total, self.h(next, self.goal)));
+            match next {
+                Some((next, total)) if total < INF => {
+                    path.push(next);
+                    cur = next;
+                }
+                _ => return vec![],
+            }
+        }
+        path
+    }
+
+    fn update_vertex(&mut self, cell: (usize, usize)) {
+        let i = self.idx(cell);
+        if cell != self.goal {
+            self.rhs[i] = self
+                .successors(cell)
+                .map(|(next, step)| step.saturating_add(self.g[self.idx(next)]))
+                .min()
+                .unwrap_or(INF)
+                .min(INF);
+        }
+
+        if self.g[i] != self.rhs[i] {
+            self.push(cell);
+        } else {
+            self.open_key[i] = None;
+        }
+    }
+
+    /// Legal steps out of `cell` with their cost. Blocked cells can be left
+    /// but never entered, matching the forward A* search.
+    fn successors(&self, cell: (usize, usize)) -> impl Iterator<Item = ((usize, usize), i32)> {
+        let blocked_fn = |x: usize, y: usize| self.blocked[y * self.width + x];
+
+        cell_steps(cell, self.options.diagonal, self.hex.as_ref())
+            .iter()
+            .filter_map(move |&(dx, dy)| {
+                let next = self.offset(cell, dx, dy)?;
+                if self.blocked[self.idx(next)] {
+                    return None;
+                }
+
+                let is_diagonal = self.hex.is_none() && dx != 0 && dy != 0;
+                if is_diagonal
+                    && !diagonal_step_allowed(
+                        cell,
+                        next,
+                        self.width,
+                        self.height,
+                        self.options.diagonal,
+                        &blocked_fn,
+                    )
+                {
+                    return None;
+                }
+
+                let base = if is_diagonal {
+                    DIAGONAL_STEP_COST
+                } else {
+                    STRAIGHT_STEP_COST
+                };
+                Some((next, weighted_step_cost(base, self.cost[self.idx(next)])))
+            })
+    }
+
+    fn calc_key(&self, cell: (usize, usize)) -> Key {
+        let i = self.idx(cell);
+        let best = self.g[i].min(self.rhs[i]);
+        (best + self.h(self.start, cell) + self.km, best)
+    }
+
+    fn push(&mut self, cell: (usize, usize)) {
+        let key = self.calc_key(cell);
+        let i = self.idx(cell);
+        self.open_key[i] = Some(key);
+        self.open.push(Reverse((key, i)));
+    }
+
+    fn rekey_open(&mut self) {
+        self.open.clear();
+        for i in 0..self.open_key.len() {
+            if self.open_key[i].is_some() {
+                self.push((i % self.width, i / self.width));
+            }
+        }
+    }
+
+    fn h(&self, a: (usize, usize), b: (usize, usize)) -> i32 {
+        cell_heuristic(
+            a,
+            b,
+            self.options.diagonal,
+            self.hex.as_ref(),
+            self.cost_floor,
+        )
+    }
+
+    fn offset(&self, cell: (usize, usize), dx: i32, dy: i32) -> Option<(usize, usize)> {
+        let x = cell.0 as i64 + dx as i64;
+        let y = cell.1 as i64 + dy as i64;
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return None;
+        }
+        Some((x as usize, y as usize))
+    }
+
+    fn in_bounds(&self, cell: (usize, usize)) -> bool {
+        cell.0 < self.width && cell.1 < self.height
+    }
+
+    #[inline]
+    fn idx(&self, cell: (usize, usize)) -> usize {
+        cell.1 * self.width + cell.0
+    }
+}
@@ -0,0 +1,143 @@
+//! Flow fields: one Dijkstra from the goal shared by every agent chasing it.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use super::{
+    DIAGONAL_STEP_COST, PathOptions, STRAIGHT_STEP_COST, cell_steps, diagonal_step_allowed,
+    weighted_step_cost,
+};
+use crate::map::RuntimeMapAdapter;
+
+/// Cost-to-goal and best next step for every cell of a map.
+#[derive(Clone, Debug)]
+pub struct FlowField {
+    width: usize,
+    height: usize,
+    goal: (usize, usize),
+    distance: Vec<Option<i32>>,
+    next: Vec<Option<(usize, usize)>>,
+}
+
+impl FlowField {
+    /// Runs a single Dijkstra outward from `goal`. Cells that cannot reach
+    /// the goal get no distance and no direction. Distances match the cost of
+    /// an `astar_with_map_options` path from the cell to `goal`.
+    pub fn from_map(map: &RuntimeMapAdapter, goal: (usize, usize), options: &PathOptions) -> Self {
+        let (width, height) = (map.width, map.height);
+        let cell_count = width * height;
+        let mut field = Self {
+            width,
+            height,
+            goal,
+            distance: vec![None; cell_count],
+            next: vec![None; cell_count],
+        };
+
+        if goal.0 >= width || goal.1 >= height || map.is_wall(goal.0, goal.1) {
+            return field;
+        }
+
+        let blocked_fn = |x: usize, y: usize| map.blocked_for_radius(x, y, options.agent_radius);
+        let mut open = BinaryHeap::new();
+        let goal_idx = field.idx(goal);
+        field.distance[goal_idx] = Some(0);
+        open.push(Reverse((0, goal.1, goal.0)));
+
+        while let Some(Reverse((dist, y, x))) = open.pop() {
+            if field.distance[field.idx((x, y))] != Some(dist) {
+                continue;
+            }
+            if map.blocked_for_radius(x, y, options.agent_radius) {
+                continue;
+            }
+
+            // Walk every step backwards: `from` is a cell that can step onto
+            // `(x, y)` and pays the cost of entering it. Adjacency is
+            // symmetric, so `(x, y)`'s own steps reach every such cell.
+            let enter_cost = map.traversal_cost(x, y);
+            for &(dx, dy) in cell_steps((x, y), options.diagonal, map.hex.as_ref()) {
+                let fx = x as i32 + dx;
+                let fy = y as i32 + dy;
+                if fx < 0 || fy < 0 || fx as usize >= width || fy as usize >= height {
+                    continue;
+                }
+                let from = (fx as usize, fy as usize);
+                if map.is_wall(from.0, from.1) {
+                    continue;
+                }
+
+                let is_diagonal = map.hex.is_none() && dx != 0 && dy != 0;
+                if is_diagonal
+                    && !diagonal_step_allowed(
+                        from,
+                        (x, y),
+                        width,
+                        height,
+                        options.diagonal,
+                        &blocked_fn,
+                    )
+                {
+                    continue;
+                }
+
+                let base = if is_diagonal {
+                    DIAGONAL_STEP_COST
+                } else {
+                    STRAIGHT_STEP_COST
+                };
+                let candidate = dist + weighted_step_cost(base, enter_cost);
+                let from_idx = field.idx(from);
+                if field.distance[from_idx].is_none_or(|d| candidate < d) {
+                    field.distance[from_idx] = Some(candidate);
+                    field.next[from_idx] = Some((x, y));
+                    open.push(Reverse((candidate, from.1, from.0)));
+                }
+            }
+        }
+
+        field
+    }
+
+    pub fn goal(&self) -> (usize, usize) {
+        self.goal
+    }
+
+    /// Cost to reach the goal from the cell, in search cost units.
+    pub fn distance(&self, x: usize, y: usize) -> Option<i32> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.distance[self.idx((x, y))]
+    }
+
+    /// The neighbor to step onto from the cell. `None` at the goal and on
+    /// cells that cannot reach it.
+    pub fn next_cell(&self, x: usize, y: usize) -> Option<(usize, usize)> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.next[self.idx((x, y))]
+    }
+
+    /// Unit world-space direction from the cell's center to its next step.
+    pub fn direction_with_map(
+        &self,
+        map: &RuntimeMapAdapter,
+        x: usize,
+        y: usize,
+    ) -> Option<(f32, f32)> {
+        let (nx, ny) = self.next_cell(x, y)?;
+        let (from_x, from_y) = map.grid_to_world(x, y);
+        let (to_x, to_y) = map.grid_to_world(nx, ny);
+        let dx = to_x - from_x;
+        let dy = to_y - from_y;
+        let len = (dx * dx + dy * dy).sqrt();
+        Some((dx / len, dy / len))
+    }
+
+    #[inline]
+    fn idx(&self, cell: (usize, usize)) -> usize {
+        cell.1 * self.width + cell.0
+    }
+}
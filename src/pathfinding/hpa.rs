@@ -0,0 +1,515 @@
+//! Hierarchical pathfinding (HPA*) over square clusters. Paths connect the
+//! same cells plain A* can, but are not guaranteed to be the cheapest.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use super::{
+    DIAGONAL_STEP_COST, GridSpace, PathFailure, PathOptions, PathResult, PathfinderWorkspace,
+    STRAIGHT_STEP_COST, SearchSpace, astar_impl, astar_with_map_options, diagonal_step_allowed,
+    heuristic, path_cost_with_map, trivial_result, weighted_step_cost,
+};
+use crate::map::RuntimeMapAdapter;
+
+/// Border runs at least this long get an entrance at each end instead of a
+/// single one in the middle.
+const LONG_ENTRANCE_LEN: usize = 6;
+
+#[derive(Clone, Debug)]
+struct AbstractEdge {
+    to: usize,
+    cost: i32,
+    /// Cell path from the edge's source node to `to`, both ends included.
+    path: Vec<(usize, usize)>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct ClusterBounds {
+    x0: usize,
+    y0: usize,
+    x1: usize,
+    y1: usize,
+}
+
+/// The grid cut down to one cluster, indexed locally so the search buffers
+/// only cover the cluster.
+struct ClusterSpace<'a> {
+    grid: GridSpace<'a>,
+    bounds: ClusterBounds,
+}
+
+impl ClusterSpace<'_> {
+    fn contains(&self, (x, y): (usize, usize)) -> bool {
+        let ClusterBounds { x0, y0, x1, y1 } = self.bounds;
+        (x0..x1).contains(&x) && (y0..y1).contains(&y)
+    }
+}
+
+impl SearchSpace for ClusterSpace<'_> {
+    type Node = (usize, usize);
+
+    fn node_count(&self) -> usize {
+        let ClusterBounds { x0, y0, x1, y1 } = self.bounds;
+        (x1 - x0) * (y1 - y0)
+    }
+
+    fn index(&self, (x, y): (usize, usize)) -> usize {
+        let ClusterBounds { x0, y0, x1, .. } = self.bounds;
+        (y - y0) * (x1 - x0) + (x - x0)
+    }
+
+    fn node(&self, index: usize) -> (usize, usize) {
+        let ClusterBounds { x0, y0, x1, .. } = self.bounds;
+        (x0 + index % (x1 - x0), y0 + index / (x1 - x0))
+    }
+
+    fn passable(&self, node: (usize, usize)) -> bool {
+        self.contains(node) && self.grid.passable(node)
+    }
+
+    fn neighbors(&self, node: (usize, usize), out: &mut Vec<(usize, usize)>) {
+        let first = out.len();
+        self.grid.neighbors(node, out);
+        let mut i = first;
+        while i < out.len() {
+            if self.contains(out[i]) {
+                i += 1;
+            } else {
+                out.swap_remove(i);
+            }
+        }
+    }
+
+    fn step_cost(&self, from: (usize, usize), to: (usize, usize)) -> i32 {
+        self.grid.step_cost(from, to)
+    }
+
+    fn heuristic(&self, from: (usize, usize), to: (usize, usize)) -> i32 {
+        self.grid.heuristic(from, to)
+    }
+}
+
+/// Precomputed cluster graph for one map and one set of path options.
+#[derive(Clone, Debug)]
+pub struct HpaGraph {
+    cluster_size: usize,
+    clusters_x: usize,
+    clusters_y: usize,
+    width: usize,
+    height: usize,
+    options: PathOptions,
+    nodes: Vec<(usize, usize)>,
+    node_at: HashMap<(usize, usize), usize>,
+    edges: Vec<Vec<AbstractEdge>>,
+    cluster_nodes: Vec<Vec<usize>>,
+}
+
+impl HpaGraph {
+    pub fn build(map: &RuntimeMapAdapter, cluster_size: usize, options: &PathOptions) -> Self {
+        assert!(cluster_size > 0, "cluster size must be positive");
+
+        let clusters_x = map.width.div_ceil(cluster_size);
+        let clusters_y = map.height.div_ceil(cluster_size);
+
+        let mut graph = Self {
+            cluster_size,
+            clusters_x,
+            clusters_y,
+            width: map.width,
+            height: map.height,
+            options: *options,
+            nodes: Vec::new(),
+            node_at: HashMap::new(),
+            edges: Vec::new(),
+            cluster_nodes: vec![Vec::new(); clusters_x * clusters_y],
+        };
+
+        for cy in 0..clusters_y {
+            for cx in 0..clusters_x {
+                let bounds = graph.bounds(cx, cy);
+                if cx + 1 < clusters_x {
+                    let a = (bounds.y0..bounds.y1).map(|y| (bounds.x1 - 1, y)).collect();
+                    let b = (bounds.y0..bounds.y1).map(|y| (bounds.x1, y)).collect();
+                    graph.add_border_entrances(map, a, b);
+                }
+                if cy + 1 < clusters_y {
+                    let a = (bounds.x0..bounds.x1).map(|x| (x, bounds.y1 - 1)).collect();
+                    let b = (bounds.x0..bounds.x1).map(|x| (x, bounds.y1)).collect();
+                    graph.add_border_entrances(map, a, b);
+                }
+                if cx + 1 < clusters_x && cy + 1 < clusters_y {
+                    graph.add_corner_transitions(map, bounds.x1, bounds.y1);
+                }
+            }
+        }
+
+        let mut workspace = PathfinderWorkspace::new();
+        for cluster in 0..graph.cluster_nodes.len() {
+            graph.link_cluster_nodes(map, &mut workspace, cluster);
+        }
+
+        graph
+    }
+
+    pub fn cluster_size(&self) -> usize {
+        self.cluster_size
+    }
+
+    pub fn cluster_count(&self) -> usize {
+        self.clusters_x * self.clusters_y
+    }
+
+    /// Number of entrance nodes in the abstract graph.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edges.iter().map(Vec::len).sum()
+    }
+
+    /// `expanded` counts abstract nodes. On hex maps this is plain A*.
+    pub fn find_path_with_map(
+        &self,
+        map: &RuntimeMapAdapter,
+        start: (usize, usize),
+        goal: (usize, usize),
+    ) -> PathResult {
+        debug_assert!(
+            map.width == self.width && map.height == self.height,
+            "HPA graph was built for a different map"
+        );
+
+        if map.hex.is_some() {
+            return astar_with_map_options(map, start, goal, &self.options);
+        }
+
+        if let Some(result) = trivial_result(start, goal, map.width * map.height, false, |c| {
+            !map.is_wall(c.0, c.1)
+        }) {
+            return result;
+        }
+
+        let mut workspace = PathfinderWorkspace::new();
+        let start_cluster = self.cluster_of(start.0, start.1);
+        let goal_cluster = self.cluster_of(goal.0, goal.1);
+        if start_cluster == goal_cluster {
+            let local = self.local_path(map, &mut workspace, start_cluster, start, goal);
+            if !local.is_empty() {
+                return PathResult {
+                    cost: path_cost_with_map(map, &local),
+                    cells: local,
+                    ..PathResult::default()
+                };
+            }
+        }
+
+        let start_node = self.nodes.len();
+        let goal_node = start_node + 1;
+
+        let start_edges: Vec<AbstractEdge> = self.cluster_nodes[start_cluster]
+            .iter()
+            .filter_map(|&n| self.local_edge(map, &mut workspace, start_cluster, start, n))
+            .collect();
+        let goal_edges: HashMap<usize, AbstractEdge> = self.cluster_nodes[goal_cluster]
+            .iter()
+            .filter_map(|&n| {
+                let path = self.local_path(map, &mut workspace, goal_cluster, self.nodes[n], goal);
+                if path.is_empty() {
+                    return None;
+                }
+                let edge = AbstractEdge {
+                    to: goal_node,
+                    cost: path_cost_with_map(map, &path),
+                    path,
+                };
+                Some((n, edge))
+            })
+            .collect();
+
+        let node_cell = |n: usize| -> (usize, usize) {
+            if n == start_node {
+                start
+            } else if n == goal_node {
+                goal
+            } else {
+                self.nodes[n]
+            }
+        };
+        let cost_floor = map.min_traversal_cost();
+        let h = |n: usize| heuristic(node_cell(n), goal, self.options.diagonal, cost_floor);
+
+        let node_total = self.nodes.len() + 2;
+        let inf = i32::MAX / 4;
+        let mut g_score = vec![inf; node_total];
+        let mut came_from: Vec<Option<&AbstractEdge>> = vec![None; node_total];
+        let mut parent = vec![usize::MAX; node_total];
+        let mut closed = vec![false; node_total];
+        let mut open = BinaryHeap::new();
+
+        g_score[start_node] = 0;
+        open.push(Reverse((h(start_node), 0, start_node)));
+
+        let mut expanded = 0;
+        while let Some(Reverse((_, g, current))) = open.pop() {
+            if closed[current] {
+                continue;
+            }
+            closed[current] = true;
+            expanded += 1;
+
+            if current == goal_node {
+                return PathResult {
+                    cells: self.refine(&came_from, &parent, start_node, goal_node),
+                    cost: g,
+                    expanded,
+                    ..PathResult::default()
+                };
+            }
+
+            let outgoing: Box<dyn Iterator<Item = &AbstractEdge>> = if current == start_node {
+                Box::new(start_edges.iter())
+            } else {
+                Box::new(self.edges[current].iter().chain(goal_edges.get(&current)))
+            };
+
+            for edge in outgoing {
+                if closed[edge.to] {
+                    continue;
+                }
+                let tentative_g = g + edge.cost;
+                if tentative_g < g_score[edge.to] {
+                    g_score[edge.to] = tentative_g;
+                    came_from[edge.to] = Some(edge);
+                    parent[edge.to] = current;
+                    open.push(Reverse((tentative_g + h(edge.to), tentative_g, edge.to)));
+                }
+            }
+        }
+
+        PathResult {
+            expanded,
+            ..PathResult::failed(PathFailure::Unreachable)
+        }
+    }
+
+    fn refine(
+        &self,
+        came_from: &[Option<&AbstractEdge>],
+        parent: &[usize],
+        start_node: usize,
+        goal_node: usize,
+    ) -> Vec<(usize, usize)> {
+        let mut segments = Vec::new();
+        let mut cur = goal_node;
+        while cur != start_node {
+            let Some(edge) = came_from[cur] else {
+                return vec![];
+            };
+            segments.push(&edge.path);
+            cur = parent[cur];
+        }
+
+        let mut path: Vec<(usize, usize)> = Vec::new();
+        for segment in segments.into_iter().rev() {
+            let skip = usize::from(path.last() == segment.first());
+            path.extend(segment.iter().skip(skip));
+        }
+        path
+    }
+
+    fn bounds(&self, cx: usize, cy: usize) -> ClusterBounds {
+        let x0 = cx * self.cluster_size;
+        let y0 = cy * self.cluster_size;
+        ClusterBounds {
+            x0,
+            y0,
+            x1: (x0 + self.cluster_size).min(self.width),
+            y1: (y0 + self.cluster_size).min(self.height),
+        }
+    }
+
+    fn cluster_of(&self, x: usize, y: usize) -> usize {
+        (y / self.cluster_size) * self.clusters_x + x / self.cluster_size
+    }
+
+    fn cluster_bounds(&self, cluster: usize) -> ClusterBounds {
+        self.bounds(cluster % self.clusters_x, cluster / self.clusters_x)
+    }
+
+    fn node_for(&mut self, cell: (usize, usize)) -> usize {
+        if let Some(&n) = self.node_at.get(&cell) {
+            return n;
+        }
+
+        let n = self.nodes.len();
+        self.nodes.push(cell);
+        self.node_at.insert(cell, n);
+        self.edges.push(Vec::new());
+        let cluster = self.cluster_of(cell.0, cell.1);
+        self.cluster_nodes[cluster].push(n);
+        n
+    }
+
+    fn add_transition(&mut self, map: &RuntimeMapAdapter, a: (usize, usize), b: (usize, usize)) {
+        let base = if a.0 != b.0 && a.1 != b.1 {
+            DIAGONAL_STEP_COST
+        } else {
+            STRAIGHT_STEP_COST
+        };
+        let na = self.node_for(a);
+        let nb = self.node_for(b);
+
+        self.edges[na].push(AbstractEdge {
+            to: nb,
+            cost: weighted_step_cost(base, map.traversal_cost(b.0, b.1)),
+            path: vec![a, b],
+        });
+        self.edges[nb].push(AbstractEdge {
+            to: na,
+            cost: weighted_step_cost(base, map.traversal_cost(a.0, a.1)),
+            path: vec![b, a],
+        });
+    }
+
+    /// `a[i]` and `b[i]` are orthogonal neighbors on either side of a border.
+    fn add_border_entrances(
+        &mut self,
+        map: &RuntimeMapAdapter,
+        a: Vec<(usize, usize)>,
+        b: Vec<(usize, usize)>,
+    ) {
+        let radius = self.options.agent_radius;
+        let open = |cell: (usize, usize)| !map.blocked_for_radius(cell.0, cell.1, radius);
+        let crossing: Vec<bool> = a
+            .iter()
+            .zip(&b)
+            .map(|(&a, &b)| open(a) && open(b))
+            .collect();
+
+        let mut i = 0;
+        while i < crossing.len() {
+            if !crossing[i] {
+                i += 1;
+                continue;
+            }
+            let run_start = i;
+            while i < crossing.len() && crossing[i] {
+                i += 1;
+            }
+            let run_end = i - 1;
+
+            if run_end - run_start + 1 >= LONG_ENTRANCE_LEN {
+                self.add_transition(map, a[run_start], b[run_start]);
+                self.add_transition(map, a[run_end], b[run_end]);
+            } else {
+                let mid = (run_start + run_end) / 2;
+                self.add_transition(map, a[mid], b[mid]);
+            }
+        }
+
+        // Corner-cutting rules can cross a border diagonally where no straight
+        // crossing exists nearby.
+        for i in 0..a.len() {
+            for j in [i.wrapping_sub(1), i + 1] {
+                if j >= b.len() || crossing[i] || crossing[j] {
+                    continue;
+                }
+                if open(a[i])
+                    && open(b[j])
+                    && diagonal_step_allowed(
+                        a[i],
+                        b[j],
+                        self.width,
+                        self.height,
+                        self.options.diagonal,
+                        &|x, y| map.blocked_for_radius(x, y, radius),
+                    )
+                {
+                    self.add_transition(map, a[i], b[j]);
+                }
+            }
+        }
+    }
+
+    /// Diagonal steps between the four clusters meeting at `(x, y)`. Only
+    /// needed when both side cells are blocked; otherwise the border
+    /// entrances already connect the two cells.
+    fn add_corner_transitions(&mut self, map: &RuntimeMapAdapter, x: usize, y: usize) {
+        let radius = self.options.agent_radius;
+        let open = |cell: (usize, usize)| !map.blocked_for_radius(cell.0, cell.1, radius);
+        for (a, b) in [((x - 1, y - 1), (x, y)), ((x, y - 1), (x - 1, y))] {
+            if open(a)
+                && open(b)
+                && !open((a.0, b.1))
+                && !open((b.0, a.1))
+                && diagonal_step_allowed(
+                    a,
+                    b,
+                    self.width,
+                    self.height,
+                    self.options.diagonal,
+                    &|x, y| map.blocked_for_radius(x, y, radius),
+                )
+            {
+                self.add_transition(map, a, b);
+            }
+        }
+    }
+
+    fn link_cluster_nodes(
+        &mut self,
+        map: &RuntimeMapAdapter,
+        workspace: &mut PathfinderWorkspace,
+        cluster: usize,
+    ) {
+        let members = self.cluster_nodes[cluster].clone();
+        for &from in &members {
+            for &to in &members {
+                if from == to {
+                    continue;
+                }
+                if let Some(edge) = self.local_edge(map, workspace, cluster, self.nodes[from], to) {
+                    self.edges[from].push(edge);
+                }
+            }
+        }
+    }
+
+    fn local_edge(
+        &self,
+        map: &RuntimeMapAdapter,
+        workspace: &mut PathfinderWorkspace,
+        cluster: usize,
+        from: (usize, usize),
+        to: usize,
+    ) -> Option<AbstractEdge> {
+        let path = self.local_path(map, workspace, cluster, from, self.nodes[to]);
+        if path.is_empty() {
+            return None;
+        }
+
+        Some(AbstractEdge {
+            to,
+            cost: path_cost_with_map(map, &path),
+            path,
+        })
+    }
+
+    /// A* confined to one cluster. Never falls back to a partial path, which
+    /// would make an edge that stops short of its entrance.
+    fn local_path(
+        &self,
+        map: &RuntimeMapAdapter,
+        workspace: &mut PathfinderWorkspace,
+        cluster: usize,
+        start: (usize, usize),
+        goal: (usize, usize),
+    ) -> Vec<(usize, usize)> {
+        let space = ClusterSpace {
+            grid: GridSpace::new(map, &self.options),
+            bounds: self.cluster_bounds(cluster),
+        };
+        astar_impl(workspace, &space, start, goal, false).cells
+    }
+}
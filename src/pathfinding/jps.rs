@@ -0,0 +1,354 @@
+//! Jump Point Search (Harabor & Grastien) over uniform-cost grids, with
+//! forced-neighbour rules per [`DiagonalMovement`] so costs match plain A*.
+
+use super::{
+    DiagonalMovement, Node, PathFailure, PathOptions, PathResult, PathfinderWorkspace, heuristic,
+    octile, reconstruct_path, trivial_result,
+};
+
+type Cell = (isize, isize);
+
+struct JumpGrid<'a, FBlocked> {
+    width: usize,
+    height: usize,
+    diagonal: DiagonalMovement,
+    goal: Cell,
+    blocked_fn: &'a FBlocked,
+}
+
+impl<FBlocked> JumpGrid<'_, FBlocked>
+where
+    FBlocked: Fn(usize, usize) -> bool,
+{
+    fn walkable(&self, x: isize, y: isize) -> bool {
+        x >= 0
+            && y >= 0
+            && (x as usize) < self.width
+            && (y as usize) < self.height
+            && !(self.blocked_fn)(x as usize, y as usize)
+    }
+
+    /// Successor directions of `(x, y)` after pruning, given the direction we
+    /// arrived from. The start node (no parent) keeps every legal step.
+    fn pruned_dirs(&self, x: isize, y: isize, parent: Option<Cell>) -> Vec<Cell> {
+        let Some((px, py)) = parent else {
+            return self.natural_dirs(x, y);
+        };
+
+        let dx = (x - px).signum();
+        let dy = (y - py).signum();
+        let w = |ox: isize, oy: isize| self.walkable(x + ox, y + oy);
+        let mut dirs = Vec::with_capacity(5);
+
+        match self.diagonal {
+            DiagonalMovement::Never => {
+                if dx != 0 {
+                    dirs.extend([(0, -1), (0, 1), (dx, 0)]);
+                } else {
+                    dirs.extend([(-1, 0), (1, 0), (0, dy)]);
+                }
+            }
+            DiagonalMovement::OnlyWhenNoObstacles => {
+                if dx != 0 && dy != 0 {
+                    dirs.extend([(0, dy), (dx, 0)]);
+                    if w(0, dy) && w(dx, 0) {
+                        dirs.push((dx, dy));
+                    }
+                } else if dx != 0 {
+                    let next = w(dx, 0);
+                    let down = w(0, 1);
+                    let up = w(0, -1);
+                    if next {
+                        dirs.push((dx, 0));
+                        if down {
+                            dirs.push((dx, 1));
+                        }
+                        if up {
+                            dirs.push((dx, -1));
+                        }
+                    }
+                    dirs.extend([(0, 1), (0, -1)]);
+                } else {
+                    let next = w(0, dy);
+                    let right = w(1, 0);
+                    let left = w(-1, 0);
+                    if next {
+                        dirs.push((0, dy));
+                        if right {
+                            dirs.push((1, dy));
+                        }
+                        if left {
+                            dirs.push((-1, dy));
+                        }
+                    }
+                    dirs.extend([(1, 0), (-1, 0)]);
+                }
+            }
+            DiagonalMovement::IfAtMostOneObstacle => {
+                if dx != 0 && dy != 0 {
+                    dirs.extend([(0, dy), (dx, 0)]);
+                    if w(0, dy) || w(dx, 0) {
+                        dirs.push((dx, dy));
+                    }
+                    if !w(-dx, 0) && w(0, dy) {
+                        dirs.push((-dx, dy));
+                    }
+                    if !w(0, -dy) && w(dx, 0) {
+                        dirs.push((dx, -dy));
+                    }
+                } else if dx != 0 {
+                    if w(dx, 0) {
+                        dirs.push((dx, 0));
+                        if !w(0, 1) {
+                            dirs.push((dx, 1));
+                        }
+                        if !w(0, -1) {
+                            dirs.push((dx, -1));
+                        }
+                    }
+                } else if w(0, dy) {
+                    dirs.push((0, dy));
+                    if !w(1, 0) {
+                        dirs.push((1, dy));
+                    }
+                    if !w(-1, 0) {
+                        dirs.push((-1, dy));
+                    }
+                }
+            }
+            DiagonalMovement::Always => {
+                if dx != 0 && dy != 0 {
+                    dirs.extend([(0, dy), (dx, 0), (dx, dy)]);
+                    if !w(-dx, 0) {
+                        dirs.push((-dx, dy));
+                    }
+                    if !w(0, -dy) {
+                        dirs.push((dx, -dy));
+                    }
+                } else if dx != 0 {
+                    dirs.push((dx, 0));
+                    if !w(0, 1) {
+                        dirs.push((dx, 1));
+                    }
+                    if !w(0, -1) {
+                        dirs.push((dx, -1));
+                    }
+                } else {
+                    dirs.push((0, dy));
+                    if !w(1, 0) {
+                        dirs.push((1, dy));
+                    }
+                    if !w(-1, 0) {
+                        dirs.push((-1, dy));
+                    }
+                }
+            }
+        }
+
+        dirs
+    }
+
+    fn natural_dirs(&self, x: isize, y: isize) -> Vec<Cell> {
+        super::neighbor_dirs(self.diagonal)
+            .iter()
+            .map(|&(dx, dy)| (dx as isize, dy as isize))
+            .filter(|&(dx, dy)| self.step_allowed(x, y, dx, dy))
+            .collect()
+    }
+
+    fn step_allowed(&self, x: isize, y: isize, dx: isize, dy: isize) -> bool {
+        if !self.walkable(x + dx, y + dy) {
+            return false;
+        }
+        if dx == 0 || dy == 0 {
+            return true;
+        }
+
+        let a = self.walkable(x + dx, y);
+        let b = self.walkable(x, y + dy);
+        match self.diagonal {
+            DiagonalMovement::Never => false,
+            DiagonalMovement::Always => true,
+            DiagonalMovement::IfAtMostOneObstacle => a || b,
+            DiagonalMovement::OnlyWhenNoObstacles => a && b,
+        }
+    }
+
+    /// Walks from `from` in direction `(dx, dy)` and returns the first jump
+    /// point, or `None` if the walk runs into a wall.
+    fn jump(&self, from: Cell, dx: isize, dy: isize) -> Option<Cell> {
+        let (mut x, mut y) = from;
+
+        loop {
+            if !self.step_allowed(x, y, dx, dy) {
+                return None;
+            }
+            x += dx;
+            y += dy;
+
+            if (x, y) == self.goal || self.has_forced_neighbor(x, y, dx, dy) {
+                return Some((x, y));
+            }
+
+            let diagonal_move = dx != 0 && dy != 0;
+            if diagonal_move
+                && (self.jump((x, y), dx, 0).is_some() || self.jump((x, y), 0, dy).is_some())
+            {
+                return Some((x, y));
+            }
+            if self.diagonal == DiagonalMovement::Never
+                && dx == 0
+                && (self.jump((x, y), 1, 0).is_some() || self.jump((x, y), -1, 0).is_some())
+            {
+                return Some((x, y));
+            }
+        }
+    }
+
+    fn has_forced_neighbor(&self, x: isize, y: isize, dx: isize, dy: isize) -> bool {
+        let w = |ox: isize, oy: isize| self.walkable(x + ox, y + oy);
+
+        match self.diagonal {
+            DiagonalMovement::Never | DiagonalMovement::OnlyWhenNoObstacles => {
+                if dx != 0 && dy != 0 {
+                    false
+                } else if dx != 0 {
+                    (w(0, -1) && !w(-dx, -1)) || (w(0, 1) && !w(-dx, 1))
+                } else {
+                    (w(-1, 0) && !w(-1, -dy)) || (w(1, 0) && !w(1, -dy))
+                }
+            }
+            DiagonalMovement::IfAtMostOneObstacle | DiagonalMovement::Always => {
+                if dx != 0 && dy != 0 {
+                    (w(-dx, dy) && !w(-dx, 0)) || (w(dx, -dy) && !w(0, -dy))
+                } else if dx != 0 {
+                    (w(dx, 1) && !w(0, 1)) || (w(dx, -1) && !w(0, -1))
+                } else {
+                    (w(1, dy) && !w(1, 0)) || (w(-1, dy) && !w(-1, 0))
+                }
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(super) fn jps_impl<FIsWall, FBlocked>(
+    workspace: &mut PathfinderWorkspace,
+    start: (usize, usize),
+    goal: (usize, usize),
+    width: usize,
+    height: usize,
+    options: &PathOptions,
+    is_wall_fn: FIsWall,
+    blocked_fn: FBlocked,
+) -> PathResult
+where
+    FIsWall: Fn(usize, usize) -> bool,
+    FBlocked: Fn(usize, usize) -> bool,
+{
+    if let Some(result) = trivial_result(start, goal, width * height, false, |(x, y)| {
+        !is_wall_fn(x, y)
+    }) {
+        return result;
+    }
+
+    let grid = JumpGrid {
+        width,
+        height,
+        diagonal: options.diagonal,
+        goal: (goal.0 as isize, goal.1 as isize),
+        blocked_fn: &blocked_fn,
+    };
+    let idx = |x: usize, y: usize| -> usize { y * width + x };
+
+    let cell_count = width * height;
+    workspace.begin(cell_count);
+    let mut expanded = 0;
+
+    workspace.set(idx(start.0, start.1), 0, None);
+
+    workspace.open.push(Node {
+        idx: idx(start.0, start.1),
+        g: 0,
+        f: heuristic(start, goal, options.diagonal, 1.0),
+    });
+
+    while let Some(current) = workspace.open.pop() {
+        let current_idx = current.idx;
+        let (cx, cy) = (current_idx % width, current_idx / width);
+
+        if workspace.is_closed(current_idx) {
+            continue;
+        }
+        workspace.close(current_idx);
+        expanded += 1;
+
+        if (cx, cy) == goal {
+            let jump_points = reconstruct_path(workspace, width, start, goal);
+            return PathResult {
+                cells: expand_jump_points(&jump_points),
+                cost: workspace.g(current_idx),
+                expanded,
+                failure: None,
+                any_angle: false,
+            };
+        }
+
+        let current_g = workspace.g(current_idx);
+        let parent = workspace
+            .came_from(current_idx)
+            .map(|p| ((p % width) as isize, (p / width) as isize));
+        let here = (cx as isize, cy as isize);
+
+        for (dx, dy) in grid.pruned_dirs(here.0, here.1, parent) {
+            let Some((jx, jy)) = grid.jump(here, dx, dy) else {
+                continue;
+            };
+            let jump_cell = (jx as usize, jy as usize);
+            let jump_idx = idx(jump_cell.0, jump_cell.1);
+            if workspace.is_closed(jump_idx) {
+                continue;
+            }
+
+            let tentative_g = current_g + octile((cx, cy), jump_cell);
+            if tentative_g < workspace.g(jump_idx) {
+                workspace.set(jump_idx, tentative_g, Some(current_idx));
+
+                let h = heuristic(jump_cell, goal, options.diagonal, 1.0);
+                workspace.open.push(Node {
+                    idx: jump_idx,
+                    g: tentative_g,
+                    f: tentative_g + h,
+                });
+            }
+        }
+    }
+
+    PathResult {
+        expanded,
+        ..PathResult::failed(PathFailure::Unreachable)
+    }
+}
+
+/// Fills in the cells between consecutive jump points. Every segment is a
+/// straight or a pure diagonal run, so stepping by the sign of the remaining
+/// offset reproduces it exactly.
+fn expand_jump_points(jump_points: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    if jump_points.is_empty() {
+        return vec![];
+    }
+    let mut path = Vec::with_capacity(jump_points.len());
+    path.push(jump_points[0]);
+
+    for w in jump_points.windows(2) {
+        let (mut x, mut y) = (w[0].0 as isize, w[0].1 as isize);
+        let (tx, ty) = (w[1].0 as isize, w[1].1 as isize);
+        while (x, y) != (tx, ty) {
+            x += (tx - x).signum();
+            y += (ty - y).signum();
+            path.push((x as usize, y as usize));
+        }
+    }
+
+    path
+}
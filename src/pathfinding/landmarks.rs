@@ -0,0 +1,350 @@
+//! ALT heuristic: lower bounds such as `d(L, goal) - d(L, cell)` from exact
+//! distances to a few landmark cells.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::{AStarRun, DiagonalMovement, FlowField, GridSpace, PathOptions, PathfinderWorkspace};
+use crate::map::RuntimeMapAdapter;
+
+/// Bumped whenever the file layout or the distance semantics change.
+const FORMAT_VERSION: u32 = 2;
+
+/// Distance entry for a cell the landmark cannot reach, or that cannot reach
+/// the landmark.
+const UNREACHABLE: i32 = -1;
+
+/// Precomputed landmark distances for one map and one set of movement rules.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LandmarkTable {
+    version: u32,
+    width: usize,
+    height: usize,
+    fingerprint: u64,
+    #[serde(with = "diagonal_code")]
+    diagonal: DiagonalMovement,
+    agent_radius: f32,
+    landmarks: Vec<(usize, usize)>,
+    /// `from[l][cell]`: cost of the cheapest path from landmark `l` to `cell`.
+    from: Vec<Vec<i32>>,
+    /// `to[l][cell]`: cost of the cheapest path from `cell` to landmark `l`.
+    to: Vec<Vec<i32>>,
+}
+
+impl LandmarkTable {
+    /// Only `options.diagonal` and `options.agent_radius` matter; the table
+    /// answers queries made with the same two values.
+    pub fn build(map: &RuntimeMapAdapter, count: usize, options: &PathOptions) -> Self {
+        let mut table = Self {
+            version: FORMAT_VERSION,
+            width: map.width,
+            height: map.height,
+            fingerprint: map_fingerprint(map, options.agent_radius),
+            diagonal: options.diagonal,
+            agent_radius: options.agent_radius,
+            landmarks: Vec::new(),
+            from: Vec::new(),
+            to: Vec::new(),
+        };
+
+        let cell_count = map.width * map.height;
+        let open =
+            |i: usize| !map.blocked_for_radius(i % map.width, i / map.width, options.agent_radius);
+        let Some(seed) = (0..cell_count).find(|&i| open(i)) else {
+            return table;
+        };
+        let mut workspace = PathfinderWorkspace::new();
+
+        // Start from the cell farthest from an arbitrary one, then keep adding
+        // the cell farthest from every landmark chosen so far. Cells no
+        // landmark reaches count as infinitely far, so every connected region
+        // gets a landmark before any region gets a second.
+        let seed = (seed % map.width, seed / map.width);
+        let seed_distances = forward_distances(&mut workspace, map, seed, options);
+        let mut nearest: Vec<i64> = vec![i64::MAX; cell_count];
+        let mut next = farthest(cell_count, &open, |i| seed_distances[i] as i64);
+
+        while let Some(i) = next {
+            if table.landmarks.len() == count {
+                break;
+            }
+            let landmark = (i % map.width, i / map.width);
+            let from = forward_distances(&mut workspace, map, landmark, options);
+            let to = backward_distances(map, landmark, options);
+
+            for (n, &d) in nearest.iter_mut().zip(&from) {
+                if d != UNREACHABLE {
+                    *n = (*n).min(d as i64);
+                }
+            }
+            nearest[i] = -1;
+
+            table.landmarks.push(landmark);
+            table.from.push(from);
+            table.to.push(to);
+            next = farthest(cell_count, &open, |i| nearest[i]);
+        }
+
+        table
+    }
+
+    /// Reads a table written by [`LandmarkTable::save`]. Whether it still fits
+    /// a map is up to [`LandmarkTable::matches_map`].
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, LandmarkError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|source| LandmarkError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let table: Self = serde_json::from_str(&text).map_err(|source| LandmarkError::Json {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        let cells = table.width * table.height;
+        let consistent = table.version == FORMAT_VERSION
+            && table.from.len() == table.landmarks.len()
+            && table.to.len() == table.landmarks.len()
+            && table.from.iter().chain(&table.to).all(|d| d.len() == cells);
+        if !consistent {
+            return Err(LandmarkError::Malformed {
+                path: path.to_path_buf(),
+            });
+        }
+        Ok(table)
+    }
+
+    /// Writes the table to `path`, creating missing parent directories.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), LandmarkError> {
+        let path = path.as_ref();
+        let io_error = |source| LandmarkError::Io {
+            path: path.to_path_buf(),
+            source,
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(io_error)?;
+        }
+        let text = serde_json::to_string(self).map_err(|source| LandmarkError::Json {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        std::fs::write(path, text).map_err(io_error)
+    }
+
+    /// Where the table for a map file is cached: `levels/map.json` pairs
+    /// with `<cache_dir>/map-<hash>.landmarks.json`, where the hash covers the
+    /// map's canonical path so same-named maps in other directories get
+    /// their own file.
+    pub fn cache_path(cache_dir: impl AsRef<Path>, map_path: impl AsRef<Path>) -> PathBuf {
+        let map_path = map_path.as_ref();
+        let full = map_path
+            .canonicalize()
+            .unwrap_or_else(|_| map_path.to_path_buf());
+        let stem = map_path.file_stem().unwrap_or_default().to_string_lossy();
+        let hash = fnv1a(FNV_OFFSET, full.as_os_str().as_encoded_bytes());
+        cache_dir
+            .as_ref()
+            .join(format!("{stem}-{hash:016x}.landmarks.json"))
+    }
+
+    /// Loads the table at `path` if it matches the map and options, otherwise
+    /// builds one and tries to save it there.
+    pub fn load_or_build(
+        path: impl AsRef<Path>,
+        map: &RuntimeMapAdapter,
+        count: usize,
+        options: &PathOptions,
+    ) -> Self {
+        let path = path.as_ref();
+        if let Ok(table) = Self::load(path)
+            && table.matches_map(map)
+            && table.supports(options)
+        {
+            return table;
+        }
+
+        let table = Self::build(map, count, options);
+        let _ = table.save(path);
+        table
+    }
+
+    /// Whether the table was built from a map with exactly these walls,
+    /// costs and collision shapes.
+    pub fn matches_map(&self, map: &RuntimeMapAdapter) -> bool {
+        self.width == map.width
+            && self.height == map.height
+            && self.fingerprint == map_fingerprint(map, self.agent_radius)
+    }
+
+    /// Whether queries made with `options` may use the table. Distances under
+    /// other movement rules can be shorter, which would make the bound
+    /// overestimate.
+    pub fn supports(&self, options: &PathOptions) -> bool {
+        self.diagonal == options.diagonal
+            && self.agent_radius.to_bits() == options.agent_radius.to_bits()
+    }
+
+    pub fn landmarks(&self) -> &[(usize, usize)] {
+        &self.landmarks
+    }
+
+    /// Lower bound on the cost of any path from `from` to `to`. Zero when no
+    /// landmark says anything about the pair.
+    pub fn lower_bound(&self, from: (usize, usize), to: (usize, usize)) -> i32 {
+        let v = from.1 * self.width + from.0;
+        let t = to.1 * self.width + to.0;
+        let mut best = 0;
+        for (from_l, to_l) in self.from.iter().zip(&self.to) {
+            if from_l[v] != UNREACHABLE && from_l[t] != UNREACHABLE {
+                best = best.max(from_l[t] - from_l[v]);
+            }
+            if to_l[v] != UNREACHABLE && to_l[t] != UNREACHABLE {
+                best = best.max(to_l[v] - to_l[t]);
+            }
+        }
+        best
+    }
+}
+
+/// Open cell with the highest `distance`, lowest index first on ties. Cells
+/// with a negative distance are never picked.
+fn farthest(
+    cell_count: usize,
+    open: &dyn Fn(usize) -> bool,
+    distance: impl Fn(usize) -> i64,
+) -> Option<usize> {
+    (0..cell_count)
+        .filter(|&i| open(i) && distance(i) >= 0)
+        .max_by_key(|&i| (distance(i), std::cmp::Reverse(i)))
+}
+
+/// Cost from `source` to every cell, exactly as A* would charge it.
+fn forward_distances(
+    workspace: &mut PathfinderWorkspace,
+    map: &RuntimeMapAdapter,
+    source: (usize, usize),
+    options: &PathOptions,
+) -> Vec<i32> {
+    let options = options.with_closest_fallback(false);
+    let space = GridSpace::new(map, &options);
+    let mut run = AStarRun::begin(workspace, &space, source, 0, false, false);
+    let _ = run.resume(workspace, &space, usize::MAX, |_| false, |_| 0);
+
+    let inf = i32::MAX / 4;
+    (0..map.width * map.height)
+        .map(|i| match workspace.g(i) {
+            g if g >= inf => UNREACHABLE,
+            g => g,
+        })
+        .collect()
+}
+
+/// Cost from every cell to `target`.
+fn backward_distances(
+    map: &RuntimeMapAdapter,
+    target: (usize, usize),
+    options: &PathOptions,
+) -> Vec<i32> {
+    let field = FlowField::from_map(map, target, options);
+    (0..map.width * map.height)
+        .map(|i| {
+            field
+                .distance(i % map.width, i / map.width)
+                .unwrap_or(UNREACHABLE)
+        })
+        .collect()
+}
+
+/// FNV-1a over every input of the map's movement rules, including the agent
+/// radius the clearance checks were made for.
+fn map_fingerprint(map: &RuntimeMapAdapter, agent_radius: f32) -> u64 {
+    let mut hash = FNV_OFFSET;
+    let mut feed = |bytes: &[u8]| hash = fnv1a(hash, bytes);
+    map.feed_collision_inputs(&mut feed);
+    feed(&agent_radius.to_bits().to_le_bytes());
+    hash
+}
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for &b in bytes {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+mod diagonal_code {
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    use crate::pathfinding::DiagonalMovement;
+
+    pub fn serialize<S: Serializer>(diagonal: &DiagonalMovement, s: S) -> Result<S::Ok, S::Error> {
+        let code = match diagonal {
+            DiagonalMovement::Never => "never",
+            DiagonalMovement::Always => "always",
+            DiagonalMovement::IfAtMostOneObstacle => "if_at_most_one_obstacle",
+            DiagonalMovement::OnlyWhenNoObstacles => "only_when_no_obstacles",
+        };
+        s.serialize_str(code)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<DiagonalMovement, D::Error> {
+        match String::deserialize(d)?.as_str() {
+            "never" => Ok(DiagonalMovement::Never),
+            "always" => Ok(DiagonalMovement::Always),
+            "if_at_most_one_obstacle" => Ok(DiagonalMovement::IfAtMostOneObstacle),
+            "only_when_no_obstacles" => Ok(DiagonalMovement::OnlyWhenNoObstacles),
+            other => Err(D::Error::custom(format!("unknown diagonal mode '{other}'"))),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum LandmarkError {
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    Json {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    /// The file parsed but its sizes disagree with each other, or it was
+    /// written by an incompatible version.
+    Malformed { path: PathBuf },
+}
+
+impl fmt::Display for LandmarkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, source } => {
+                write!(f, "failed to access {}: {}", path.display(), source)
+            }
+            Self::Json { path, source } => {
+                write!(
+                    f,
+                    "failed to parse landmark table {}: {}",
+                    path.display(),
+                    source
+                )
+            }
+            Self::Malformed { path } => {
+                write!(f, "landmark table {} is malformed", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for LandmarkError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            Self::Json { source, .. } => Some(source),
+            Self::Malformed { .. } => None,
+        }
+    }
+}
@@ -0,0 +1,145 @@
+//! A* over any graph that can describe itself through [`SearchSpace`].
+
+use super::{
+    DIAGONAL_STEP_COST, LandmarkTable, PathOptions, PathResult, PathfinderWorkspace,
+    STRAIGHT_STEP_COST, cell_steps, diagonal_step_allowed, landmark_heuristic, weighted_step_cost,
+};
+use crate::map::RuntimeMapAdapter;
+
+/// A graph [`astar_over_space`] can search. Nodes map onto dense indices
+/// below [`SearchSpace::node_count`].
+pub trait SearchSpace {
+    type Node: Copy + Eq;
+
+    fn node_count(&self) -> usize;
+
+    /// Dense index of `node`, unique and below `node_count()`.
+    fn index(&self, node: Self::Node) -> usize;
+
+    /// The node whose index is `index`; the inverse of [`SearchSpace::index`].
+    fn node(&self, index: usize) -> Self::Node;
+
+    /// Whether a path may start or end on `node`.
+    fn passable(&self, node: Self::Node) -> bool;
+
+    /// Appends the nodes one step away from `node` to `out`.
+    fn neighbors(&self, node: Self::Node, out: &mut Vec<Self::Node>);
+
+    /// Cost of the step from `from` to its neighbor `to`. Must be positive.
+    fn step_cost(&self, from: Self::Node, to: Self::Node) -> i32;
+
+    /// Estimate of the cost from `from` to `to`. It must never exceed the
+    /// true cost, or the path found may not be the cheapest; zero is always
+    /// safe.
+    fn heuristic(&self, from: Self::Node, to: Self::Node) -> i32;
+}
+
+/// Cheapest path from `start` to `goal` in `space`. With `closest_fallback`,
+/// an unreachable goal yields the path to the node the heuristic rates closest.
+pub fn astar_over_space<S: SearchSpace>(
+    space: &S,
+    start: S::Node,
+    goal: S::Node,
+    closest_fallback: bool,
+) -> PathResult<S::Node> {
+    PathfinderWorkspace::new().astar_over_space(space, start, goal, closest_fallback)
+}
+
+/// The map grid as a [`SearchSpace`], with the same movement rules, costs
+/// and heuristic as `astar_with_map_options`.
+#[derive(Clone, Copy, Debug)]
+pub struct GridSpace<'a> {
+    map: &'a RuntimeMapAdapter,
+    options: PathOptions,
+    cost_floor: f32,
+    landmarks: Option<&'a LandmarkTable>,
+}
+
+impl<'a> GridSpace<'a> {
+    pub fn new(map: &'a RuntimeMapAdapter, options: &PathOptions) -> Self {
+        Self {
+            map,
+            options: *options,
+            cost_floor: map.min_traversal_cost(),
+            landmarks: map.landmarks().filter(|table| table.supports(options)),
+        }
+    }
+
+    pub fn map(&self) -> &'a RuntimeMapAdapter {
+        self.map
+    }
+}
+
+impl SearchSpace for GridSpace<'_> {
+    type Node = (usize, usize);
+
+    fn node_count(&self) -> usize {
+        self.map.width * self.map.height
+    }
+
+    fn index(&self, node: (usize, usize)) -> usize {
+        node.1 * self.map.width + node.0
+    }
+
+    fn node(&self, index: usize) -> (usize, usize) {
+        (index % self.map.width, index / self.map.width)
+    }
+
+    fn passable(&self, node: (usize, usize)) -> bool {
+        node.0 < self.map.width && node.1 < self.map.height && !self.map.is_wall(node.0, node.1)
+    }
+
+    fn neighbors(&self, node: (usize, usize), out: &mut Vec<(usize, usize)>) {
+        let (width, height) = (self.map.width, self.map.height);
+        let blocked_fn =
+            |x: usize, y: usize| self.map.blocked_for_radius(x, y, self.options.agent_radius);
+
+        let hex = self.map.hex.as_ref();
+        for &(dx, dy) in cell_steps(node, self.options.diagonal, hex) {
+            let nx = node.0 as i32 + dx;
+            let ny = node.1 as i32 + dy;
+            if nx < 0 || ny < 0 || nx as usize >= width || ny as usize >= height {
+                continue;
+            }
+            let next = (nx as usize, ny as usize);
+            if blocked_fn(next.0, next.1) {
+                continue;
+            }
+            if hex.is_none()
+                && dx != 0
+                && dy != 0
+                && !diagonal_step_allowed(
+                    node,
+                    next,
+                    width,
+                    height,
+                    self.options.diagonal,
+                    &blocked_fn,
+                )
+            {
+                continue;
+            }
+            out.push(next);
+        }
+    }
+
+    fn step_cost(&self, from: (usize, usize), to: (usize, usize)) -> i32 {
+        let base = if self.map.hex.is_none() && from.0 != to.0 && from.1 != to.1 {
+            DIAGONAL_STEP_COST
+        } else {
+            STRAIGHT_STEP_COST
+        };
+        weighted_step_cost(base, self.map.traversal_cost(to.0, to.1))
+    }
+
+    fn heuristic(&self, from: (usize, usize), to: (usize, usize)) -> i32 {
+        landmark_heuristic(
+            from,
+            to,
+            self.options.diagonal,
+            self.map.hex.as_ref(),
+            self.cost_floor,
+            self.landmarks,
+        )
+    }
+}
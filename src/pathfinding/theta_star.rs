@@ -0,0 +1,166 @@
+//! Theta* (Nash et al.): A* over the grid where a node may take its
+//! grandparent as parent whenever the two see each other, which yields
+//! any-angle paths made of straight segments.
+
+use super::{
+    DIAGONAL_STEP_COST, Node, PathFailure, PathOptions, PathResult, PathfinderWorkspace,
+    STRAIGHT_STEP_COST, diagonal_step_allowed, neighbor_dirs, reconstruct_path, trivial_result,
+};
+
+/// Straight-line distance in step-cost units.
+fn euclidean(a: (usize, usize), b: (usize, usize)) -> i32 {
+    let dx = a.0.abs_diff(b.0) as f32;
+    let dy = a.1.abs_diff(b.1) as f32;
+    ((dx * dx + dy * dy).sqrt() * STRAIGHT_STEP_COST as f32).round() as i32
+}
+
+/// The result's cells are the turning points of the path, start and goal
+/// included. Consecutive cells are generally not adjacent; each pair is
+/// connected by a segment that `sight_fn` accepted.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn theta_star_impl<FIsWall, FBlocked, FSight>(
+    workspace: &mut PathfinderWorkspace,
+    start: (usize, usize),
+    goal: (usize, usize),
+    width: usize,
+    height: usize,
+    options: &PathOptions,
+    is_wall_fn: FIsWall,
+    blocked_fn: FBlocked,
+    sight_fn: FSight,
+) -> PathResult
+where
+    FIsWall: Fn(usize, usize) -> bool,
+    FBlocked: Fn(usize, usize) -> bool,
+    FSight: Fn((usize, usize), (usize, usize)) -> bool,
+{
+    if let Some(result) = trivial_result(
+        start,
+        goal,
+        width * height,
+        options.closest_fallback,
+        |(x, y)| !is_wall_fn(x, y),
+    ) {
+        return result;
+    }
+
+    let idx = |x: usize, y: usize| -> usize { y * width + x };
+
+    let inf: i32 = i32::MAX / 4;
+
+    let cell_count = width * height;
+    workspace.begin(cell_count);
+    let mut expanded = 0;
+    let mut closest = ((inf, inf), start);
+
+    workspace.set(idx(start.0, start.1), 0, None);
+
+    workspace.open.push(Node {
+        idx: idx(start.0, start.1),
+        g: 0,
+        f: euclidean(start, goal),
+    });
+
+    while let Some(current) = workspace.open.pop() {
+        let current_idx = current.idx;
+        let (cx, cy) = (current_idx % width, current_idx / width);
+
+        if workspace.is_closed(current_idx) {
+            continue;
+        }
+        workspace.close(current_idx);
+        expanded += 1;
+
+        if (cx, cy) == goal {
+            return PathResult {
+                cells: reconstruct_path(workspace, width, start, goal),
+                cost: workspace.g(current_idx),
+                expanded,
+                failure: None,
+                any_angle: true,
+            };
+        }
+
+        let current_g = workspace.g(current_idx);
+        let closeness = (euclidean((cx, cy), goal), current_g);
+        if closeness < closest.0 {
+            closest = (closeness, (cx, cy));
+        }
+        let parent = workspace
+            .came_from(current_idx)
+            .map(|p| (p % width, p / width));
+
+        for &(dx, dy) in neighbor_dirs(options.diagonal) {
+            let nx = cx as i32 + dx;
+            let ny = cy as i32 + dy;
+            if nx < 0 || ny < 0 || nx as usize >= width || ny as usize >= height {
+                continue;
+            }
+
+            let next = (nx as usize, ny as usize);
+            let next_idx = idx(next.0, next.1);
+            if workspace.is_closed(next_idx) || blocked_fn(next.0, next.1) {
+                continue;
+            }
+
+            let is_diagonal = dx != 0 && dy != 0;
+            if is_diagonal
+                && !diagonal_step_allowed(
+                    (cx, cy),
+                    next,
+                    width,
+                    height,
+                    options.diagonal,
+                    &blocked_fn,
+                )
+            {
+                continue;
+            }
+
+            // Path 2: skip the current cell if its parent sees the neighbor.
+            let (via, tentative_g) = match parent {
+                Some(p) if sight_fn(p, next) => {
+                    (p, workspace.g(idx(p.0, p.1)) + euclidean(p, next))
+                }
+                _ => {
+                    let step = if is_diagonal {
+                        DIAGONAL_STEP_COST
+                    } else {
+                        STRAIGHT_STEP_COST
+                    };
+                    ((cx, cy), current_g + step)
+                }
+            };
+
+            if tentative_g < workspace.g(next_idx) {
+                workspace.set(next_idx, tentative_g, Some(idx(via.0, via.1)));
+                workspace.open.push(Node {
+                    idx: next_idx,
+                    g: tentative_g,
+                    f: tentative_g + euclidean(next, goal),
+                });
+            }
+        }
+    }
+
+    let failure = if is_wall_fn(goal.0, goal.1) {
+        PathFailure::GoalInWall
+    } else {
+        PathFailure::Unreachable
+    };
+    if !options.closest_fallback {
+        return PathResult {
+            expanded,
+            ..PathResult::failed(failure)
+        };
+    }
+
+    let (_, end) = closest;
+    PathResult {
+        cells: reconstruct_path(workspace, width, start, end),
+        cost: workspace.g(idx(end.0, end.1)),
+        expanded,
+        failure: Some(failure),
+        any_angle: true,
+    }
+}
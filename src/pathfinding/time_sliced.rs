@@ -0,0 +1,130 @@
+//! A* that can be spread over several frames.
+
+use super::{
+    AStarRun, GridSpace, PathOptions, PathResult, PathfinderWorkspace, SearchAlgorithm,
+    SearchSpace, trivial_result,
+};
+use crate::map::RuntimeMapAdapter;
+
+/// Progress of a [`PathSearch`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum SearchStatus {
+    /// The node budget ran out; call [`PathSearch::step`] again.
+    InProgress,
+    Found(PathResult),
+    /// The goal cannot be reached. The result carries the reason and, with
+    /// `closest_fallback`, the path to the closest reachable cell.
+    Failed(PathResult),
+}
+
+/// Resumable A* query; construction panics unless `options.algorithm` is
+/// [`SearchAlgorithm::AStar`]. Every [`PathSearch::step`] must get the
+/// unchanged map the search started on.
+#[derive(Clone, Debug)]
+pub struct PathSearch {
+    workspace: PathfinderWorkspace,
+    run: Option<AStarRun<(usize, usize)>>,
+    options: PathOptions,
+    start: (usize, usize),
+    goal: (usize, usize),
+    result: Option<PathResult>,
+}
+
+impl PathSearch {
+    pub fn new(
+        map: &RuntimeMapAdapter,
+        start: (usize, usize),
+        goal: (usize, usize),
+        options: &PathOptions,
+    ) -> Self {
+        Self::with_workspace(PathfinderWorkspace::new(), map, start, goal, options)
+    }
+
+    /// Like [`PathSearch::new`], but searches in an existing workspace. Get
+    /// it back with [`PathSearch::into_workspace`].
+    pub fn with_workspace(
+        mut workspace: PathfinderWorkspace,
+        map: &RuntimeMapAdapter,
+        start: (usize, usize),
+        goal: (usize, usize),
+        options: &PathOptions,
+    ) -> Self {
+        assert_eq!(
+            options.algorithm,
+            SearchAlgorithm::AStar,
+            "PathSearch only supports A*"
+        );
+
+        let space = GridSpace::new(map, options);
+        let trivial = trivial_result(
+            start,
+            goal,
+            space.node_count(),
+            options.closest_fallback,
+            |cell| space.passable(cell),
+        );
+        let run = match trivial {
+            Some(_) => None,
+            None => Some(AStarRun::begin(
+                &mut workspace,
+                &space,
+                start,
+                space.heuristic(start, goal),
+                options.closest_fallback,
+                !space.passable(goal),
+            )),
+        };
+
+        Self {
+            workspace,
+            run,
+            options: *options,
+            start,
+            goal,
+            result: trivial,
+        }
+    }
+
+    pub fn start(&self) -> (usize, usize) {
+        self.start
+    }
+
+    pub fn goal(&self) -> (usize, usize) {
+        self.goal
+    }
+
+    /// Nodes expanded so far, over all steps.
+    pub fn expanded(&self) -> usize {
+        match (&self.result, &self.run) {
+            (Some(result), _) => result.expanded,
+            (None, Some(run)) => run.expanded,
+            (None, None) => 0,
+        }
+    }
+
+    /// Expands up to `max_expansions` nodes. Once the search has finished,
+    /// further calls keep returning the same outcome.
+    pub fn step(&mut self, map: &RuntimeMapAdapter, max_expansions: usize) -> SearchStatus {
+        if let (None, Some(run)) = (&self.result, self.run.as_mut()) {
+            let goal = self.goal;
+            let space = GridSpace::new(map, &self.options);
+            self.result = run.resume(
+                &mut self.workspace,
+                &space,
+                max_expansions,
+                |cell| cell == goal,
+                |cell| space.heuristic(cell, goal),
+            );
+        }
+
+        match &self.result {
+            None => SearchStatus::InProgress,
+            Some(result) if result.is_found() => SearchStatus::Found(result.clone()),
+            Some(result) => SearchStatus::Failed(result.clone()),
+        }
+    }
+
+    pub fn into_workspace(self) -> PathfinderWorkspace {
+        self.workspace
+    }
+}
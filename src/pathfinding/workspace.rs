@@ -0,0 +1,126 @@
+//! Search buffers that survive between queries.
+
+use std::collections::BinaryHeap;
+
+use super::{
+    Node, PathOptions, PathResult, SearchAlgorithm, SearchSpace, astar_impl, astar_in,
+    bidirectional_in, jps_in, theta_star_in,
+};
+use crate::map::RuntimeMapAdapter;
+
+const INF: i32 = i32::MAX / 4;
+
+/// Scratch space for grid searches. Per-cell state is tagged with a query
+/// generation, so starting a query never clears the buffers.
+#[derive(Clone, Debug, Default)]
+pub struct PathfinderWorkspace {
+    generation: u32,
+    visited: Vec<u32>,
+    closed: Vec<u32>,
+    g_score: Vec<i32>,
+    came_from: Vec<Option<usize>>,
+    pub(super) open: BinaryHeap<Node>,
+    /// Second set of buffers for the backward half of bidirectional search,
+    /// created on first use.
+    reverse: Option<Box<PathfinderWorkspace>>,
+}
+
+impl PathfinderWorkspace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs the search selected by `options.algorithm` using this
+    /// workspace's buffers.
+    pub fn find_path_with_map(
+        &mut self,
+        map: &RuntimeMapAdapter,
+        start: (usize, usize),
+        goal: (usize, usize),
+        options: &PathOptions,
+    ) -> PathResult {
+        match options.algorithm {
+            SearchAlgorithm::AStar => astar_in(self, map, start, goal, options),
+            SearchAlgorithm::JumpPoint => jps_in(self, map, start, goal, options),
+            SearchAlgorithm::ThetaStar => theta_star_in(self, map, start, goal, options),
+            SearchAlgorithm::Bidirectional => {
+                let mut reverse = self.reverse.take().unwrap_or_default();
+                let result = bidirectional_in(self, &mut reverse, map, start, goal, options);
+                self.reverse = Some(reverse);
+                result
+            }
+        }
+    }
+
+    /// [`astar_over_space`](super::astar_over_space) using this
+    /// workspace's buffers.
+    pub fn astar_over_space<S: SearchSpace>(
+        &mut self,
+        space: &S,
+        start: S::Node,
+        goal: S::Node,
+        closest_fallback: bool,
+    ) -> PathResult<S::Node> {
+        astar_impl(self, space, start, goal, closest_fallback)
+    }
+
+    /// Number of cells the buffers currently cover.
+    pub fn capacity(&self) -> usize {
+        self.visited.len()
+    }
+
+    /// Invalidates the previous query's state and makes room for
+    /// `cell_count` cells.
+    pub(super) fn begin(&mut self, cell_count: usize) {
+        if self.visited.len() < cell_count {
+            self.visited.resize(cell_count, 0);
+            self.closed.resize(cell_count, 0);
+            self.g_score.resize(cell_count, INF);
+            self.came_from.resize(cell_count, None);
+        }
+        self.open.clear();
+
+        self.generation = self.generation.wrapping_add(1);
+        if self.generation == 0 {
+            // Stale tags could now collide with the new generation.
+            self.visited.fill(0);
+            self.closed.fill(0);
+            self.generation = 1;
+        }
+    }
+
+    #[inline]
+    pub(super) fn g(&self, i: usize) -> i32 {
+        if self.visited[i] == self.generation {
+            self.g_score[i]
+        } else {
+            INF
+        }
+    }
+
+    #[inline]
+    pub(super) fn came_from(&self, i: usize) -> Option<usize> {
+        if self.visited[i] == self.generation {
+            self.came_from[i]
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    pub(super) fn set(&mut self, i: usize, g: i32, came_from: Option<usize>) {
+        self.visited[i] = self.generation;
+        self.g_score[i] = g;
+        self.came_from[i] = came_from;
+    }
+
+    #[inline]
+    pub(super) fn is_closed(&self, i: usize) -> bool {
+        self.closed[i] == self.generation
+    }
+
+    #[inline]
+    pub(super) fn close(&mut self, i: usize) {
+        self.closed[i] = self.generation;
+    }
+}
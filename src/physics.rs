@@ -1,37 +1,71 @@
 use crate::actor::Actor;
-use crate::map::{RuntimeMapAdapter, is_wall, map_height, map_tile_size, map_width, world_to_grid};
+use crate::map::{Obstacle, RuntimeMapAdapter, WorldRect, runtime_map};
+use crate::pathfinding::FlowField;
+
+/// Actor speed scaled down by the traversal cost of the cell it stands on.
+pub fn terrain_speed(actor: &Actor) -> f32 {
+    let map = runtime_map().expect("runtime map unavailable");
+    terrain_speed_with_map(&map, actor)
+}
+
+pub fn terrain_speed_with_map(map: &RuntimeMapAdapter, actor: &Actor) -> f32 {
+    let (cx, cy) = map.world_to_grid(actor.pos.0, actor.pos.1);
+    actor.speed / map.traversal_cost(cx, cy)
+}
 
 pub fn resolve_circle_map(actor: &mut Actor) {
-    let (cx, cy) = world_to_grid(actor.pos.0, actor.pos.1);
-    let map_w = map_width();
-    let map_h = map_height();
-    let tile = map_tile_size();
-    resolve_circle_map_impl(actor, cx, cy, map_w, map_h, tile, |x, y| is_wall(x, y));
+    let map = runtime_map().expect("runtime map unavailable");
+    resolve_circle_map_with_map(&map, actor);
 }
 
 pub fn resolve_circle_map_with_map(map: &RuntimeMapAdapter, actor: &mut Actor) {
     let (cx, cy) = map.world_to_grid(actor.pos.0, actor.pos.1);
-    resolve_circle_map_impl(
-        actor,
-        cx,
-        cy,
-        map.width,
-        map.height,
-        map.tile_size,
-        |x, y| map.is_wall(x, y),
-    );
+    resolve_circle_map_impl(actor, cx, cy, map.width, map.height, |x, y, out| {
+        map.collision_rects(x, y, out)
+    });
+    resolve_circle_obstacles(actor, map.obstacles());
+}
+
+/// Pushes the actor out of every obstacle it overlaps, through the nearest
+/// point of the obstacle's outline.
+fn resolve_circle_obstacles(actor: &mut Actor, obstacles: &[Obstacle]) {
+    for obstacle in obstacles {
+        let (min, max) = obstacle.bounds();
+        if actor.pos.0 + actor.radius <= min.0
+            || actor.pos.0 - actor.radius >= max.0
+            || actor.pos.1 + actor.radius <= min.1
+            || actor.pos.1 - actor.radius >= max.1
+        {
+            continue;
+        }
+
+        let edge = obstacle.closest_boundary_point(actor.pos);
+        let delta = (actor.pos.0 - edge.0, actor.pos.1 - edge.1);
+        let dist = (delta.0 * delta.0 + delta.1 * delta.1).sqrt();
+        if obstacle.contains(actor.pos) {
+            // Inside: step out past the nearest edge, away from the interior.
+            if dist > 0.0 {
+                let out = (-delta.0 / dist, -delta.1 / dist);
+                actor.pos = (edge.0 + out.0 * actor.radius, edge.1 + out.1 * actor.radius);
+            }
+        } else if dist < actor.radius && dist > 0.0 {
+            let push = (actor.radius - dist) / dist;
+            actor.pos = (actor.pos.0 + delta.0 * push, actor.pos.1 + delta.1 * push);
+        }
+    }
 }
 
-fn resolve_circle_map_impl<FIsWall>(
+/// Pushes the actor out of the rects `rects_fn` reports for the cells
+/// around it.
+fn resolve_circle_map_impl<FRects>(
     actor: &mut Actor,
     cx: usize,
     cy: usize,
     map_w: usize,
     map_h: usize,
-    tile: f32,
-    is_wall_fn: FIsWall,
+    rects_fn: FRects,
 ) where
-    FIsWall: Fn(usize, usize) -> bool,
+    FRects: Fn(usize, usize, &mut Vec<WorldRect>),
 {
     if map_w == 0 || map_h == 0 {
         return;
@@ -42,12 +76,12 @@ fn resolve_circle_map_impl<FIsWall>(
     let y1 = (cy + 1).min(map_h - 1);
     let x1 = (cx + 1).min(map_w - 1);
 
+    let mut rects = Vec::new();
     for ty in y0..=y1 {
         for tx in x0..=x1 {
-            if is_wall_fn(tx, ty) {
-                let tile_min = (tx as f32 * tile, ty as f32 * tile);
-                let tile_max = (tile_min.0 + tile, tile_min.1 + tile);
-
+            rects.clear();
+            rects_fn(tx, ty, &mut rects);
+            for &(tile_min, tile_max) in &rects {
                 let closest = (
                     actor.pos.0.clamp(tile_min.0, tile_max.0),
                     actor.pos.1.clamp(tile_min.1, tile_max.1),
@@ -71,7 +105,8 @@ fn resolve_circle_map_impl<FIsWall>(
 }
 
 pub fn move_with_slide(actor: &mut Actor, vx: f32, vy: f32) {
-    move_with_slide_impl(actor, vx, vy, |actor| resolve_circle_map(actor));
+    let map = runtime_map().expect("runtime map unavailable");
+    move_with_slide_with_map(&map, actor, vx, vy);
 }
 
 pub fn move_with_slide_with_map(map: &RuntimeMapAdapter, actor: &mut Actor, vx: f32, vy: f32) {
@@ -106,3 +141,49 @@ where
         let _ = after_x;
     }
 }
+
+/// Moves the actor one frame down the flow field, aiming at the center of the
+/// next cell so it rounds corners instead of clipping them. Returns `false`
+/// once the actor stands on the goal cell or on a cell with no route.
+pub fn steer_along_flow_field(field: &FlowField, actor: &mut Actor, dt: f32) -> bool {
+    let map = runtime_map().expect("runtime map unavailable");
+    steer_along_flow_field_with_map(&map, field, actor, dt)
+}
+
+pub fn steer_along_flow_field_with_map(
+    map: &RuntimeMapAdapter,
+    field: &FlowField,
+    actor: &mut Actor,
+    dt: f32,
+) -> bool {
+    let (cx, cy) = map.world_to_grid(actor.pos.0, actor.pos.1);
+    let Some((nx, ny)) = field.next_cell(cx, cy) else {
+        return false;
+    };
+    let target = map.grid_to_world(nx, ny);
+    let step = terrain_speed_with_map(map, actor) * dt;
+    steer_toward_impl(actor, target, step, |actor, vx, vy| {
+        move_with_slide_with_map(map, actor, vx, vy)
+    })
+}
+
+fn steer_toward_impl<FMove>(
+    actor: &mut Actor,
+    target: (f32, f32),
+    step: f32,
+    move_fn: FMove,
+) -> bool
+where
+    FMove: FnOnce(&mut Actor, f32, f32),
+{
+    let dx = target.0 - actor.pos.0;
+    let dy = target.1 - actor.pos.1;
+    let dist = (dx * dx + dy * dy).sqrt();
+    if dist <= 0.001 {
+        return true;
+    }
+
+    let step = step.min(dist);
+    move_fn(actor, dx / dist * step, dy / dist * step);
+    true
+}
@@ -1,38 +1,171 @@
-use crate::map::{RuntimeMapAdapter, grid_to_world, is_wall, map_tile_size};
+use crate::map::{HexLayout, RuntimeMapAdapter, runtime_map};
 
 pub fn los_grid(a: (usize, usize), b: (usize, usize)) -> bool {
-    let aw = grid_to_world(a.0, a.1);
-    let bw = grid_to_world(b.0, b.1);
-    has_line_of_sight(aw, bw)
+    let map = runtime_map().expect("runtime map unavailable");
+    los_grid_with_map(&map, a, b)
 }
 
+/// Sight between cell centers: only cells that block sight stop it.
 pub fn los_grid_with_map(map: &RuntimeMapAdapter, a: (usize, usize), b: (usize, usize)) -> bool {
+    grid_line_clear(map, a, b, |x, y| map.blocks_sight(x, y))
+}
+
+/// Whether a straight walk between cell centers crosses no wall.
+fn walk_grid_with_map(map: &RuntimeMapAdapter, a: (usize, usize), b: (usize, usize)) -> bool {
+    grid_line_clear(map, a, b, |x, y| map.is_wall(x, y))
+}
+
+fn grid_line_clear<FIsWall>(
+    map: &RuntimeMapAdapter,
+    a: (usize, usize),
+    b: (usize, usize),
+    is_wall_fn: FIsWall,
+) -> bool
+where
+    FIsWall: Fn(usize, usize) -> bool,
+{
+    if let Some(hex) = &map.hex {
+        return hex_line_of_sight(hex, a, b, is_wall_fn);
+    }
     let aw = map.grid_to_world(a.0, a.1);
     let bw = map.grid_to_world(b.0, b.1);
-    has_line_of_sight_with(aw, bw, map.tile_size, |x, y| map.is_wall(x, y))
+    has_line_of_sight_with(aw, bw, frame_of(map), false, is_wall_fn)
+}
+
+/// Whether an agent of `radius` world units can move in a straight line
+/// between cell centers. Both ends must leave the agent room, and the
+/// circle swept along the segment must not touch a wall: lines parallel to
+/// the segment, at most a tile apart out to `radius` on either side, must
+/// all stay clear. A line through a tile corner must clear both cells that
+/// share it.
+pub fn los_grid_for_radius(
+    map: &RuntimeMapAdapter,
+    a: (usize, usize),
+    b: (usize, usize),
+    radius: f32,
+) -> bool {
+    let blocked_fn = |x: usize, y: usize| map.blocked_for_radius(x, y, radius);
+    if let Some(hex) = &map.hex {
+        return hex_line_of_sight(hex, a, b, blocked_fn);
+    }
+    if blocked_fn(a.0, a.1) || blocked_fn(b.0, b.1) {
+        return false;
+    }
+
+    let frame = frame_of(map);
+    let aw = map.grid_to_world(a.0, a.1);
+    let bw = map.grid_to_world(b.0, b.1);
+    let wall_fn = |x: usize, y: usize| x >= map.width || y >= map.height || map.is_wall(x, y);
+    if !has_line_of_sight_with(aw, bw, frame, true, wall_fn) {
+        return false;
+    }
+
+    let (dx, dy) = (bw.0 - aw.0, bw.1 - aw.1);
+    let len = (dx * dx + dy * dy).sqrt();
+    if radius <= 0.0 || len == 0.0 {
+        return true;
+    }
+    let normal = (-dy / len, dx / len);
+    let (tile_width, tile_height) = frame.0;
+    let lanes = (radius / tile_width.min(tile_height)).ceil() as usize;
+    (1..=lanes).all(|lane| {
+        let offset = radius * lane as f32 / lanes as f32;
+        [offset, -offset].into_iter().all(|offset| {
+            let shift = |p: (f32, f32)| (p.0 + normal.0 * offset, p.1 + normal.1 * offset);
+            let (from, to) = (shift(aw), shift(bw));
+            inside_frame(map, from)
+                && inside_frame(map, to)
+                && has_line_of_sight_with(from, to, frame, true, wall_fn)
+        })
+    })
 }
 
+/// Whether a world point lies on the grid.
+fn inside_frame(map: &RuntimeMapAdapter, (x, y): (f32, f32)) -> bool {
+    let (left, top) = map.world_origin();
+    x >= left
+        && y >= top
+        && x < left + map.width as f32 * map.tile_width
+        && y < top + map.height as f32 * map.tile_height
+}
+
+/// Sight between world points, stopped only by cells that block sight. On
+/// hex maps the points are snapped to their cells first.
 pub fn has_line_of_sight_with_map(map: &RuntimeMapAdapter, a: (f32, f32), b: (f32, f32)) -> bool {
-    has_line_of_sight_with(a, b, map.tile_size, |x, y| map.is_wall(x, y))
+    world_line_clear(map, a, b, |x, y| map.blocks_sight(x, y))
 }
 
 pub fn has_line_of_sight(a: (f32, f32), b: (f32, f32)) -> bool {
-    has_line_of_sight_with(a, b, map_tile_size(), |x, y| is_wall(x, y))
+    let map = runtime_map().expect("runtime map unavailable");
+    has_line_of_sight_with_map(&map, a, b)
+}
+
+/// Whether a point can walk straight to another without crossing a wall.
+/// Unlike sight, windows and chasms stop it.
+pub fn has_walkable_line_with_map(map: &RuntimeMapAdapter, a: (f32, f32), b: (f32, f32)) -> bool {
+    world_line_clear(map, a, b, |x, y| map.is_wall(x, y))
+}
+
+pub fn has_walkable_line(a: (f32, f32), b: (f32, f32)) -> bool {
+    let map = runtime_map().expect("runtime map unavailable");
+    has_walkable_line_with_map(&map, a, b)
+}
+
+fn world_line_clear<FIsWall>(
+    map: &RuntimeMapAdapter,
+    a: (f32, f32),
+    b: (f32, f32),
+    is_wall_fn: FIsWall,
+) -> bool
+where
+    FIsWall: Fn(usize, usize) -> bool,
+{
+    if map.hex.is_some() {
+        let a = map.world_to_grid(a.0, a.1);
+        let b = map.world_to_grid(b.0, b.1);
+        return grid_line_clear(map, a, b, is_wall_fn);
+    }
+    has_line_of_sight_with(a, b, frame_of(map), false, is_wall_fn)
+}
+
+/// Tile size and world position of the grid's top-left corner.
+fn frame_of(map: &RuntimeMapAdapter) -> ((f32, f32), (f32, f32)) {
+    ((map.tile_width, map.tile_height), map.world_origin())
 }
 
+/// Every hex on the line between the two cells, ends included, must be
+/// clear.
+fn hex_line_of_sight<FIsWall>(
+    hex: &HexLayout,
+    a: (usize, usize),
+    b: (usize, usize),
+    is_wall_fn: FIsWall,
+) -> bool
+where
+    FIsWall: Fn(usize, usize) -> bool,
+{
+    hex.line(a, b)
+        .into_iter()
+        .all(|(x, y)| x >= 0 && y >= 0 && !is_wall_fn(x as usize, y as usize))
+}
+
+/// Grid traversal along the segment. With `strict_corners`, a segment passing
+/// exactly through a tile corner is blocked by either cell beside it.
 fn has_line_of_sight_with<FIsWall>(
     a: (f32, f32),
     b: (f32, f32),
-    tile: f32,
+    (tile, origin): ((f32, f32), (f32, f32)),
+    strict_corners: bool,
     is_wall_fn: FIsWall,
 ) -> bool
 where
     FIsWall: Fn(usize, usize) -> bool,
 {
-    let x0 = a.0 / tile;
-    let y0 = a.1 / tile;
-    let x1 = b.0 / tile;
-    let y1 = b.1 / tile;
+    // Work in tile units; the traversal itself only ever sees unit cells.
+    let x0 = (a.0 - origin.0) / tile.0;
+    let y0 = (a.1 - origin.1) / tile.1;
+    let x1 = (b.0 - origin.0) / tile.0;
+    let y1 = (b.1 - origin.1) / tile.1;
 
     let mut cx = x0 as usize;
     let mut cy = y0 as usize;
@@ -67,7 +200,20 @@ where
     }
 
     while cx != end_cx || cy != end_cy {
-        if t_max_x < t_max_y {
+        if strict_corners && (t_max_x - t_max_y).abs() < 1e-5 {
+            t_max_x += t_delta_x;
+            t_max_y += t_delta_y;
+            let nx = cx as isize + step_x;
+            let ny = cy as isize + step_y;
+            if nx < 0 || ny < 0 {
+                return false;
+            }
+            if is_wall_fn(nx as usize, cy) || is_wall_fn(cx, ny as usize) {
+                return false;
+            }
+            cx = nx as usize;
+            cy = ny as usize;
+        } else if t_max_x < t_max_y {
             t_max_x += t_delta_x;
             let nx = cx as isize + step_x;
             if nx < 0 {
@@ -90,25 +236,79 @@ where
     true
 }
 
+/// Furthest path cell after `from_idx` the enemy can walk to in a straight
+/// line; what merely can be seen, e.g. through a window, does not count.
 pub fn furthest_visible_waypoint(
     enemy_pos: (f32, f32),
     path: &[(usize, usize)],
     from_idx: usize,
+) -> usize {
+    let map = runtime_map().expect("runtime map unavailable");
+    furthest_visible_waypoint_with_map(&map, enemy_pos, path, from_idx)
+}
+
+pub fn furthest_visible_waypoint_with_map(
+    map: &RuntimeMapAdapter,
+    enemy_pos: (f32, f32),
+    path: &[(usize, usize)],
+    from_idx: usize,
 ) -> usize {
     if path.is_empty() {
         return from_idx;
     }
 
-    let enemy_cell = crate::map::world_to_grid(enemy_pos.0, enemy_pos.1);
-    let mut best = from_idx;
+    let enemy_cell = map.world_to_grid(enemy_pos.0, enemy_pos.1);
+    furthest_visible_impl(path, from_idx, |cell| {
+        walk_grid_with_map(map, enemy_cell, cell)
+    })
+}
 
-    for i in (from_idx + 1)..path.len() {
-        if los_grid(enemy_cell, path[i]) {
+fn furthest_visible_impl<FSight>(
+    path: &[(usize, usize)],
+    from_idx: usize,
+    sight_fn: FSight,
+) -> usize
+where
+    FSight: Fn((usize, usize)) -> bool,
+{
+    let mut best = from_idx;
+    for (i, &cell) in path.iter().enumerate().skip(from_idx + 1) {
+        if sight_fn(cell) {
             best = i;
         } else {
             break;
         }
     }
-
     best
 }
+
+/// String-pulls a cell path into world-space waypoints. Each waypoint is the
+/// furthest later cell an agent of `agent_radius` can walk to in a straight
+/// line, so the agent crosses open rooms directly instead of hugging the
+/// grid. The first and last cells are always kept.
+pub fn smooth_path(path: &[(usize, usize)], agent_radius: f32) -> Vec<(f32, f32)> {
+    let map = runtime_map().expect("runtime map unavailable");
+    smooth_path_with_map(&map, path, agent_radius)
+}
+
+pub fn smooth_path_with_map(
+    map: &RuntimeMapAdapter,
+    path: &[(usize, usize)],
+    agent_radius: f32,
+) -> Vec<(f32, f32)> {
+    let Some(&first) = path.first() else {
+        return vec![];
+    };
+
+    let mut waypoints = vec![map.grid_to_world(first.0, first.1)];
+    let mut anchor = 0;
+    while anchor + 1 < path.len() {
+        let from = path[anchor];
+        let next = furthest_visible_impl(path, anchor + 1, |cell| {
+            los_grid_for_radius(map, from, cell, agent_radius)
+        });
+        waypoints.push(map.grid_to_world(path[next].0, path[next].1));
+        anchor = next;
+    }
+    waypoints
+}
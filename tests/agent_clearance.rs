@@ -0,0 +1,99 @@
+use std::path::PathBuf;
+
+use enemy_pathfinder::map::RuntimeMapAdapter;
+use enemy_pathfinder::pathfinding::{
+    DiagonalMovement, FlowField, PathOptions, astar_with_map_options,
+};
+
+mod common;
+
+use common::grid_from_rows;
+
+fn assets_map_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("assets")
+        .join("map.json")
+}
+
+/// Two rooms joined by a one-tile corridor (row 2) and a three-tile corridor
+/// (rows 5..=7). Only the middle row of the wide corridor fits a fat agent.
+fn two_corridor_map() -> RuntimeMapAdapter {
+    grid_from_rows(&[
+        "###############",
+        "#...#######...#",
+        "#.............#",
+        "#...#######...#",
+        "#...#######...#",
+        "#.............#",
+        "#.............#",
+        "#.............#",
+        "###############",
+    ])
+}
+
+#[test]
+fn clearance_is_the_distance_to_the_nearest_wall_edge() {
+    let map = two_corridor_map();
+
+    assert_eq!(map.clearance(0, 0), 0.0);
+    assert_eq!(map.clearance(7, 2), 16.0);
+    assert_eq!(map.clearance(7, 5), 16.0);
+    assert_eq!(map.clearance(7, 6), 48.0);
+    assert!(!map.blocked_for_radius(7, 2, 16.0));
+    assert!(map.blocked_for_radius(7, 2, 20.0));
+}
+
+#[test]
+fn fat_agents_take_the_wide_corridor() {
+    let map = two_corridor_map();
+    let (start, goal) = ((2, 2), (12, 2));
+    let options = PathOptions::eight_way(DiagonalMovement::OnlyWhenNoObstacles);
+
+    let thin = astar_with_map_options(&map, start, goal, &options).cells;
+    assert!(thin.contains(&(7, 2)));
+
+    let fat = astar_with_map_options(&map, start, goal, &options.with_agent_radius(20.0)).cells;
+    assert!(fat.contains(&(7, 6)));
+    for &(x, y) in &fat {
+        assert!(map.clearance(x, y) >= 20.0, "cell ({x}, {y}) is too tight");
+    }
+
+    let field = FlowField::from_map(&map, goal, &options.with_agent_radius(20.0));
+    assert_eq!(field.distance(7, 2), None);
+    assert!(field.distance(start.0, start.1).is_some());
+}
+
+#[test]
+fn set_solid_refreshes_nearby_clearance() {
+    let mut map = two_corridor_map();
+    let options = PathOptions::four_way().with_agent_radius(20.0);
+
+    map.set_solid(7, 7, true);
+    assert_eq!(map.clearance(7, 6), 16.0);
+    assert!(
+        astar_with_map_options(&map, (2, 2), (12, 2), &options)
+            .cells
+            .is_empty()
+    );
+
+    map.set_solid(7, 7, false);
+    assert_eq!(map.clearance(7, 6), 48.0);
+    assert!(
+        !astar_with_map_options(&map, (2, 2), (12, 2), &options)
+            .cells
+            .is_empty()
+    );
+}
+
+#[test]
+fn enemy_sized_agents_fit_every_corridor_of_the_bundled_map() {
+    let map = RuntimeMapAdapter::from_tiled_json_wall_layer(assets_map_path())
+        .expect("assets/map.json Wall_Layer should parse");
+    let options = PathOptions::eight_way(DiagonalMovement::OnlyWhenNoObstacles);
+
+    let point = astar_with_map_options(&map, (2, 18), (26, 10), &options).cells;
+    let sized =
+        astar_with_map_options(&map, (2, 18), (26, 10), &options.with_agent_radius(12.0)).cells;
+    assert!(!point.is_empty());
+    assert_eq!(sized, point);
+}
@@ -0,0 +1,181 @@
+use std::path::PathBuf;
+
+use enemy_pathfinder::map::RuntimeMapAdapter;
+use enemy_pathfinder::pathfinding::{
+    DiagonalMovement, PathOptions, SearchAlgorithm, astar_with_map_options, find_path_with_map,
+    path_cost_with_map,
+};
+use enemy_pathfinder::visibility::{
+    furthest_visible_waypoint_with_map, has_line_of_sight_with_map, los_grid_for_radius,
+    los_grid_with_map, smooth_path_with_map,
+};
+
+mod common;
+
+use common::grid_from_rows;
+
+fn assets_map_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("assets")
+        .join("map.json")
+}
+
+fn load_runtime_map() -> RuntimeMapAdapter {
+    RuntimeMapAdapter::from_tiled_json_wall_layer(assets_map_path())
+        .expect("assets/map.json Wall_Layer should parse")
+}
+
+/// Length of a cell polyline in step-cost units.
+fn polyline_length(path: &[(usize, usize)]) -> f32 {
+    path.windows(2)
+        .map(|w| {
+            let dx = w[0].0.abs_diff(w[1].0) as f32;
+            let dy = w[0].1.abs_diff(w[1].1) as f32;
+            (dx * dx + dy * dy).sqrt() * 100.0
+        })
+        .sum()
+}
+
+#[test]
+fn smoothing_collapses_open_room_paths_to_one_segment() {
+    let map = grid_from_rows(&[
+        "##########",
+        "#........#",
+        "#........#",
+        "#........#",
+        "#........#",
+        "##########",
+    ]);
+    let path = astar_with_map_options(&map, (1, 1), (8, 3), &PathOptions::four_way()).cells;
+    assert!(path.len() > 2);
+
+    let waypoints = smooth_path_with_map(&map, &path, 0.0);
+    assert_eq!(
+        waypoints,
+        vec![map.grid_to_world(1, 1), map.grid_to_world(8, 3)]
+    );
+    assert!(smooth_path_with_map(&map, &[], 0.0).is_empty());
+}
+
+#[test]
+fn smoothed_bundled_map_paths_keep_endpoints_and_clear_walls() {
+    let map = load_runtime_map();
+    let options = PathOptions::eight_way(DiagonalMovement::OnlyWhenNoObstacles);
+    let path = astar_with_map_options(&map, (2, 18), (26, 10), &options).cells;
+    assert!(!path.is_empty());
+
+    let waypoints = smooth_path_with_map(&map, &path, 12.0);
+    assert!(waypoints.len() >= 2 && waypoints.len() < path.len());
+    assert_eq!(waypoints.first(), Some(&map.grid_to_world(2, 18)));
+    assert_eq!(waypoints.last(), Some(&map.grid_to_world(26, 10)));
+    for w in waypoints.windows(2) {
+        assert!(has_line_of_sight_with_map(&map, w[0], w[1]));
+    }
+}
+
+#[test]
+fn furthest_visible_waypoint_uses_the_given_map() {
+    let map = grid_from_rows(&["#######", "#.....#", "#####.#", "#.....#", "#######"]);
+    let path = [
+        (1, 1),
+        (2, 1),
+        (3, 1),
+        (4, 1),
+        (5, 1),
+        (5, 2),
+        (5, 3),
+        (4, 3),
+    ];
+
+    let best = furthest_visible_waypoint_with_map(&map, map.grid_to_world(1, 1), &path, 0);
+    assert_eq!(best, 4);
+}
+
+#[test]
+fn radius_line_of_sight_refuses_to_graze_wall_corners() {
+    let map = grid_from_rows(&[".#", ".."]);
+
+    assert!(los_grid_with_map(&map, (0, 0), (1, 1)));
+    assert!(!los_grid_for_radius(&map, (0, 0), (1, 1), 0.0));
+    assert!(los_grid_for_radius(&map, (0, 0), (0, 1), 0.0));
+}
+
+#[test]
+fn radius_line_of_sight_sweeps_the_whole_circle() {
+    let map = grid_from_rows(&["......", "..#...", "......", "......"]);
+
+    // Every cell the center line crosses has 16 units of clearance, but the
+    // line itself passes about 7 units from the wall.
+    assert!(los_grid_for_radius(&map, (0, 1), (2, 0), 0.0));
+    assert!(!los_grid_for_radius(&map, (0, 1), (2, 0), 14.0));
+    // Along the row below the wall the circle keeps 2 units to spare.
+    assert!(los_grid_for_radius(&map, (0, 2), (5, 2), 14.0));
+}
+
+#[test]
+fn theta_star_goes_straight_across_open_rooms() {
+    let map = grid_from_rows(&[
+        "##########",
+        "#........#",
+        "#........#",
+        "#........#",
+        "##########",
+    ]);
+    let options = PathOptions::eight_way(DiagonalMovement::OnlyWhenNoObstacles)
+        .with_algorithm(SearchAlgorithm::ThetaStar);
+
+    let result = find_path_with_map(&map, (1, 1), (8, 2), &options);
+    assert_eq!(result.cells, vec![(1, 1), (8, 2)]);
+    assert!(result.any_angle);
+    assert!(!astar_with_map_options(&map, (1, 1), (8, 2), &options).any_angle);
+}
+
+#[test]
+fn theta_star_paths_are_visible_and_no_longer_than_grid_paths() {
+    let map = load_runtime_map();
+    let grid = PathOptions::eight_way(DiagonalMovement::OnlyWhenNoObstacles);
+    let theta = grid.with_algorithm(SearchAlgorithm::ThetaStar);
+
+    for (start, goal) in [((2, 18), (26, 10)), ((1, 3), (12, 3)), ((1, 1), (5, 1))] {
+        let grid_path = find_path_with_map(&map, start, goal, &grid).cells;
+        let theta_path = find_path_with_map(&map, start, goal, &theta).cells;
+        assert!(!grid_path.is_empty());
+
+        assert_eq!(theta_path.first(), Some(&start));
+        assert_eq!(theta_path.last(), Some(&goal));
+        assert!(polyline_length(&theta_path) <= path_cost_with_map(&map, &grid_path) as f32);
+        for w in theta_path.windows(2) {
+            assert!(los_grid_for_radius(&map, w[0], w[1], 0.0), "{:?}", w);
+        }
+    }
+}
+
+#[test]
+fn theta_star_falls_back_to_astar_on_weighted_terrain() {
+    let map = grid_from_rows(&["....", "....", "...."]).with_traversal_costs(vec![
+        1.0, 1.0, 1.0, 1.0, //
+        1.0, 3.0, 3.0, 1.0, //
+        1.0, 1.0, 1.0, 1.0,
+    ]);
+    let options = PathOptions::four_way();
+
+    assert_eq!(
+        find_path_with_map(
+            &map,
+            (0, 1),
+            (3, 1),
+            &options.with_algorithm(SearchAlgorithm::ThetaStar)
+        )
+        .cells,
+        astar_with_map_options(&map, (0, 1), (3, 1), &options).cells
+    );
+    assert!(
+        !find_path_with_map(
+            &map,
+            (0, 1),
+            (3, 1),
+            &options.with_algorithm(SearchAlgorithm::ThetaStar)
+        )
+        .any_angle
+    );
+}
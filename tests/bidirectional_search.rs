@@ -0,0 +1,151 @@
+use std::path::PathBuf;
+
+use enemy_pathfinder::map::RuntimeMapAdapter;
+use enemy_pathfinder::pathfinding::{
+    DiagonalMovement, PathFailure, PathOptions, PathfinderWorkspace, SearchAlgorithm,
+    astar_with_map_options, bidirectional_astar_with_map, path_cost_with_map,
+};
+
+mod common;
+
+use common::XorShift;
+
+fn assets_map_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("assets")
+        .join("map.json")
+}
+
+fn load_runtime_map() -> RuntimeMapAdapter {
+    RuntimeMapAdapter::from_tiled_json_wall_layer(assets_map_path())
+        .expect("assets/map.json Wall_Layer should parse")
+}
+
+const ALL_DIAGONALS: [DiagonalMovement; 4] = [
+    DiagonalMovement::Never,
+    DiagonalMovement::Always,
+    DiagonalMovement::IfAtMostOneObstacle,
+    DiagonalMovement::OnlyWhenNoObstacles,
+];
+
+fn random_map(rng: &mut XorShift, width: usize, height: usize) -> RuntimeMapAdapter {
+    let solid = (0..width * height).map(|_| rng.below(100) < 28).collect();
+    let cost = (0..width * height)
+        .map(|_| [1.0, 1.0, 1.0, 0.5, 2.0, 3.5][rng.below(6)])
+        .collect();
+    RuntimeMapAdapter::from_solid_cells(32.0, width, height, solid).with_traversal_costs(cost)
+}
+
+fn assert_same_cost(
+    map: &RuntimeMapAdapter,
+    start: (usize, usize),
+    goal: (usize, usize),
+    options: &PathOptions,
+) {
+    let one_sided = astar_with_map_options(map, start, goal, options);
+    let two_sided = bidirectional_astar_with_map(map, start, goal, options);
+
+    assert_eq!(
+        two_sided.is_found(),
+        one_sided.is_found(),
+        "{start:?} -> {goal:?}"
+    );
+    assert_eq!(
+        two_sided.cost, one_sided.cost,
+        "{start:?} -> {goal:?} {options:?}"
+    );
+    if !two_sided.is_found() {
+        assert!(two_sided.cells.is_empty());
+        return;
+    }
+
+    let cells = &two_sided.cells;
+    assert_eq!(cells.first(), Some(&start));
+    assert_eq!(cells.last(), Some(&goal));
+    assert_eq!(path_cost_with_map(map, cells), two_sided.cost);
+    for w in cells.windows(2) {
+        assert!(w[0].0.abs_diff(w[1].0) <= 1 && w[0].1.abs_diff(w[1].1) <= 1);
+        assert!(!map.blocked_for_agent(w[1].0, w[1].1));
+    }
+}
+
+#[test]
+fn random_weighted_grids_match_unidirectional_costs() {
+    let mut rng = XorShift(0x5eed_b1d1);
+
+    for _ in 0..60 {
+        let (width, height) = (4 + rng.below(20), 4 + rng.below(20));
+        let map = random_map(&mut rng, width, height);
+        for diagonal in ALL_DIAGONALS {
+            let options = PathOptions::eight_way(diagonal);
+            for _ in 0..6 {
+                let start = (rng.below(width), rng.below(height));
+                let goal = (rng.below(width), rng.below(height));
+                assert_same_cost(&map, start, goal, &options);
+            }
+        }
+    }
+}
+
+#[test]
+fn bundled_map_matches_unidirectional_costs() {
+    let map = load_runtime_map();
+    let mut rng = XorShift(0xc0ff_ee11);
+
+    for diagonal in ALL_DIAGONALS {
+        let options = PathOptions::eight_way(diagonal);
+        for _ in 0..40 {
+            let start = (rng.below(map.width), rng.below(map.height));
+            let goal = (rng.below(map.width), rng.below(map.height));
+            assert_same_cost(&map, start, goal, &options);
+        }
+    }
+}
+
+#[test]
+fn agent_radius_is_respected_from_both_ends() {
+    let map = RuntimeMapAdapter::from_solid_cells(
+        32.0,
+        7,
+        5,
+        "#######\
+         #.....#\
+         ###.###\
+         #.....#\
+         #######"
+            .bytes()
+            .map(|b| b == b'#')
+            .collect(),
+    );
+    let options = PathOptions::four_way();
+
+    assert!(bidirectional_astar_with_map(&map, (1, 1), (5, 3), &options).is_found());
+    let fat = bidirectional_astar_with_map(&map, (1, 1), (5, 3), &options.with_agent_radius(20.0));
+    assert_eq!(fat.failure, Some(PathFailure::Unreachable));
+}
+
+#[test]
+fn long_corridor_runs_expand_fewer_nodes() {
+    let map = load_runtime_map();
+    let options = PathOptions::four_way();
+
+    let one_sided = astar_with_map_options(&map, (2, 18), (26, 10), &options);
+    let two_sided = bidirectional_astar_with_map(&map, (2, 18), (26, 10), &options);
+    assert_eq!(two_sided.cost, one_sided.cost);
+    assert!(two_sided.expanded < one_sided.expanded);
+}
+
+#[test]
+fn workspace_dispatch_reuses_both_halves() {
+    let map = load_runtime_map();
+    let options = PathOptions::eight_way(DiagonalMovement::OnlyWhenNoObstacles)
+        .with_algorithm(SearchAlgorithm::Bidirectional);
+    let mut workspace = PathfinderWorkspace::new();
+
+    for (start, goal) in [((2, 18), (26, 10)), ((1, 3), (12, 3)), ((26, 10), (2, 18))] {
+        assert_eq!(
+            workspace.find_path_with_map(&map, start, goal, &options),
+            bidirectional_astar_with_map(&map, start, goal, &options)
+        );
+    }
+}
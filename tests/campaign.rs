@@ -0,0 +1,110 @@
+use std::fs;
+use std::path::PathBuf;
+
+use enemy_pathfinder::campaign::{
+    Campaign, CampaignError, CampaignProgress, LEVEL_CLEAR_SCORE, LevelMap, LevelOutcome,
+};
+
+mod common;
+
+use common::temp_dir;
+
+fn assets_campaign_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("assets")
+        .join("campaign.json")
+}
+
+#[test]
+fn manifest_lists_levels_in_order_relative_to_itself() {
+    let dir = temp_dir("campaign_order");
+    let manifest = dir.join("campaign.json");
+    fs::write(
+        &manifest,
+        r#"{ "levels": [
+            { "map": "levels/one.json", "name": "One" },
+            { "map": "two.tmx" }
+        ] }"#,
+    )
+    .expect("failed to write manifest");
+
+    let campaign = Campaign::load(&manifest).expect("manifest should load");
+
+    assert_eq!(campaign.levels().len(), 2);
+    let first = campaign.level(0).expect("first level");
+    assert_eq!(first.map_path, dir.join("levels/one.json"));
+    assert_eq!(first.name.as_deref(), Some("One"));
+    let second = campaign.level(1).expect("second level");
+    assert_eq!(second.map_path, dir.join("two.tmx"));
+    assert_eq!(second.name, None);
+    assert!(campaign.level(2).is_none());
+}
+
+#[test]
+fn bad_manifests_are_reported() {
+    let dir = temp_dir("campaign_errors");
+
+    let missing = Campaign::load(dir.join("missing.json"));
+    assert!(matches!(missing, Err(CampaignError::Io { .. })));
+
+    let broken = dir.join("broken.json");
+    fs::write(&broken, r#"{ "levels": [{ "name": "no map" }] }"#).expect("write");
+    assert!(matches!(
+        Campaign::load(&broken),
+        Err(CampaignError::Json { .. })
+    ));
+
+    let empty = dir.join("empty.json");
+    fs::write(&empty, r#"{ "levels": [] }"#).expect("write");
+    let err = Campaign::load(&empty).expect_err("no levels");
+    assert!(matches!(err, CampaignError::NoLevels { .. }));
+    assert!(err.to_string().contains("lists no levels"));
+}
+
+#[test]
+fn progress_carries_score_and_time_across_levels() {
+    let dir = temp_dir("campaign_progress");
+    let manifest = dir.join("campaign.json");
+    fs::write(
+        &manifest,
+        r#"{ "levels": [{ "map": "a.json" }, { "map": "b.json" }] }"#,
+    )
+    .expect("failed to write manifest");
+    let campaign = Campaign::load(&manifest).expect("manifest should load");
+
+    let mut progress = CampaignProgress::default();
+    progress.tick(1.5);
+    assert_eq!(progress.complete_level(&campaign), LevelOutcome::Next(1));
+    progress.tick(2.0);
+    assert_eq!(progress.complete_level(&campaign), LevelOutcome::Finished);
+
+    assert_eq!(progress.level, 1);
+    assert_eq!(progress.score, 2 * LEVEL_CLEAR_SCORE);
+    assert!((progress.elapsed - 3.5).abs() < 1e-6);
+
+    let single = dir.join("single.json");
+    fs::write(&single, r#"{ "levels": [{ "map": "a.json" }] }"#).expect("write");
+    let single = Campaign::load(&single).expect("manifest should load");
+    assert_eq!(
+        CampaignProgress::default().complete_level(&single),
+        LevelOutcome::Finished
+    );
+}
+
+#[test]
+fn shipped_campaign_levels_are_playable() {
+    let campaign = Campaign::load(assets_campaign_path()).expect("assets campaign should load");
+
+    assert!(
+        campaign.levels().len() >= 2,
+        "the shipped campaign should chain levels"
+    );
+    for level in campaign.levels() {
+        let path = level.map_path.display();
+        let map = LevelMap::load(&level.map_path)
+            .unwrap_or_else(|err| panic!("{path} should load: {err}"));
+        assert!(map.enemy_spawn.is_some(), "{path} has no Enemy spawn");
+        assert!(map.player_spawn.is_some(), "{path} has no Player spawn");
+        assert!(map.end_zone.is_some(), "{path} has no end zone");
+    }
+}
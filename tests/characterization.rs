@@ -40,14 +40,14 @@ fn line_of_sight_detects_open_and_blocked_segments() {
 fn astar_returns_empty_for_wall_start_or_goal() {
     let map = load_runtime_map();
     assert!(map.is_wall(0, 0));
-    assert!(astar_with_map(&map, (0, 0), (1, 1)).is_empty());
-    assert!(astar_with_map(&map, (1, 1), (0, 0)).is_empty());
+    assert!(astar_with_map(&map, (0, 0), (1, 1)).cells.is_empty());
+    assert!(astar_with_map(&map, (1, 1), (0, 0)).cells.is_empty());
 }
 
 #[test]
 fn astar_returns_path_with_expected_endpoints_for_known_open_cells() {
     let map = load_runtime_map();
-    let path = astar_with_map(&map, (1, 3), (12, 3));
+    let path = astar_with_map(&map, (1, 3), (12, 3)).cells;
 
     assert!(!path.is_empty());
     assert_eq!(path.first().copied(), Some((1, 3)));
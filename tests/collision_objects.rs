@@ -0,0 +1,220 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use enemy_pathfinder::actor::Actor;
+use enemy_pathfinder::map::{HexLayout, Obstacle, RuntimeMapAdapter, StaggerAxis, StaggerIndex};
+use enemy_pathfinder::pathfinding::astar_with_map;
+use enemy_pathfinder::physics::resolve_circle_map_with_map;
+use enemy_pathfinder::visibility::los_grid_with_map;
+
+mod common;
+
+use common::temp_dir;
+
+/// Open 6x4 map of 32px tiles whose collision layer holds:
+/// - a rect over cells (2, 0) and (2, 1);
+/// - a 64px circle centered at (160, 96), over cells (4..6, 2..4);
+/// - a triangle in the bottom-left corner, over cells (0, 3) and (1, 3);
+/// - a point and a hidden rect, which collide with nothing.
+fn write_object_map(dir: &Path) -> PathBuf {
+    let map_path = dir.join("map.json");
+    let json = r#"{
+  "width": 6,
+  "height": 4,
+  "tilewidth": 32,
+  "tileheight": 32,
+  "layers": [
+    {
+      "type": "tilelayer",
+      "name": "Wall_Layer",
+      "width": 6,
+      "height": 4,
+      "data": [0, 0, 0, 0, 0, 0,
+               0, 0, 0, 0, 0, 0,
+               0, 0, 0, 0, 0, 0,
+               0, 0, 0, 0, 0, 0]
+    },
+    {
+      "type": "objectgroup",
+      "name": "Collision_Layer",
+      "objects": [
+        { "id": 1, "x": 64, "y": 0, "width": 32, "height": 64 },
+        { "id": 2, "x": 128, "y": 64, "width": 64, "height": 64, "ellipse": true },
+        { "id": 3, "x": 0, "y": 96, "polygon": [
+          { "x": 0, "y": 0 }, { "x": 64, "y": 0 }, { "x": 0, "y": 32 }
+        ] },
+        { "id": 4, "x": 16, "y": 16, "point": true },
+        { "id": 5, "x": 0, "y": 0, "width": 32, "height": 32, "visible": false }
+      ]
+    }
+  ]
+}"#;
+
+    fs::write(&map_path, json).expect("failed to write map fixture");
+    map_path
+}
+
+fn load_object_map(tag: &str) -> RuntimeMapAdapter {
+    let dir = temp_dir(tag);
+    RuntimeMapAdapter::from_tiled_json_wall_layer(write_object_map(&dir))
+        .expect("object map should load")
+}
+
+fn actor_at(pos: (f32, f32), radius: f32) -> Actor {
+    Actor {
+        pos,
+        radius,
+        speed: 0.0,
+    }
+}
+
+#[test]
+fn collision_objects_are_rasterized_into_the_grid() {
+    let map = load_object_map("raster");
+
+    let walls: Vec<(usize, usize)> = (0..map.height)
+        .flat_map(|y| (0..map.width).map(move |x| (x, y)))
+        .filter(|&(x, y)| map.is_wall(x, y))
+        .collect();
+    assert_eq!(
+        walls,
+        vec![
+            (2, 0),
+            (2, 1),
+            (4, 2),
+            (5, 2),
+            (0, 3),
+            (1, 3),
+            (4, 3),
+            (5, 3)
+        ]
+    );
+    assert!(walls.iter().all(|&(x, y)| map.blocks_sight(x, y)));
+    assert_eq!(map.obstacles().len(), 3);
+    assert!(matches!(map.obstacles()[0], Obstacle::Rect(_)));
+}
+
+#[test]
+fn pathfinding_routes_around_collision_objects() {
+    let map = load_object_map("astar");
+
+    let path = astar_with_map(&map, (1, 0), (3, 0));
+    assert!(path.is_found());
+    assert!(path.cells.iter().all(|&(x, y)| !map.is_wall(x, y)));
+    assert!(path.cells.iter().any(|&(_, y)| y == 2));
+}
+
+#[test]
+fn actors_are_pushed_out_of_every_object_shape() {
+    let map = load_object_map("physics");
+
+    // Overlapping the rect's left side.
+    let mut actor = actor_at((60.0, 16.0), 10.0);
+    resolve_circle_map_with_map(&map, &mut actor);
+    assert!((actor.pos.0 - 54.0).abs() < 0.01, "{:?}", actor.pos);
+
+    // Overlapping the top of the circle.
+    let mut actor = actor_at((160.0, 58.0), 10.0);
+    resolve_circle_map_with_map(&map, &mut actor);
+    assert!((actor.pos.1 - 54.0).abs() < 0.01, "{:?}", actor.pos);
+    assert!((actor.pos.0 - 160.0).abs() < 0.01, "{:?}", actor.pos);
+
+    // Inside the triangle, nearest its top edge.
+    let mut actor = actor_at((6.0, 100.0), 10.0);
+    resolve_circle_map_with_map(&map, &mut actor);
+    assert!((actor.pos.1 - 86.0).abs() < 0.01, "{:?}", actor.pos);
+    assert!((actor.pos.0 - 6.0).abs() < 0.01, "{:?}", actor.pos);
+}
+
+#[test]
+fn obstacles_close_every_cell_they_touch_and_clearance_measures_them() {
+    let map = RuntimeMapAdapter::from_solid_cells(32.0, 5, 3, vec![false; 15])
+        .with_obstacles(vec![Obstacle::Rect(((72.0, 42.0), (76.0, 52.0)))]);
+
+    // Far from the cell center, but inside the cell.
+    assert!(map.is_wall(2, 1));
+    assert!(!map.is_wall(1, 1) && !map.is_wall(2, 0));
+    // Measured to the obstacle, not to the closed tile 16 units away.
+    assert!((map.clearance(1, 1) - 24.0).abs() < 0.01);
+}
+
+#[test]
+fn hex_cells_are_closed_by_overlap_with_the_hex() {
+    let layout = HexLayout {
+        tile_width: 28.0,
+        tile_height: 32.0,
+        side_length: 16.0,
+        stagger_axis: StaggerAxis::Y,
+        stagger_index: StaggerIndex::Odd,
+    };
+    let open =
+        RuntimeMapAdapter::from_solid_cells(28.0, 4, 4, vec![false; 16]).with_hex_layout(layout);
+    let (cx, cy) = open.grid_to_world(1, 1);
+    // A sliver just inside the hex's right side, clear of its neighbors.
+    let map = open.with_obstacles(vec![Obstacle::Rect((
+        (cx + 12.0, cy - 2.0),
+        (cx + 13.0, cy + 2.0),
+    ))]);
+
+    let walls: Vec<(usize, usize)> = (0..map.height)
+        .flat_map(|y| (0..map.width).map(move |x| (x, y)))
+        .filter(|&(x, y)| map.is_wall(x, y))
+        .collect();
+    assert_eq!(walls, vec![(1, 1)]);
+}
+
+#[test]
+fn collision_objects_can_leave_sight_open() {
+    let dir = temp_dir("objects_glass");
+    let map_path = dir.join("map.json");
+    let json = r#"{
+  "width": 3,
+  "height": 1,
+  "tilewidth": 32,
+  "tileheight": 32,
+  "layers": [
+    { "type": "tilelayer", "name": "Wall_Layer", "width": 3, "height": 1, "data": [0, 0, 0] },
+    {
+      "type": "objectgroup",
+      "name": "Collision_Layer",
+      "objects": [
+        { "id": 1, "x": 36, "y": 4, "width": 24, "height": 24,
+          "properties": [{ "name": "blocks_sight", "type": "bool", "value": false }] }
+      ]
+    }
+  ]
+}"#;
+    fs::write(&map_path, json).expect("failed to write map fixture");
+
+    let map = RuntimeMapAdapter::from_tiled_json_wall_layer(&map_path).expect("map should load");
+    assert!(map.is_wall(1, 0));
+    assert!(!map.blocks_sight(1, 0));
+    assert!(los_grid_with_map(&map, (0, 0), (2, 0)));
+}
+
+#[test]
+fn tmx_collision_objects_follow_rotation() {
+    let dir = temp_dir("objects_tmx");
+    let map_path = dir.join("map.tmx");
+    let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="4" height="3" tilewidth="32" tileheight="32" infinite="0">
+ <layer id="1" name="Wall_Layer" width="4" height="3">
+  <data encoding="csv">
+0,0,0,0,
+0,0,0,0,
+0,0,0,0
+</data>
+ </layer>
+ <objectgroup id="2" name="Collision_Layer">
+  <object id="1" x="64" y="0" width="64" height="32" rotation="90"/>
+ </objectgroup>
+</map>
+"#;
+    fs::write(&map_path, xml).expect("failed to write map fixture");
+
+    let map = RuntimeMapAdapter::from_tmx_wall_layer(&map_path).expect("tmx map should load");
+
+    assert!(map.is_wall(1, 0) && map.is_wall(1, 1));
+    assert!(!map.is_wall(2, 0) && !map.is_wall(1, 2));
+    assert!(matches!(map.obstacles()[0], Obstacle::Polygon(_)));
+}
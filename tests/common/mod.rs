@@ -0,0 +1,82 @@
+//! Helpers shared by the integration tests. Each test crate uses a subset.
+#![allow(dead_code)]
+
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use enemy_pathfinder::map::RuntimeMapAdapter;
+use enemy_pathfinder::pathfinding::{DIAGONAL_STEP_COST, STRAIGHT_STEP_COST};
+
+/// 32px map from ASCII rows; `#` is a wall.
+pub fn grid_from_rows(rows: &[&str]) -> RuntimeMapAdapter {
+    let width = rows.first().map_or(0, |row| row.len());
+    let solid = rows
+        .iter()
+        .flat_map(|row| row.bytes().map(|b| b == b'#'))
+        .collect();
+    RuntimeMapAdapter::from_solid_cells(32.0, width, rows.len(), solid)
+}
+
+/// Unit-weight cost of a grid path, counting diagonal steps at the diagonal rate.
+pub fn path_cost(path: &[(usize, usize)]) -> i32 {
+    path.windows(2)
+        .map(|w| {
+            if w[0].0 != w[1].0 && w[0].1 != w[1].1 {
+                DIAGONAL_STEP_COST
+            } else {
+                STRAIGHT_STEP_COST
+            }
+        })
+        .sum()
+}
+
+/// Deterministic xorshift generator for randomised tests.
+pub struct XorShift(pub u64);
+
+impl XorShift {
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+/// Scratch directory under the system temp dir, removed again on drop.
+pub struct TempDir(PathBuf);
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+pub fn temp_dir(tag: &str) -> TempDir {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("clock went backwards")
+        .as_nanos();
+    let dir = std::env::temp_dir().join(format!(
+        "enemy_pathfinder_{tag}_{}_{nanos}_{}",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::create_dir_all(&dir).expect("failed to create temp dir");
+    TempDir(dir)
+}
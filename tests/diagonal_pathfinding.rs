@@ -0,0 +1,111 @@
+use std::path::PathBuf;
+
+use enemy_pathfinder::map::RuntimeMapAdapter;
+use enemy_pathfinder::pathfinding::{
+    DIAGONAL_STEP_COST, DiagonalMovement, PathOptions, STRAIGHT_STEP_COST, astar_with_map,
+    astar_with_map_options, octile,
+};
+
+mod common;
+
+use common::{grid_from_rows, path_cost};
+
+fn assets_map_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("assets")
+        .join("map.json")
+}
+
+#[test]
+fn octile_distance_mixes_diagonal_and_straight_steps() {
+    assert_eq!(octile((0, 0), (3, 3)), 3 * DIAGONAL_STEP_COST);
+    assert_eq!(
+        octile((0, 0), (5, 2)),
+        2 * DIAGONAL_STEP_COST + 3 * STRAIGHT_STEP_COST
+    );
+    assert_eq!(octile((4, 1), (4, 1)), 0);
+}
+
+#[test]
+fn eight_way_search_walks_diagonally_across_open_room() {
+    let map = grid_from_rows(&["......", "......", "......", "......"]);
+    let options = PathOptions::eight_way(DiagonalMovement::OnlyWhenNoObstacles);
+
+    let path = astar_with_map_options(&map, (0, 0), (3, 3), &options).cells;
+
+    assert_eq!(path, vec![(0, 0), (1, 1), (2, 2), (3, 3)]);
+}
+
+#[test]
+fn default_options_keep_four_way_behavior() {
+    let map = grid_from_rows(&["....", "....", "...."]);
+
+    let path = astar_with_map(&map, (0, 0), (2, 2)).cells;
+
+    assert_eq!(path.len(), 5);
+    assert!(
+        path.windows(2)
+            .all(|w| w[0].0 == w[1].0 || w[0].1 == w[1].1)
+    );
+}
+
+#[test]
+fn corner_rules_control_squeezing_between_touching_walls() {
+    let map = grid_from_rows(&[".#", "#."]);
+
+    let always = astar_with_map_options(
+        &map,
+        (0, 0),
+        (1, 1),
+        &PathOptions::eight_way(DiagonalMovement::Always),
+    )
+    .cells;
+    let one_obstacle = astar_with_map_options(
+        &map,
+        (0, 0),
+        (1, 1),
+        &PathOptions::eight_way(DiagonalMovement::IfAtMostOneObstacle),
+    )
+    .cells;
+
+    assert_eq!(always, vec![(0, 0), (1, 1)]);
+    assert!(one_obstacle.is_empty());
+}
+
+#[test]
+fn no_obstacle_rule_refuses_to_cut_wall_corners() {
+    let map = grid_from_rows(&["..", "#."]);
+
+    let cut = astar_with_map_options(
+        &map,
+        (0, 0),
+        (1, 1),
+        &PathOptions::eight_way(DiagonalMovement::IfAtMostOneObstacle),
+    )
+    .cells;
+    let no_cut = astar_with_map_options(
+        &map,
+        (0, 0),
+        (1, 1),
+        &PathOptions::eight_way(DiagonalMovement::OnlyWhenNoObstacles),
+    )
+    .cells;
+
+    assert_eq!(cut, vec![(0, 0), (1, 1)]);
+    assert_eq!(no_cut, vec![(0, 0), (1, 0), (1, 1)]);
+}
+
+#[test]
+fn eight_way_path_is_cheaper_than_four_way_on_bundled_map() {
+    let map = RuntimeMapAdapter::from_tiled_json_wall_layer(assets_map_path())
+        .expect("assets/map.json Wall_Layer should parse");
+    let options = PathOptions::eight_way(DiagonalMovement::OnlyWhenNoObstacles);
+
+    let four = astar_with_map(&map, (2, 18), (26, 10)).cells;
+    let eight = astar_with_map_options(&map, (2, 18), (26, 10), &options).cells;
+
+    assert_eq!(eight.first().copied(), Some((2, 18)));
+    assert_eq!(eight.last().copied(), Some((26, 10)));
+    assert!(path_cost(&eight) < path_cost(&four));
+    assert!(path_cost(&eight) >= octile((2, 18), (26, 10)));
+}
@@ -0,0 +1,120 @@
+use std::path::PathBuf;
+
+use enemy_pathfinder::actor::Actor;
+use enemy_pathfinder::map::{HexLayout, RuntimeMapAdapter, StaggerAxis, StaggerIndex};
+use enemy_pathfinder::pathfinding::{
+    DiagonalMovement, FlowField, PathOptions, astar_with_map_options, path_cost_with_map,
+};
+use enemy_pathfinder::physics::steer_along_flow_field_with_map;
+
+fn assets_map_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("assets")
+        .join("map.json")
+}
+
+fn load_runtime_map() -> RuntimeMapAdapter {
+    RuntimeMapAdapter::from_tiled_json_wall_layer(assets_map_path())
+        .expect("assets/map.json Wall_Layer should parse")
+}
+
+#[test]
+fn field_distances_match_astar_costs_to_the_goal() {
+    let map = load_runtime_map();
+    let goal = (26, 10);
+
+    for options in [
+        PathOptions::four_way(),
+        PathOptions::eight_way(DiagonalMovement::OnlyWhenNoObstacles),
+    ] {
+        let field = FlowField::from_map(&map, goal, &options);
+
+        for y in 0..map.height {
+            for x in 0..map.width {
+                let path = astar_with_map_options(&map, (x, y), goal, &options).cells;
+                let expected = (!path.is_empty()).then(|| path_cost_with_map(&map, &path));
+                assert_eq!(field.distance(x, y), expected, "cell ({x}, {y})");
+            }
+        }
+    }
+}
+
+#[test]
+fn following_next_cells_reaches_the_goal() {
+    let map = load_runtime_map();
+    let goal = (26, 10);
+    let field = FlowField::from_map(&map, goal, &PathOptions::default());
+
+    let mut cell = (2, 18);
+    let mut steps = 0;
+    while let Some(next) = field.next_cell(cell.0, cell.1) {
+        assert!(field.distance(next.0, next.1) < field.distance(cell.0, cell.1));
+        cell = next;
+        steps += 1;
+        assert!(steps < map.width * map.height);
+    }
+
+    assert_eq!(cell, goal);
+    assert_eq!(field.direction_with_map(&map, goal.0, goal.1), None);
+    assert_eq!(field.direction_with_map(&map, 25, 10), Some((1.0, 0.0)));
+}
+
+#[test]
+fn walls_and_sealed_cells_have_no_direction() {
+    let map =
+        RuntimeMapAdapter::from_solid_cells(32.0, 5, 1, vec![false, false, true, false, false]);
+    let field = FlowField::from_map(&map, (0, 0), &PathOptions::default());
+
+    assert_eq!(field.distance(1, 0), Some(100));
+    assert_eq!(field.distance(2, 0), None);
+    assert_eq!(field.next_cell(4, 0), None);
+}
+
+#[test]
+fn directions_follow_world_space_cell_centers() {
+    let map = RuntimeMapAdapter::from_solid_cells(32.0, 1, 2, vec![false, false]).with_hex_layout(
+        HexLayout {
+            tile_width: 28.0,
+            tile_height: 32.0,
+            side_length: 16.0,
+            stagger_axis: StaggerAxis::Y,
+            stagger_index: StaggerIndex::Odd,
+        },
+    );
+    let field = FlowField::from_map(&map, (0, 1), &PathOptions::four_way());
+
+    let (x0, y0) = map.grid_to_world(0, 0);
+    let (x1, y1) = map.grid_to_world(0, 1);
+    let len = ((x1 - x0).powi(2) + (y1 - y0).powi(2)).sqrt();
+    let (dx, dy) = field.direction_with_map(&map, 0, 0).unwrap();
+    assert!((dx - (x1 - x0) / len).abs() < 1e-6);
+    assert!((dy - (y1 - y0) / len).abs() < 1e-6);
+    assert!(dx > 0.0, "odd rows are shifted right in world space");
+}
+
+#[test]
+fn many_actors_descend_the_same_field() {
+    let map = load_runtime_map();
+    let goal = (26, 10);
+    let field = FlowField::from_map(
+        &map,
+        goal,
+        &PathOptions::eight_way(DiagonalMovement::OnlyWhenNoObstacles),
+    );
+
+    let spawns = [(2, 18), (1, 1), (12, 8), (20, 17), (28, 2)];
+    let mut actors: Vec<Actor> = spawns
+        .iter()
+        .map(|&(x, y)| Actor::new_world(map.grid_to_world(x, y), 180.0))
+        .collect();
+
+    for _ in 0..600 {
+        for actor in &mut actors {
+            steer_along_flow_field_with_map(&map, &field, actor, 1.0 / 60.0);
+        }
+    }
+
+    for actor in &actors {
+        assert_eq!(map.world_to_grid(actor.pos.0, actor.pos.1), goal);
+    }
+}
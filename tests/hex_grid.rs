@@ -0,0 +1,210 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use enemy_pathfinder::map::{
+    HexLayout, RuntimeMapAdapter, RuntimeMapAdapterError, StaggerAxis, StaggerIndex,
+};
+use enemy_pathfinder::pathfinding::{
+    DiagonalMovement, FlowField, PathOptions, SearchAlgorithm, astar_with_map,
+    astar_with_map_options, find_path_with_map, path_cost_with_map,
+};
+use enemy_pathfinder::visibility::{has_line_of_sight_with_map, los_grid_with_map};
+
+mod common;
+
+use common::temp_dir;
+
+/// 6x5 pointy-topped hex map with a wall column at x = 3 that is open only
+/// in the bottom row.
+fn write_hex_map(dir: &Path, orientation: &str) -> PathBuf {
+    let map_path = dir.join("hex.json");
+    let json = format!(
+        r#"{{
+  "width": 6,
+  "height": 5,
+  "tilewidth": 28,
+  "tileheight": 32,
+  "orientation": "{orientation}",
+  "hexsidelength": 16,
+  "staggeraxis": "y",
+  "staggerindex": "odd",
+  "layers": [
+    {{
+      "type": "tilelayer",
+      "name": "Wall_Layer",
+      "width": 6,
+      "height": 5,
+      "data": [0, 0, 0, 1, 0, 0,
+               0, 0, 0, 1, 0, 0,
+               0, 0, 0, 1, 0, 0,
+               0, 0, 0, 1, 0, 0,
+               0, 0, 0, 0, 0, 0]
+    }}
+  ]
+}}"#
+    );
+
+    fs::write(&map_path, json).expect("failed to write map fixture");
+    map_path
+}
+
+fn layouts() -> Vec<HexLayout> {
+    let mut layouts = Vec::new();
+    for stagger_axis in [StaggerAxis::X, StaggerAxis::Y] {
+        for stagger_index in [StaggerIndex::Odd, StaggerIndex::Even] {
+            layouts.push(HexLayout {
+                tile_width: 28.0,
+                tile_height: 32.0,
+                side_length: 16.0,
+                stagger_axis,
+                stagger_index,
+            });
+        }
+    }
+    layouts
+}
+
+fn open_hex_map(layout: HexLayout, width: usize, height: usize) -> RuntimeMapAdapter {
+    RuntimeMapAdapter::from_solid_cells(28.0, width, height, vec![false; width * height])
+        .with_hex_layout(layout)
+}
+
+#[test]
+fn tiled_hex_maps_parse_their_layout() {
+    let dir = temp_dir("hex_parse");
+    let map = RuntimeMapAdapter::from_tiled_json_wall_layer(write_hex_map(&dir, "hexagonal"))
+        .expect("hex map should load");
+
+    assert_eq!(
+        map.hex,
+        Some(HexLayout {
+            tile_width: 28.0,
+            tile_height: 32.0,
+            side_length: 16.0,
+            stagger_axis: StaggerAxis::Y,
+            stagger_index: StaggerIndex::Odd,
+        })
+    );
+    assert_eq!((map.width, map.height), (6, 5));
+    assert_eq!(map.summary().solid_cells, 4);
+    // Row 1 is shifted right by half a tile; rows are 24 units apart.
+    assert_eq!(map.grid_to_world(0, 0), (14.0, 16.0));
+    assert_eq!(map.grid_to_world(0, 1), (28.0, 40.0));
+}
+
+#[test]
+fn other_orientations_are_rejected() {
+    let dir = temp_dir("hex_iso");
+    let err = RuntimeMapAdapter::from_tiled_json_wall_layer(write_hex_map(&dir, "isometric"))
+        .expect_err("isometric maps are not supported");
+    assert!(matches!(
+        err,
+        RuntimeMapAdapterError::UnsupportedOrientation { ref orientation } if orientation == "isometric"
+    ));
+}
+
+#[test]
+fn world_and_hex_coordinates_round_trip() {
+    for layout in layouts() {
+        let map = open_hex_map(layout, 7, 6);
+        for y in 0..map.height {
+            for x in 0..map.width {
+                let (wx, wy) = map.grid_to_world(x, y);
+                assert_eq!(map.world_to_grid(wx, wy), (x, y), "{layout:?}");
+                assert_eq!(map.world_to_grid(wx + 3.0, wy - 3.0), (x, y), "{layout:?}");
+
+                let (q, r) = layout.to_axial(x as i32, y as i32);
+                assert_eq!(layout.from_axial(q, r), (x as i32, y as i32));
+            }
+        }
+    }
+}
+
+#[test]
+fn every_cell_has_six_neighbors_one_step_away() {
+    for layout in layouts() {
+        for (x, y) in [(2, 2), (3, 2), (2, 3), (3, 3)] {
+            let offsets = layout.neighbor_offsets(x, y);
+            assert_eq!(offsets.len(), 6);
+            for &(dx, dy) in offsets {
+                let n = ((x as i32 + dx) as usize, (y as i32 + dy) as usize);
+                assert_eq!(layout.distance((x, y), n), 1, "{layout:?} {n:?}");
+
+                // Adjacency is symmetric.
+                let back = layout.neighbor_offsets(n.0, n.1);
+                assert!(
+                    back.iter()
+                        .any(|&(bx, by)| (n.0 as i32 + bx, n.1 as i32 + by)
+                            == (x as i32, y as i32)),
+                    "{layout:?} {n:?}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn astar_walks_six_neighbors_at_straight_cost() {
+    for layout in layouts() {
+        let map = open_hex_map(layout, 8, 7);
+        let start = (1, 1);
+        let goal = (6, 5);
+        let result = astar_with_map(&map, start, goal);
+        assert!(result.is_found());
+
+        let steps = layout.distance(start, goal);
+        assert_eq!(result.cells.len(), steps + 1, "{layout:?}");
+        assert_eq!(result.cost, steps as i32 * 100);
+        assert_eq!(path_cost_with_map(&map, &result.cells), result.cost);
+        for pair in result.cells.windows(2) {
+            assert_eq!(layout.distance(pair[0], pair[1]), 1);
+        }
+    }
+}
+
+#[test]
+fn hex_paths_detour_around_walls_for_every_algorithm() {
+    let dir = temp_dir("hex_detour");
+    let map = RuntimeMapAdapter::from_tiled_json_wall_layer(write_hex_map(&dir, "hexagonal"))
+        .expect("hex map should load");
+    let layout = map.hex.unwrap();
+
+    let expected = astar_with_map(&map, (1, 0), (5, 0));
+    assert!(expected.is_found());
+    assert!(expected.cells.contains(&(3, 4)), "{:?}", expected.cells);
+
+    for algorithm in [
+        SearchAlgorithm::JumpPoint,
+        SearchAlgorithm::ThetaStar,
+        SearchAlgorithm::Bidirectional,
+    ] {
+        let options = PathOptions::eight_way(DiagonalMovement::Always).with_algorithm(algorithm);
+        let result = find_path_with_map(&map, (1, 0), (5, 0), &options);
+        assert_eq!(result.cost, expected.cost, "{algorithm:?}");
+        for pair in result.cells.windows(2) {
+            assert_eq!(layout.distance(pair[0], pair[1]), 1, "{algorithm:?}");
+        }
+    }
+
+    let field = FlowField::from_map(&map, (5, 0), &PathOptions::default());
+    assert_eq!(field.distance(1, 0), Some(expected.cost));
+    assert_eq!(
+        astar_with_map_options(&map, (1, 0), (5, 0), &PathOptions::default()).cost,
+        expected.cost
+    );
+}
+
+#[test]
+fn hex_line_of_sight_stops_at_walls() {
+    let dir = temp_dir("hex_los");
+    let map = RuntimeMapAdapter::from_tiled_json_wall_layer(write_hex_map(&dir, "hexagonal"))
+        .expect("hex map should load");
+
+    assert!(los_grid_with_map(&map, (0, 0), (2, 3)));
+    assert!(!los_grid_with_map(&map, (1, 1), (5, 1)));
+    assert!(los_grid_with_map(&map, (0, 4), (5, 4)));
+
+    let a = map.grid_to_world(1, 2);
+    let b = map.grid_to_world(5, 2);
+    assert!(!has_line_of_sight_with_map(&map, a, b));
+}
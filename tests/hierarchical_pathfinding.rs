@@ -0,0 +1,190 @@
+use std::path::PathBuf;
+
+use enemy_pathfinder::map::RuntimeMapAdapter;
+use enemy_pathfinder::pathfinding::{
+    DiagonalMovement, HpaGraph, PathFailure, PathOptions, astar_with_map_options,
+    path_cost_with_map,
+};
+
+fn assets_map_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("assets")
+        .join("map.json")
+}
+
+fn load_runtime_map() -> RuntimeMapAdapter {
+    RuntimeMapAdapter::from_tiled_json_wall_layer(assets_map_path())
+        .expect("assets/map.json Wall_Layer should parse")
+}
+
+fn open_cells(map: &RuntimeMapAdapter) -> Vec<(usize, usize)> {
+    (0..map.height)
+        .flat_map(|y| (0..map.width).map(move |x| (x, y)))
+        .filter(|&(x, y)| !map.is_wall(x, y))
+        .collect()
+}
+
+fn assert_walkable_chain(map: &RuntimeMapAdapter, path: &[(usize, usize)]) {
+    for &(x, y) in path {
+        assert!(!map.is_wall(x, y), "path crosses wall at ({x}, {y})");
+    }
+    for w in path.windows(2) {
+        assert!(
+            w[0].0.abs_diff(w[1].0) <= 1 && w[0].1.abs_diff(w[1].1) <= 1 && w[0] != w[1],
+            "path cells {:?} -> {:?} are not adjacent",
+            w[0],
+            w[1]
+        );
+    }
+}
+
+#[test]
+fn graph_partitions_bundled_map_into_clusters_with_entrances() {
+    let map = load_runtime_map();
+    let graph = HpaGraph::build(&map, 10, &PathOptions::default());
+
+    assert_eq!(graph.cluster_size(), 10);
+    assert_eq!(graph.cluster_count(), 6);
+    assert!(graph.node_count() > 0);
+    assert!(graph.edge_count() >= graph.node_count());
+}
+
+#[test]
+fn hpa_paths_connect_the_same_cells_as_astar_with_bounded_cost() {
+    let map = load_runtime_map();
+    let cells = open_cells(&map);
+
+    for options in [
+        PathOptions::four_way(),
+        PathOptions::eight_way(DiagonalMovement::OnlyWhenNoObstacles),
+        PathOptions::eight_way(DiagonalMovement::Always),
+    ] {
+        let graph = HpaGraph::build(&map, 8, &options);
+
+        for &start in cells.iter().step_by(37) {
+            for &goal in cells.iter().step_by(11) {
+                let exact = astar_with_map_options(&map, start, goal, &options).cells;
+                let result = graph.find_path_with_map(&map, start, goal);
+                let hpa = &result.cells;
+
+                assert_eq!(
+                    exact.is_empty(),
+                    !result.is_found(),
+                    "{start:?} -> {goal:?}"
+                );
+                if hpa.is_empty() {
+                    assert_eq!(result.failure, Some(PathFailure::Unreachable));
+                    continue;
+                }
+
+                assert_eq!(hpa.first().copied(), Some(start));
+                assert_eq!(hpa.last().copied(), Some(goal));
+                assert_walkable_chain(&map, hpa);
+
+                let exact_cost = path_cost_with_map(&map, &exact);
+                let hpa_cost = path_cost_with_map(&map, hpa);
+                assert_eq!(result.cost, hpa_cost);
+                assert!(hpa_cost >= exact_cost);
+                assert!(
+                    hpa_cost <= exact_cost * 2,
+                    "{start:?} -> {goal:?}: hpa {hpa_cost} vs exact {exact_cost}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn hpa_rejects_wall_endpoints_and_handles_trivial_queries() {
+    let map = load_runtime_map();
+    let graph = HpaGraph::build(&map, 10, &PathOptions::default());
+
+    assert_eq!(
+        graph.find_path_with_map(&map, (0, 0), (1, 1)).failure,
+        Some(PathFailure::StartInWall)
+    );
+    assert_eq!(
+        graph.find_path_with_map(&map, (1, 1), (0, 0)).failure,
+        Some(PathFailure::GoalInWall)
+    );
+    assert_eq!(
+        graph.find_path_with_map(&map, (1, 1), (1, 1)).cells,
+        vec![(1, 1)]
+    );
+}
+
+#[test]
+fn hpa_reports_unreachable_goal_behind_sealed_cluster() {
+    let rows = [
+        "..........",
+        "..........",
+        "......####",
+        "......#...",
+        "......#...",
+    ];
+    let solid = rows
+        .iter()
+        .flat_map(|row| row.chars().map(|c| c == '#'))
+        .collect();
+    let map = RuntimeMapAdapter::from_solid_cells(32.0, 10, 5, solid);
+    let graph = HpaGraph::build(&map, 3, &PathOptions::default());
+
+    let sealed = graph.find_path_with_map(&map, (0, 0), (8, 4));
+    assert_eq!(sealed.failure, Some(PathFailure::Unreachable));
+    assert!(sealed.cells.is_empty());
+    assert!(sealed.expanded > 0);
+    assert!(graph.find_path_with_map(&map, (0, 0), (9, 1)).is_found());
+}
+
+#[test]
+fn hpa_crosses_cluster_corners_diagonally() {
+    let rows = ["..#.", "..#.", "##..", "...."];
+    let solid = rows
+        .iter()
+        .flat_map(|row| row.chars().map(|c| c == '#'))
+        .collect();
+    let map = RuntimeMapAdapter::from_solid_cells(32.0, 4, 4, solid);
+
+    let options = PathOptions::eight_way(DiagonalMovement::Always);
+    let graph = HpaGraph::build(&map, 2, &options);
+    let path = graph.find_path_with_map(&map, (0, 0), (3, 3)).cells;
+    assert!(
+        !astar_with_map_options(&map, (0, 0), (3, 3), &options)
+            .cells
+            .is_empty()
+    );
+    assert_walkable_chain(&map, &path);
+    assert_eq!(path.first(), Some(&(0, 0)));
+    assert_eq!(path.last(), Some(&(3, 3)));
+
+    let options = PathOptions::eight_way(DiagonalMovement::IfAtMostOneObstacle);
+    let graph = HpaGraph::build(&map, 2, &options);
+    assert!(!graph.find_path_with_map(&map, (0, 0), (3, 3)).is_found());
+}
+
+#[test]
+fn closest_fallback_never_leaks_partial_paths_out_of_a_cluster() {
+    // The middle cluster's two entrances are split by its inner wall.
+    let rows = ["....#....", "....#....", "....#...."];
+    let solid = rows
+        .iter()
+        .flat_map(|row| row.chars().map(|c| c == '#'))
+        .collect();
+    let map = RuntimeMapAdapter::from_solid_cells(32.0, 9, 3, solid);
+    let options = PathOptions::four_way().with_closest_fallback(true);
+    let graph = HpaGraph::build(&map, 3, &options);
+    let cells = open_cells(&map);
+
+    for &start in &cells {
+        for &goal in &cells {
+            let exact = astar_with_map_options(&map, start, goal, &options);
+            let hpa = graph.find_path_with_map(&map, start, goal).cells;
+
+            assert_eq!(exact.is_found(), !hpa.is_empty(), "{start:?} -> {goal:?}");
+            if !hpa.is_empty() {
+                assert_eq!(hpa.last().copied(), Some(goal));
+                assert_walkable_chain(&map, &hpa);
+            }
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use std::path::PathBuf;
+
+use enemy_pathfinder::map::RuntimeMapAdapter;
+use enemy_pathfinder::pathfinding::{
+    DStarLite, DiagonalMovement, PathOptions, PathResult, astar_with_map_options,
+    path_cost_with_map,
+};
+
+mod common;
+
+use common::XorShift;
+
+fn assets_map_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("assets")
+        .join("map.json")
+}
+
+fn load_runtime_map() -> RuntimeMapAdapter {
+    RuntimeMapAdapter::from_tiled_json_wall_layer(assets_map_path())
+        .expect("assets/map.json Wall_Layer should parse")
+}
+
+fn assert_matches_astar(
+    map: &RuntimeMapAdapter,
+    planner: &mut DStarLite,
+    options: &PathOptions,
+) -> PathResult {
+    let (start, goal) = (planner.start(), planner.goal());
+    let repaired = planner.compute_path();
+    let fresh = astar_with_map_options(map, start, goal, options);
+
+    assert_eq!(
+        repaired.failure, fresh.failure,
+        "outcome differs for {start:?} -> {goal:?}"
+    );
+    if fresh.is_found() {
+        assert_eq!(repaired.cells.first().copied(), Some(start));
+        assert_eq!(repaired.cells.last().copied(), Some(goal));
+        assert_eq!(repaired.cost, path_cost_with_map(map, &repaired.cells));
+        assert_eq!(
+            repaired.cost, fresh.cost,
+            "cost differs for {start:?} -> {goal:?}"
+        );
+    }
+    repaired
+}
+
+#[test]
+fn initial_plan_matches_astar_on_bundled_map() {
+    let map = load_runtime_map();
+    let options = PathOptions::eight_way(DiagonalMovement::OnlyWhenNoObstacles);
+    let mut planner = DStarLite::new(&map, (2, 18), (26, 10), &options);
+
+    let path = assert_matches_astar(&map, &mut planner, &options);
+
+    assert!(path.is_found());
+}
+
+#[test]
+fn chase_replans_expand_fewer_cells_than_fresh_searches() {
+    let map = load_runtime_map();
+    let options = PathOptions::four_way();
+    let goals = [(26, 10), (26, 11), (25, 11), (25, 12), (24, 12), (24, 13)];
+    let mut planner = DStarLite::new(&map, (2, 18), goals[0], &options);
+    let mut path = planner.compute_path().cells;
+
+    let mut repair_total = 0;
+    let mut fresh_total = 0;
+    for step in 1..18 {
+        planner.set_start(path[1]);
+        if step % 3 == 0 {
+            planner.set_goal(goals[step / 3]);
+        }
+        let repaired = assert_matches_astar(&map, &mut planner, &options);
+        repair_total += repaired.expanded;
+        path = repaired.cells;
+
+        let mut fresh = DStarLite::new(&map, planner.start(), planner.goal(), &options);
+        fresh_total += fresh.compute_path().expanded;
+    }
+
+    assert!(
+        repair_total * 2 < fresh_total,
+        "repairs expanded {repair_total}, fresh searches expanded {fresh_total}"
+    );
+}
+
+#[test]
+fn start_moves_along_the_path_need_no_reexpansion() {
+    let map = load_runtime_map();
+    let options = PathOptions::eight_way(DiagonalMovement::OnlyWhenNoObstacles);
+    let mut planner = DStarLite::new(&map, (2, 18), (26, 10), &options);
+    let path = planner.compute_path().cells;
+
+    planner.set_start(path[3]);
+    let rest = assert_matches_astar(&map, &mut planner, &options);
+
+    assert_eq!(rest.expanded, 0);
+    assert_eq!(rest.cells.len(), path.len() - 3);
+}
+
+#[test]
+fn new_wall_on_the_path_is_routed_around() {
+    let mut map = RuntimeMapAdapter::from_solid_cells(32.0, 7, 3, vec![false; 21]);
+    let options = PathOptions::four_way();
+    let mut planner = DStarLite::new(&map, (0, 1), (6, 1), &options);
+    assert_eq!(planner.compute_path().cells.len(), 7);
+
+    map.set_solid(3, 1, true);
+    planner.update_cell(&map, 3, 1);
+    let path = assert_matches_astar(&map, &mut planner, &options);
+
+    assert!(!path.cells.contains(&(3, 1)));
+    assert_eq!(path.cells.len(), 9);
+}
+
+/// A wide agent keeps a tile of room from walls and the map edge, so every
+/// wall toggle also opens or closes the cells around it.
+#[test]
+fn wall_changes_update_clearance_for_wide_agents() {
+    let mut rng = XorShift(0x9e37_79b9_7f4a_7c15);
+    let (width, height) = (16, 12);
+    let mut map =
+        RuntimeMapAdapter::from_solid_cells(32.0, width, height, vec![false; width * height]);
+    let options = PathOptions::four_way().with_agent_radius(20.0);
+    let mut planner = DStarLite::new(&map, (2, 2), (width - 3, height - 3), &options);
+    let mut reachable = 0;
+
+    for _ in 0..60 {
+        let (x, y) = (2 + rng.below(width - 4), 2 + rng.below(height - 4));
+        if (x, y) != planner.start() && (x, y) != planner.goal() {
+            map.set_solid(x, y, !map.is_wall(x, y));
+            planner.update_cell(&map, x, y);
+        }
+        if assert_matches_astar(&map, &mut planner, &options).is_found() {
+            reachable += 1;
+        }
+    }
+    assert!(reachable > 10);
+}
+
+#[test]
+fn randomized_start_goal_and_wall_changes_stay_optimal() {
+    let mut rng = XorShift(0x2545_f491_4f6c_dd1d);
+
+    for diagonal in [
+        DiagonalMovement::Never,
+        DiagonalMovement::OnlyWhenNoObstacles,
+        DiagonalMovement::Always,
+    ] {
+        let options = PathOptions::eight_way(diagonal);
+        let (width, height) = (14, 11);
+        let solid = (0..width * height).map(|_| rng.below(100) < 25).collect();
+        let mut map = RuntimeMapAdapter::from_solid_cells(32.0, width, height, solid);
+        let mut planner = DStarLite::new(&map, (0, 0), (width - 1, height - 1), &options);
+
+        for _ in 0..120 {
+            match rng.below(3) {
+                0 => planner.set_start((rng.below(width), rng.below(height))),
+                1 => planner.set_goal((rng.below(width), rng.below(height))),
+                _ => {
+                    let (x, y) = (rng.below(width), rng.below(height));
+                    map.set_solid(x, y, !map.is_wall(x, y));
+                    planner.update_cell(&map, x, y);
+                }
+            }
+            assert_matches_astar(&map, &mut planner, &options);
+        }
+    }
+}
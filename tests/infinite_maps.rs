@@ -0,0 +1,194 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use enemy_pathfinder::actor::Actor;
+use enemy_pathfinder::map::{RuntimeMapAdapter, RuntimeMapAdapterError};
+use enemy_pathfinder::pathfinding::{astar_with_map, path_cost_with_map};
+use enemy_pathfinder::physics::resolve_circle_map_with_map;
+use enemy_pathfinder::visibility::has_line_of_sight_with_map;
+
+mod common;
+
+use common::temp_dir;
+
+/// Infinite map of 4x4 chunks. The wall layer has a wall along tile column
+/// 0 from row -4 down to row 1, plus a lone wall at (-2, -2); the chunk at
+/// (-4, 0) is missing. The cost layer reaches out to column 7 with a chunk
+/// of cost-3 tiles.
+fn write_infinite_map(dir: &Path, wall_chunk_len: usize) -> PathBuf {
+    let map_path = dir.join("infinite.json");
+    let mut lone_wall = vec![0; 16];
+    lone_wall[2 * 4 + 2] = 1;
+    lone_wall.truncate(wall_chunk_len);
+    let json = format!(
+        r#"{{
+  "width": 4,
+  "height": 4,
+  "tilewidth": 32,
+  "tileheight": 32,
+  "infinite": true,
+  "layers": [
+    {{
+      "type": "tilelayer",
+      "name": "Wall_Layer",
+      "startx": -4,
+      "starty": -4,
+      "width": 8,
+      "height": 8,
+      "chunks": [
+        {{ "x": -4, "y": -4, "width": 4, "height": 4, "data": {lone_wall:?} }},
+        {{ "x": 0, "y": -4, "width": 4, "height": 4,
+           "data": [1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0] }},
+        {{ "x": 0, "y": 0, "width": 4, "height": 4,
+           "data": [1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0] }}
+      ]
+    }},
+    {{
+      "type": "tilelayer",
+      "name": "Cost_Layer",
+      "properties": [{{ "name": "cost", "type": "float", "value": 3.0 }}],
+      "chunks": [
+        {{ "x": 4, "y": 0, "width": 4, "height": 4,
+           "data": [2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2] }}
+      ]
+    }}
+  ]
+}}"#
+    );
+
+    fs::write(&map_path, json).expect("failed to write map fixture");
+    map_path
+}
+
+fn load_infinite_map(tag: &str) -> RuntimeMapAdapter {
+    let dir = temp_dir(tag);
+    RuntimeMapAdapter::from_tiled_json_wall_layer(write_infinite_map(&dir, 16))
+        .expect("infinite map should load")
+}
+
+fn cell(map: &RuntimeMapAdapter, tx: i32, ty: i32) -> (usize, usize) {
+    map.tile_to_cell(tx, ty).expect("tile inside the map")
+}
+
+#[test]
+fn chunks_of_all_layers_are_covered() {
+    let map = load_infinite_map("load");
+    assert_eq!(map.origin, (-4, -4));
+    assert_eq!((map.width, map.height), (12, 8));
+    assert_eq!(map.summary().solid_cells, 7);
+
+    let (x, y) = cell(&map, -2, -2);
+    assert!(map.is_wall(x, y));
+    let (x, y) = cell(&map, -2, 2);
+    assert!(!map.is_wall(x, y), "missing chunks are empty");
+    let (x, y) = cell(&map, 5, 1);
+    assert_eq!(map.traversal_cost(x, y), 3.0);
+
+    assert_eq!(map.tile_to_cell(-5, 0), None);
+    assert_eq!(map.tile_to_cell(8, 0), None);
+    assert_eq!(map.cell_to_tile(0, 0), (-4, -4));
+}
+
+#[test]
+fn world_positions_keep_tiled_coordinates() {
+    let map = load_infinite_map("convert");
+    // Tile (-2, -2) spans world -64..-32 on both axes.
+    assert_eq!(map.grid_to_world(2, 2), (-48.0, -48.0));
+    assert_eq!(map.world_to_grid(-48.0, -48.0), (2, 2));
+    assert_eq!(map.world_to_grid(-0.5, 0.5), cell(&map, -1, 0));
+
+    for y in 0..map.height {
+        for x in 0..map.width {
+            let (wx, wy) = map.grid_to_world(x, y);
+            assert_eq!(map.world_to_grid(wx, wy), (x, y));
+        }
+    }
+}
+
+#[test]
+fn paths_cross_chunk_seams_around_walls() {
+    let map = load_infinite_map("path");
+    let start = cell(&map, -1, -3);
+    let goal = cell(&map, 1, -3);
+
+    let result = astar_with_map(&map, start, goal);
+    assert!(result.is_found());
+    assert_eq!(path_cost_with_map(&map, &result.cells), result.cost);
+    assert!(
+        result
+            .cells
+            .iter()
+            .any(|&(x, y)| map.cell_to_tile(x, y) == (0, 2) || map.cell_to_tile(x, y) == (0, 3)),
+        "{:?}",
+        result.cells
+    );
+}
+
+#[test]
+fn physics_and_sight_see_walls_at_negative_coordinates() {
+    let map = load_infinite_map("physics");
+
+    // Wall (-2, -2) spans x -64..-32.
+    let mut actor = Actor::new_world((-28.0, -48.0), 0.0);
+    actor.radius = 6.0;
+    resolve_circle_map_with_map(&map, &mut actor);
+    assert!((actor.pos.0 + 26.0).abs() < 1e-4, "{:?}", actor.pos);
+
+    let a = map.grid_to_world(cell(&map, -1, -3).0, cell(&map, -1, -3).1);
+    let b = map.grid_to_world(cell(&map, 1, -3).0, cell(&map, 1, -3).1);
+    assert!(!has_line_of_sight_with_map(&map, a, b));
+    assert!(has_line_of_sight_with_map(
+        &map,
+        (-40.0, 80.0),
+        (40.0, 80.0)
+    ));
+}
+
+#[test]
+fn short_chunks_are_rejected() {
+    let dir = temp_dir("infinite_short");
+    let err = RuntimeMapAdapter::from_tiled_json_wall_layer(write_infinite_map(&dir, 15))
+        .expect_err("a chunk is missing a tile");
+    assert!(matches!(
+        err,
+        RuntimeMapAdapterError::InvalidLayerDataLen {
+            expected: 16,
+            actual: 15,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn chunks_spread_too_far_apart_are_rejected() {
+    let dir = temp_dir("infinite_sparse");
+    let map_path = dir.join("sparse.json");
+    let json = r#"{
+  "width": 4,
+  "height": 4,
+  "tilewidth": 32,
+  "tileheight": 32,
+  "infinite": true,
+  "layers": [
+    {
+      "type": "tilelayer",
+      "name": "Wall_Layer",
+      "chunks": [
+        { "x": 0, "y": 0, "width": 1, "height": 1, "data": [1] },
+        { "x": 100000, "y": 100000, "width": 1, "height": 1, "data": [1] }
+      ]
+    }
+  ]
+}"#;
+    fs::write(&map_path, json).expect("failed to write map fixture");
+
+    let err = RuntimeMapAdapter::from_tiled_json_wall_layer(&map_path)
+        .expect_err("the chunks span 10^10 cells");
+    assert!(matches!(
+        err,
+        RuntimeMapAdapterError::InfiniteMapTooLarge {
+            width: 100001,
+            height: 100001,
+        }
+    ));
+}
@@ -0,0 +1,128 @@
+use std::path::PathBuf;
+
+use enemy_pathfinder::map::RuntimeMapAdapter;
+use enemy_pathfinder::pathfinding::{
+    DiagonalMovement, PathOptions, SearchAlgorithm, astar_with_map_options, find_path_with_map,
+};
+
+mod common;
+
+use common::{XorShift, path_cost};
+
+const ALL_RULES: [DiagonalMovement; 4] = [
+    DiagonalMovement::Never,
+    DiagonalMovement::Always,
+    DiagonalMovement::IfAtMostOneObstacle,
+    DiagonalMovement::OnlyWhenNoObstacles,
+];
+
+fn assets_map_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("assets")
+        .join("map.json")
+}
+
+fn random_grid(rng: &mut XorShift, width: usize, height: usize) -> RuntimeMapAdapter {
+    let solid = (0..width * height).map(|_| rng.below(100) < 30).collect();
+    RuntimeMapAdapter::from_solid_cells(32.0, width, height, solid)
+}
+
+fn assert_valid_path(map: &RuntimeMapAdapter, path: &[(usize, usize)]) {
+    for &(x, y) in path {
+        assert!(!map.is_wall(x, y), "path crosses wall at ({x}, {y})");
+    }
+    for w in path.windows(2) {
+        assert!(
+            w[0].0.abs_diff(w[1].0) <= 1 && w[0].1.abs_diff(w[1].1) <= 1 && w[0] != w[1],
+            "path cells {:?} -> {:?} are not adjacent",
+            w[0],
+            w[1]
+        );
+    }
+}
+
+fn assert_same_cost(
+    map: &RuntimeMapAdapter,
+    start: (usize, usize),
+    goal: (usize, usize),
+    rule: DiagonalMovement,
+) {
+    let options = PathOptions::eight_way(rule);
+    let astar = astar_with_map_options(map, start, goal, &options).cells;
+    let jps = find_path_with_map(
+        map,
+        start,
+        goal,
+        &options.with_algorithm(SearchAlgorithm::JumpPoint),
+    )
+    .cells;
+
+    assert_eq!(
+        astar.is_empty(),
+        jps.is_empty(),
+        "reachability differs for {start:?} -> {goal:?} with {rule:?}"
+    );
+    if jps.is_empty() {
+        return;
+    }
+
+    assert_eq!(jps.first().copied(), Some(start));
+    assert_eq!(jps.last().copied(), Some(goal));
+    assert_valid_path(map, &jps);
+    assert_eq!(
+        path_cost(&jps),
+        path_cost(&astar),
+        "cost differs for {start:?} -> {goal:?} with {rule:?}"
+    );
+}
+
+#[test]
+fn jps_matches_astar_cost_on_bundled_map() {
+    let map = RuntimeMapAdapter::from_tiled_json_wall_layer(assets_map_path())
+        .expect("assets/map.json Wall_Layer should parse");
+    let open: Vec<(usize, usize)> = (0..map.height)
+        .flat_map(|y| (0..map.width).map(move |x| (x, y)))
+        .filter(|&(x, y)| !map.is_wall(x, y))
+        .step_by(7)
+        .collect();
+
+    for rule in ALL_RULES {
+        for &start in open.iter().step_by(5) {
+            for &goal in &open {
+                assert_same_cost(&map, start, goal, rule);
+            }
+        }
+    }
+}
+
+#[test]
+fn jps_matches_astar_cost_on_random_grids() {
+    let mut rng = XorShift(0x9e37_79b9_7f4a_7c15);
+
+    for _ in 0..60 {
+        let width = 4 + rng.below(20);
+        let height = 4 + rng.below(20);
+        let map = random_grid(&mut rng, width, height);
+
+        for rule in ALL_RULES {
+            for _ in 0..10 {
+                let start = (rng.below(width), rng.below(height));
+                let goal = (rng.below(width), rng.below(height));
+                assert_same_cost(&map, start, goal, rule);
+            }
+        }
+    }
+}
+
+#[test]
+fn jps_falls_back_to_astar_on_weighted_maps() {
+    let map = RuntimeMapAdapter::from_solid_cells(32.0, 3, 3, vec![false; 9])
+        .with_traversal_costs(vec![1.0, 1.0, 1.0, 1.0, 9.0, 1.0, 1.0, 1.0, 1.0]);
+    let options =
+        PathOptions::eight_way(DiagonalMovement::Always).with_algorithm(SearchAlgorithm::JumpPoint);
+
+    let path = find_path_with_map(&map, (0, 0), (2, 2), &options).cells;
+
+    assert!(!path.contains(&(1, 1)));
+    assert_eq!(path.last().copied(), Some((2, 2)));
+}
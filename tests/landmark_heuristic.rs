@@ -0,0 +1,266 @@
+use std::fs;
+use std::path::PathBuf;
+
+use enemy_pathfinder::map::{CellRect, Obstacle, RuntimeMapAdapter};
+use enemy_pathfinder::pathfinding::{
+    DiagonalMovement, FlowField, LandmarkTable, PathOptions, PathSearch, SearchStatus,
+    astar_with_map, astar_with_map_options,
+};
+
+mod common;
+
+use common::{grid_from_rows, temp_dir};
+
+fn assets_map_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("assets")
+        .join("map.json")
+}
+
+fn load_runtime_map() -> RuntimeMapAdapter {
+    RuntimeMapAdapter::from_tiled_json_wall_layer(assets_map_path())
+        .expect("assets/map.json Wall_Layer should parse")
+}
+
+/// Two rooms joined only at the far right end: octile distance points
+/// straight at the dividing wall and plain A* floods the lower room.
+fn divided_rooms() -> RuntimeMapAdapter {
+    grid_from_rows(&[
+        "....................",
+        "....................",
+        "....................",
+        "....................",
+        "###################.",
+        "....................",
+        "....................",
+        "....................",
+        "....................",
+    ])
+}
+
+fn open_cells(map: &RuntimeMapAdapter) -> Vec<(usize, usize)> {
+    (0..map.height)
+        .flat_map(|y| (0..map.width).map(move |x| (x, y)))
+        .filter(|&(x, y)| !map.is_wall(x, y))
+        .collect()
+}
+
+#[test]
+fn landmarks_keep_costs_and_cut_expansions_on_a_maze() {
+    let plain = divided_rooms();
+    let mut map = divided_rooms();
+    let table = LandmarkTable::build(&map, 4, &PathOptions::default());
+    assert_eq!(table.landmarks().len(), 4);
+    map.set_landmarks(table).expect("table built for this map");
+
+    let without = astar_with_map(&plain, (0, 5), (0, 3));
+    let with = astar_with_map(&map, (0, 5), (0, 3));
+    assert!(with.is_found());
+    assert_eq!(with.cost, without.cost);
+    assert!(
+        with.expanded < without.expanded,
+        "landmarks expanded {} nodes, plain A* {}",
+        with.expanded,
+        without.expanded
+    );
+}
+
+#[test]
+fn landmark_searches_stay_optimal_on_the_assets_map() {
+    let plain = load_runtime_map();
+    let cells = open_cells(&plain);
+
+    for diagonal in [
+        DiagonalMovement::Never,
+        DiagonalMovement::OnlyWhenNoObstacles,
+    ] {
+        let options = PathOptions::eight_way(diagonal);
+        let mut map = load_runtime_map();
+        map.set_landmarks(LandmarkTable::build(&map, 6, &options))
+            .expect("table built for this map");
+
+        for (i, &start) in cells.iter().enumerate().step_by(37) {
+            let goal = cells[(i * 7 + 11) % cells.len()];
+            let without = astar_with_map_options(&plain, start, goal, &options);
+            let with = astar_with_map_options(&map, start, goal, &options);
+            assert_eq!(
+                with.cost, without.cost,
+                "{diagonal:?} {start:?} -> {goal:?}"
+            );
+            assert_eq!(with.failure, without.failure);
+            assert!(with.expanded <= without.expanded);
+        }
+    }
+}
+
+#[test]
+fn lower_bound_never_exceeds_the_true_cost() {
+    let map = load_runtime_map();
+    let options = PathOptions::eight_way(DiagonalMovement::OnlyWhenNoObstacles);
+    let table = LandmarkTable::build(&map, 4, &options);
+    let cells = open_cells(&map);
+
+    for &goal in cells.iter().step_by(53) {
+        let field = FlowField::from_map(&map, goal, &options);
+        for &cell in &cells {
+            if let Some(cost) = field.distance(cell.0, cell.1) {
+                assert!(
+                    table.lower_bound(cell, goal) <= cost,
+                    "{cell:?} -> {goal:?}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn tables_for_other_options_are_ignored() {
+    let plain = divided_rooms();
+    let mut map = divided_rooms();
+    map.set_landmarks(LandmarkTable::build(&map, 4, &PathOptions::default()))
+        .expect("table built for this map");
+
+    let options = PathOptions::eight_way(DiagonalMovement::Always);
+    assert!(!map.landmarks().unwrap().supports(&options));
+    assert_eq!(
+        astar_with_map_options(&map, (0, 5), (0, 3), &options),
+        astar_with_map_options(&plain, (0, 5), (0, 3), &options)
+    );
+}
+
+#[test]
+fn time_sliced_search_uses_landmarks() {
+    let mut map = divided_rooms();
+    map.set_landmarks(LandmarkTable::build(&map, 4, &PathOptions::default()))
+        .expect("table built for this map");
+    let expected = astar_with_map(&map, (0, 5), (0, 3));
+
+    let mut search = PathSearch::new(&map, (0, 5), (0, 3), &PathOptions::default());
+    let result = loop {
+        match search.step(&map, 5) {
+            SearchStatus::InProgress => continue,
+            SearchStatus::Found(result) => break result,
+            SearchStatus::Failed(result) => panic!("search failed: {result:?}"),
+        }
+    };
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn mismatched_tables_are_rejected_and_edits_drop_the_table() {
+    let mut map = divided_rooms();
+    let other = grid_from_rows(&["....", "....", "...."]);
+    let foreign = LandmarkTable::build(&other, 2, &PathOptions::default());
+    assert!(map.set_landmarks(foreign).is_err());
+    assert!(map.landmarks().is_none());
+
+    map.set_landmarks(LandmarkTable::build(&map, 2, &PathOptions::default()))
+        .expect("table built for this map");
+    assert!(map.landmarks().is_some());
+    map.set_solid(0, 0, true);
+    assert!(map.landmarks().is_none());
+}
+
+#[test]
+fn tables_only_match_maps_with_the_same_collision_inputs() {
+    let options = PathOptions::default().with_agent_radius(10.0);
+    let table = LandmarkTable::build(&divided_rooms(), 2, &options);
+    assert!(table.matches_map(&divided_rooms()));
+
+    let mut resized = divided_rooms();
+    resized.tile_width = 16.0;
+    let mut shifted = divided_rooms();
+    shifted.origin = (-3, 0);
+    let mut shaped = divided_rooms();
+    shaped.set_cell_shapes(
+        2,
+        2,
+        vec![CellRect {
+            x: 0.0,
+            y: 0.0,
+            width: 8.0,
+            height: 8.0,
+        }],
+    );
+    let blocked =
+        divided_rooms().with_obstacles(vec![Obstacle::Rect(((40.0, 40.0), (44.0, 44.0)))]);
+    for edited in [resized, shifted, shaped, blocked] {
+        assert!(!table.matches_map(&edited));
+    }
+}
+
+#[test]
+fn tables_round_trip_through_the_cache() {
+    let dir = temp_dir("landmarks_cache");
+    let cache_dir = dir.join("cache");
+    let sidecar = LandmarkTable::cache_path(&cache_dir, dir.join("levels").join("level.json"));
+    assert_eq!(sidecar.parent(), Some(cache_dir.as_path()));
+    let name = sidecar.file_name().unwrap().to_string_lossy();
+    assert!(name.starts_with("level-") && name.ends_with(".landmarks.json"));
+
+    let map = divided_rooms();
+    let options = PathOptions::default();
+    let built = LandmarkTable::load_or_build(&sidecar, &map, 3, &options);
+    assert!(sidecar.exists(), "load_or_build should write the table");
+
+    let loaded = LandmarkTable::load(&sidecar).expect("sidecar should load");
+    assert_eq!(loaded, built);
+    assert!(loaded.matches_map(&map));
+    assert!(loaded.supports(&options));
+    assert_eq!(
+        LandmarkTable::load_or_build(&sidecar, &map, 3, &options),
+        built
+    );
+
+    // A changed map invalidates the cached table.
+    let mut edited = divided_rooms();
+    edited.set_solid(5, 0, true);
+    assert!(!loaded.matches_map(&edited));
+    let rebuilt = LandmarkTable::load_or_build(&sidecar, &edited, 3, &options);
+    assert!(rebuilt.matches_map(&edited));
+    assert_eq!(LandmarkTable::load(&sidecar).unwrap(), rebuilt);
+}
+
+#[test]
+fn same_named_maps_in_different_directories_keep_their_own_tables() {
+    let dir = temp_dir("landmarks_same_name");
+    let cache_dir = dir.join("cache");
+    let mut paths = Vec::new();
+    for level in ["one", "two"] {
+        let level_dir = dir.join(level);
+        fs::create_dir_all(&level_dir).unwrap();
+        fs::write(level_dir.join("map.json"), "{}").unwrap();
+        paths.push(LandmarkTable::cache_path(
+            &cache_dir,
+            level_dir.join("map.json"),
+        ));
+    }
+    assert_ne!(paths[0], paths[1]);
+
+    let options = PathOptions::default();
+    let first_map = divided_rooms();
+    let mut second_map = divided_rooms();
+    second_map.set_solid(0, 0, true);
+    let first = LandmarkTable::load_or_build(&paths[0], &first_map, 3, &options);
+    let second = LandmarkTable::load_or_build(&paths[1], &second_map, 3, &options);
+
+    assert_eq!(LandmarkTable::load(&paths[0]).unwrap(), first);
+    assert_eq!(LandmarkTable::load(&paths[1]).unwrap(), second);
+}
+
+#[test]
+fn malformed_sidecars_are_reported() {
+    let dir = temp_dir("landmarks_malformed");
+    let path = dir.join("broken.landmarks.json");
+    fs::write(&path, "{ not json").unwrap();
+    let err = LandmarkTable::load(&path).unwrap_err();
+    assert!(err.to_string().contains("broken.landmarks.json"), "{err}");
+
+    let table = LandmarkTable::build(&divided_rooms(), 1, &PathOptions::default());
+    table.save(&path).unwrap();
+    let text = fs::read_to_string(&path)
+        .unwrap()
+        .replace("\"width\":20", "\"width\":21");
+    fs::write(&path, text).unwrap();
+    assert!(LandmarkTable::load(&path).is_err());
+}
@@ -0,0 +1,156 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use enemy_pathfinder::map::{RuntimeMapAdapter, RuntimeMapAdapterError};
+use flate2::Compression;
+use flate2::write::ZlibEncoder;
+
+mod common;
+
+use common::temp_dir;
+
+fn fixture_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join("layer_encodings")
+        .join(format!("{name}.json"))
+}
+
+fn load_fixture(name: &str) -> RuntimeMapAdapter {
+    RuntimeMapAdapter::from_tiled_json_wall_layer(fixture_path(name))
+        .unwrap_or_else(|err| panic!("{name} fixture should load: {err}"))
+}
+
+/// 2x2 map whose wall layer data is given verbatim, with its encoding and
+/// compression fields.
+fn write_encoded_map(dir: &Path, encoding: &str, compression: &str, data: &str) -> PathBuf {
+    let map_path = dir.join("encoded.json");
+    let json = format!(
+        r#"{{
+  "width": 2,
+  "height": 2,
+  "tilewidth": 32,
+  "tileheight": 32,
+  "layers": [
+    {{
+      "type": "tilelayer",
+      "name": "Wall_Layer",
+      "width": 2,
+      "height": 2,
+      "encoding": "{encoding}",
+      "compression": "{compression}",
+      "data": {data}
+    }}
+  ]
+}}"#
+    );
+
+    fs::write(&map_path, json).expect("failed to write map fixture");
+    map_path
+}
+
+fn gid_bytes(gids: &[u32]) -> Vec<u8> {
+    gids.iter().flat_map(|gid| gid.to_le_bytes()).collect()
+}
+
+#[test]
+fn every_encoding_loads_the_same_grid() {
+    let expected = load_fixture("csv");
+    assert_eq!(expected.summary().solid_cells, 3);
+    assert_eq!(expected.traversal_cost(1, 0), 2.5);
+
+    for name in ["base64", "zlib", "gzip", "zstd"] {
+        let map = load_fixture(name);
+        assert_eq!(map.summary(), expected.summary(), "{name}");
+        assert_eq!(map.solid, expected.solid, "{name}");
+        assert_eq!(map.cost, expected.cost, "{name}");
+    }
+}
+
+#[test]
+fn unknown_compression_is_rejected() {
+    let dir = temp_dir("encoding_lz4");
+    let data = format!("\"{}\"", STANDARD.encode(gid_bytes(&[0, 1, 0, 0])));
+    let err = RuntimeMapAdapter::from_tiled_json_wall_layer(write_encoded_map(
+        &dir, "base64", "lz4", &data,
+    ))
+    .expect_err("lz4 is not a Tiled compression");
+    assert!(matches!(
+        err,
+        RuntimeMapAdapterError::UnsupportedLayerEncoding { ref detail, .. } if detail.contains("lz4")
+    ));
+}
+
+#[test]
+fn corrupt_data_is_reported() {
+    let dir = temp_dir("encoding_corrupt");
+    let not_zlib = format!("\"{}\"", STANDARD.encode(gid_bytes(&[0, 1, 0, 0])));
+    for (compression, data) in [("", "\"@@@@\""), ("zlib", not_zlib.as_str())] {
+        let err = RuntimeMapAdapter::from_tiled_json_wall_layer(write_encoded_map(
+            &dir,
+            "base64",
+            compression,
+            data,
+        ))
+        .expect_err("data does not decode");
+        assert!(
+            matches!(err, RuntimeMapAdapterError::InvalidLayerData { .. }),
+            "{compression}: {err}"
+        );
+    }
+
+    // A whole number of gids, but not the map's cell count.
+    let short = format!("\"{}\"", STANDARD.encode(gid_bytes(&[0, 1, 0])));
+    let err = RuntimeMapAdapter::from_tiled_json_wall_layer(write_encoded_map(
+        &dir, "base64", "", &short,
+    ))
+    .expect_err("one gid is missing");
+    assert!(matches!(
+        err,
+        RuntimeMapAdapterError::InvalidLayerDataLen {
+            expected: 4,
+            actual: 3,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn infinite_map_chunks_are_decoded() {
+    let dir = temp_dir("encoding_chunks");
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(&gid_bytes(&[1, 0, 0, 1]))
+        .expect("compress chunk");
+    let chunk = STANDARD.encode(encoder.finish().expect("compress chunk"));
+
+    let map_path = dir.join("infinite.json");
+    let json = format!(
+        r#"{{
+  "width": 2,
+  "height": 2,
+  "tilewidth": 32,
+  "tileheight": 32,
+  "infinite": true,
+  "layers": [
+    {{
+      "type": "tilelayer",
+      "name": "Wall_Layer",
+      "encoding": "base64",
+      "compression": "zlib",
+      "chunks": [{{ "x": -2, "y": 0, "width": 2, "height": 2, "data": "{chunk}" }}]
+    }}
+  ]
+}}"#
+    );
+    fs::write(&map_path, json).expect("failed to write map fixture");
+
+    let map = RuntimeMapAdapter::from_tiled_json_wall_layer(&map_path)
+        .expect("compressed chunks should load");
+    assert_eq!(map.origin, (-2, 0));
+    assert_eq!(map.solid, vec![true, false, false, true]);
+}
@@ -0,0 +1,178 @@
+use std::path::PathBuf;
+
+use enemy_pathfinder::map::{HexLayout, RuntimeMapAdapter, StaggerAxis, StaggerIndex};
+use enemy_pathfinder::pathfinding::{
+    DiagonalMovement, PathFailure, PathOptions, astar_to_any_with_map, astar_to_matching_with_map,
+    astar_with_map_options, find_path_with_map, path_cost_with_map,
+};
+use enemy_pathfinder::visibility::los_grid_with_map;
+
+mod common;
+
+use common::grid_from_rows;
+
+fn assets_map_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("assets")
+        .join("map.json")
+}
+
+fn load_runtime_map() -> RuntimeMapAdapter {
+    RuntimeMapAdapter::from_tiled_json_wall_layer(assets_map_path())
+        .expect("assets/map.json Wall_Layer should parse")
+}
+
+/// Cheapest single-goal cost over `goals`, by brute force.
+fn cheapest_single_goal(
+    map: &RuntimeMapAdapter,
+    start: (usize, usize),
+    goals: &[(usize, usize)],
+    options: &PathOptions,
+) -> Option<i32> {
+    goals
+        .iter()
+        .map(|&goal| astar_with_map_options(map, start, goal, options))
+        .filter(|result| result.is_found())
+        .map(|result| result.cost)
+        .min()
+}
+
+#[test]
+fn nearest_goal_wins() {
+    let map = load_runtime_map();
+    let goals = [(26, 10), (12, 3), (5, 1), (2, 18)];
+
+    for options in [
+        PathOptions::four_way(),
+        PathOptions::eight_way(DiagonalMovement::OnlyWhenNoObstacles),
+    ] {
+        for start in [(1, 3), (20, 12), (2, 17)] {
+            let result = astar_to_any_with_map(&map, start, &goals, &options);
+            assert!(result.is_found());
+            assert!(goals.contains(result.cells.last().unwrap()));
+            assert_eq!(result.cost, path_cost_with_map(&map, &result.cells));
+            assert_eq!(
+                Some(result.cost),
+                cheapest_single_goal(&map, start, &goals, &options),
+                "{start:?}"
+            );
+        }
+    }
+}
+
+/// Manhattan distance overestimates hex steps, so it would steer the
+/// search to the straight-line goals over the nearer slanted ones.
+#[test]
+fn nearest_goal_wins_on_hex_maps() {
+    let goals = [(3, 6), (6, 3), (8, 0), (0, 8)];
+    let options = PathOptions::four_way();
+
+    for stagger_axis in [StaggerAxis::X, StaggerAxis::Y] {
+        for stagger_index in [StaggerIndex::Odd, StaggerIndex::Even] {
+            let map = grid_from_rows(&[".........."; 10]).with_hex_layout(HexLayout {
+                tile_width: 28.0,
+                tile_height: 32.0,
+                side_length: 16.0,
+                stagger_axis,
+                stagger_index,
+            });
+            for start in [(0, 0), (1, 0), (0, 1)] {
+                let result = astar_to_any_with_map(&map, start, &goals, &options);
+                let cheapest = goals
+                    .iter()
+                    .map(|&goal| find_path_with_map(&map, start, goal, &options))
+                    .filter(|single| single.is_found())
+                    .map(|single| single.cost)
+                    .min();
+                assert!(result.is_found());
+                assert!(goals.contains(result.cells.last().unwrap()));
+                assert_eq!(
+                    Some(result.cost),
+                    cheapest,
+                    "{stagger_axis:?} {stagger_index:?} {start:?}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn walled_and_missing_goals_are_reported() {
+    let map = grid_from_rows(&["....#", "....#", "#####"]);
+    let options = PathOptions::four_way();
+
+    let walled = astar_to_any_with_map(&map, (0, 0), &[(4, 0), (4, 1)], &options);
+    assert_eq!(walled.failure, Some(PathFailure::GoalInWall));
+
+    let approach = astar_to_any_with_map(
+        &map,
+        (0, 0),
+        &[(4, 0), (4, 1)],
+        &options.with_closest_fallback(true),
+    );
+    assert_eq!(approach.failure, Some(PathFailure::GoalInWall));
+    assert!(matches!(approach.cells.last(), Some(&(3, 0) | &(3, 1))));
+
+    let none = astar_to_any_with_map(&map, (0, 0), &[], &options);
+    assert_eq!(none.failure, Some(PathFailure::Unreachable));
+
+    let already_there = astar_to_any_with_map(&map, (1, 1), &[(4, 1), (1, 1)], &options);
+    assert_eq!(already_there.cells, vec![(1, 1)]);
+}
+
+#[test]
+fn area_goals_match_between_cell_sets_and_predicates() {
+    let map = load_runtime_map();
+    let options = PathOptions::eight_way(DiagonalMovement::OnlyWhenNoObstacles);
+    let (x, y) = map.grid_to_world(20, 8);
+    let area = (x - 40.0, y - 40.0, 100.0, 80.0);
+
+    let cells = map.cells_in_world_rect(area.0, area.1, area.2, area.3);
+    assert!(cells.contains(&(20, 8)));
+    assert!(!cells.is_empty());
+
+    let by_set = astar_to_any_with_map(&map, (2, 18), &cells, &options);
+    let by_predicate = astar_to_matching_with_map(
+        &map,
+        (2, 18),
+        |cx, cy| {
+            let (wx, wy) = map.grid_to_world(cx, cy);
+            wx >= area.0 && wx < area.0 + area.2 && wy >= area.1 && wy < area.1 + area.3
+        },
+        &options,
+    );
+
+    assert!(by_set.is_found());
+    assert_eq!(by_set.cost, by_predicate.cost);
+    assert!(by_set.expanded <= by_predicate.expanded);
+}
+
+#[test]
+fn line_of_sight_predicate_stops_at_the_first_cell_that_sees_the_player() {
+    let map = grid_from_rows(&[
+        "#########",
+        "#.......#",
+        "#.#####.#",
+        "#.#...#.#",
+        "#.#.#.#.#",
+        "#...#...#",
+        "#########",
+    ]);
+    let options = PathOptions::four_way();
+    let player = (3, 3);
+    let sees_player =
+        |x: usize, y: usize| !map.is_wall(x, y) && los_grid_with_map(&map, (x, y), player);
+
+    let result = astar_to_matching_with_map(&map, (7, 5), sees_player, &options);
+    let end = *result.cells.last().unwrap();
+    assert!(sees_player(end.0, end.1));
+
+    let candidates: Vec<(usize, usize)> = (0..map.height)
+        .flat_map(|y| (0..map.width).map(move |x| (x, y)))
+        .filter(|&(x, y)| sees_player(x, y))
+        .collect();
+    assert_eq!(
+        Some(result.cost),
+        cheapest_single_goal(&map, (7, 5), &candidates, &options)
+    );
+}
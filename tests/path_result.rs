@@ -0,0 +1,110 @@
+use std::path::PathBuf;
+
+use enemy_pathfinder::map::RuntimeMapAdapter;
+use enemy_pathfinder::pathfinding::{
+    DiagonalMovement, PathFailure, PathOptions, SearchAlgorithm, astar_with_map,
+    astar_with_map_options, find_path_with_map, path_cost_with_map,
+};
+
+mod common;
+
+use common::grid_from_rows;
+
+fn assets_map_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("assets")
+        .join("map.json")
+}
+
+/// The right room (x >= 5) is sealed off from the left one.
+fn sealed_rooms() -> RuntimeMapAdapter {
+    grid_from_rows(&[
+        "#########",
+        "#...#...#",
+        "#...#.#.#",
+        "#...#...#",
+        "#########",
+    ])
+}
+
+#[test]
+fn found_paths_report_cost_and_expansions() {
+    let map = RuntimeMapAdapter::from_tiled_json_wall_layer(assets_map_path())
+        .expect("assets/map.json Wall_Layer should parse");
+
+    let result = astar_with_map(&map, (1, 3), (12, 3));
+    assert!(result.is_found());
+    assert_eq!(result.failure, None);
+    assert_eq!(result.cost, path_cost_with_map(&map, &result.cells));
+    assert!(result.expanded >= result.cells.len());
+}
+
+#[test]
+fn failures_carry_a_reason() {
+    let map = sealed_rooms();
+
+    let empty = RuntimeMapAdapter::from_solid_cells(32.0, 0, 0, vec![]);
+    assert_eq!(
+        astar_with_map(&empty, (0, 0), (0, 0)).failure,
+        Some(PathFailure::EmptyMap)
+    );
+    assert_eq!(
+        astar_with_map(&map, (0, 0), (1, 1)).failure,
+        Some(PathFailure::StartInWall)
+    );
+    assert_eq!(
+        astar_with_map(&map, (1, 1), (4, 1)).failure,
+        Some(PathFailure::GoalInWall)
+    );
+
+    let unreachable = astar_with_map(&map, (1, 1), (7, 3));
+    assert_eq!(unreachable.failure, Some(PathFailure::Unreachable));
+    assert!(unreachable.cells.is_empty());
+    assert_eq!(unreachable.expanded, 9);
+}
+
+#[test]
+fn closest_fallback_walks_up_to_the_barrier() {
+    let map = sealed_rooms();
+    let options = PathOptions::four_way().with_closest_fallback(true);
+
+    let result = astar_with_map_options(&map, (1, 1), (7, 2), &options);
+    assert_eq!(result.failure, Some(PathFailure::Unreachable));
+    assert_eq!(result.cells.first(), Some(&(1, 1)));
+    assert_eq!(result.cells.last(), Some(&(3, 2)));
+    assert_eq!(result.cost, path_cost_with_map(&map, &result.cells));
+
+    let walled_goal = astar_with_map_options(&map, (5, 1), (6, 2), &options);
+    assert_eq!(walled_goal.failure, Some(PathFailure::GoalInWall));
+    assert!(matches!(walled_goal.cells.last(), Some(&(5, 2) | &(6, 1))));
+}
+
+#[test]
+fn every_algorithm_honors_the_fallback() {
+    let map = sealed_rooms();
+    let options =
+        PathOptions::eight_way(DiagonalMovement::OnlyWhenNoObstacles).with_closest_fallback(true);
+
+    for algorithm in [
+        SearchAlgorithm::AStar,
+        SearchAlgorithm::JumpPoint,
+        SearchAlgorithm::ThetaStar,
+    ] {
+        let result = find_path_with_map(&map, (1, 1), (7, 2), &options.with_algorithm(algorithm));
+        assert_eq!(
+            result.failure,
+            Some(PathFailure::Unreachable),
+            "{algorithm:?}"
+        );
+        assert_eq!(result.cells.last(), Some(&(3, 2)), "{algorithm:?}");
+
+        let strict = options
+            .with_algorithm(algorithm)
+            .with_closest_fallback(false);
+        assert!(
+            find_path_with_map(&map, (1, 1), (7, 2), &strict)
+                .cells
+                .is_empty()
+        );
+    }
+}
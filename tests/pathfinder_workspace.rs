@@ -0,0 +1,90 @@
+use std::path::PathBuf;
+
+use enemy_pathfinder::map::RuntimeMapAdapter;
+use enemy_pathfinder::pathfinding::{
+    DiagonalMovement, PathOptions, PathfinderWorkspace, SearchAlgorithm, find_path_with_map,
+};
+
+mod common;
+
+use common::grid_from_rows;
+
+fn assets_map_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("assets")
+        .join("map.json")
+}
+
+fn load_runtime_map() -> RuntimeMapAdapter {
+    RuntimeMapAdapter::from_tiled_json_wall_layer(assets_map_path())
+        .expect("assets/map.json Wall_Layer should parse")
+}
+
+#[test]
+fn reused_workspace_matches_fresh_searches() {
+    let map = load_runtime_map();
+    let queries = [
+        ((2, 18), (26, 10)),
+        ((1, 3), (12, 3)),
+        ((1, 1), (5, 1)),
+        ((26, 10), (2, 18)),
+        ((0, 0), (1, 1)),
+    ];
+    let mut workspace = PathfinderWorkspace::new();
+
+    for algorithm in [
+        SearchAlgorithm::AStar,
+        SearchAlgorithm::JumpPoint,
+        SearchAlgorithm::ThetaStar,
+    ] {
+        for diagonal in [
+            DiagonalMovement::Never,
+            DiagonalMovement::OnlyWhenNoObstacles,
+        ] {
+            let options = PathOptions::eight_way(diagonal).with_algorithm(algorithm);
+            for &(start, goal) in &queries {
+                assert_eq!(
+                    workspace.find_path_with_map(&map, start, goal, &options),
+                    find_path_with_map(&map, start, goal, &options),
+                    "{algorithm:?} {diagonal:?} {start:?} -> {goal:?}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn workspace_serves_maps_of_different_sizes() {
+    let small = grid_from_rows(&["....", ".##.", "...."]);
+    let large = load_runtime_map();
+    let options = PathOptions::four_way();
+    let mut workspace = PathfinderWorkspace::new();
+
+    let first = workspace.find_path_with_map(&small, (0, 1), (3, 1), &options);
+    assert_eq!(workspace.capacity(), 12);
+
+    let big = workspace.find_path_with_map(&large, (2, 18), (26, 10), &options);
+    assert!(big.is_found());
+    assert_eq!(workspace.capacity(), large.width * large.height);
+
+    // Stale state from the large map must not leak into the small one.
+    let again = workspace.find_path_with_map(&small, (0, 1), (3, 1), &options);
+    assert_eq!(again, first);
+    assert_eq!(workspace.capacity(), large.width * large.height);
+}
+
+#[test]
+fn failed_searches_do_not_poison_later_ones() {
+    let map = grid_from_rows(&["..#..", "..#..", "..#.."]);
+    let options = PathOptions::four_way();
+    let mut workspace = PathfinderWorkspace::new();
+
+    assert!(
+        !workspace
+            .find_path_with_map(&map, (0, 0), (4, 0), &options)
+            .is_found()
+    );
+    let result = workspace.find_path_with_map(&map, (0, 0), (1, 2), &options);
+    assert_eq!(result, find_path_with_map(&map, (0, 0), (1, 2), &options));
+    assert_eq!(result.cells.len(), 4);
+}
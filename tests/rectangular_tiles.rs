@@ -0,0 +1,110 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use enemy_pathfinder::actor::Actor;
+use enemy_pathfinder::map::RuntimeMapAdapter;
+use enemy_pathfinder::physics::resolve_circle_map_with_map;
+use enemy_pathfinder::visibility::{has_line_of_sight_with_map, los_grid_with_map};
+
+mod common;
+
+use common::temp_dir;
+
+/// 5x4 map of 32x16 tiles with a single wall at (2, 1).
+fn write_rect_map(dir: &Path) -> PathBuf {
+    let map_path = dir.join("map.json");
+    let json = r#"{
+  "width": 5,
+  "height": 4,
+  "tilewidth": 32,
+  "tileheight": 16,
+  "layers": [
+    {
+      "type": "tilelayer",
+      "name": "Wall_Layer",
+      "width": 5,
+      "height": 4,
+      "data": [0, 0, 0, 0, 0,
+               0, 0, 1, 0, 0,
+               0, 0, 0, 0, 0,
+               0, 0, 0, 0, 0]
+    }
+  ]
+}"#;
+
+    fs::write(&map_path, json).expect("failed to write map fixture");
+    map_path
+}
+
+fn load_rect_map(tag: &str) -> RuntimeMapAdapter {
+    let dir = temp_dir(tag);
+    RuntimeMapAdapter::from_tiled_json_wall_layer(write_rect_map(&dir))
+        .expect("32x16 tiles should load")
+}
+
+#[test]
+fn non_square_tiles_load_with_both_dimensions() {
+    let map = load_rect_map("load");
+    assert_eq!((map.tile_width, map.tile_height), (32.0, 16.0));
+
+    let summary = map.summary();
+    assert_eq!((summary.tile_width, summary.tile_height), (32.0, 16.0));
+    assert_eq!(summary.solid_cells, 1);
+}
+
+#[test]
+fn world_conversion_scales_each_axis() {
+    let map = load_rect_map("convert");
+    assert_eq!(map.grid_to_world(2, 3), (80.0, 56.0));
+    assert_eq!(map.world_to_grid(80.0, 56.0), (2, 3));
+    assert_eq!(map.world_to_grid(63.9, 16.1), (1, 1));
+
+    for y in 0..map.height {
+        for x in 0..map.width {
+            let (wx, wy) = map.grid_to_world(x, y);
+            assert_eq!(map.world_to_grid(wx, wy), (x, y));
+        }
+    }
+}
+
+#[test]
+fn clearance_is_measured_in_world_units() {
+    let map = load_rect_map("clearance");
+    // Beside the wall the gap is half a tile wide; above it, half a tile
+    // high.
+    assert_eq!(map.clearance(1, 1), 16.0);
+    assert_eq!(map.clearance(2, 2), 8.0);
+}
+
+#[test]
+fn circles_are_pushed_out_of_rectangular_walls() {
+    let map = load_rect_map("physics");
+
+    // Wall (2, 1) spans x 64..96 and y 16..32.
+    let mut below = Actor::new_world((80.0, 36.0), 0.0);
+    below.radius = 6.0;
+    resolve_circle_map_with_map(&map, &mut below);
+    assert!((below.pos.1 - 38.0).abs() < 1e-4, "{:?}", below.pos);
+    assert_eq!(below.pos.0, 80.0);
+
+    let mut left = Actor::new_world((60.0, 24.0), 0.0);
+    left.radius = 6.0;
+    resolve_circle_map_with_map(&map, &mut left);
+    assert!((left.pos.0 - 58.0).abs() < 1e-4, "{:?}", left.pos);
+}
+
+#[test]
+fn line_of_sight_walks_rectangular_cells() {
+    let map = load_rect_map("los");
+    assert!(!los_grid_with_map(&map, (0, 1), (4, 1)));
+    assert!(los_grid_with_map(&map, (0, 0), (4, 0)));
+    assert!(los_grid_with_map(&map, (0, 2), (4, 3)));
+
+    // Enters the wall spanning x 64..96, y 16..32 at (64, 24).
+    assert!(!has_line_of_sight_with_map(&map, (40.0, 8.0), (88.0, 40.0)));
+    assert!(has_line_of_sight_with_map(
+        &map,
+        (16.0, 40.0),
+        (150.0, 60.0)
+    ));
+}
@@ -0,0 +1,37 @@
+use enemy_pathfinder::map::{
+    RuntimeMapAdapter, clear_runtime_map, install_runtime_map, install_runtime_map_compatible,
+    is_wall, replace_runtime_map, runtime_map, try_is_wall,
+};
+use enemy_pathfinder::pathfinding::astar;
+
+fn corridor(solid: Vec<bool>) -> RuntimeMapAdapter {
+    RuntimeMapAdapter::from_solid_cells(32.0, solid.len(), 1, solid)
+}
+
+// The registry is process-wide, so the whole lifecycle runs in one test.
+#[test]
+fn runtime_map_can_be_replaced_and_cleared() {
+    let open = corridor(vec![false, false, false]);
+    let blocked = corridor(vec![false, true, false]);
+
+    assert!(replace_runtime_map(open.clone()).is_none());
+    assert!(!is_wall(1, 0));
+    assert!(astar((0, 0), (2, 0)).is_found());
+    let snapshot = runtime_map().expect("map was just installed");
+
+    let previous = replace_runtime_map(blocked.clone()).expect("a map was installed");
+    assert!(previous.is_compatible_with(&open));
+    assert!(is_wall(1, 0));
+    assert!(!astar((0, 0), (2, 0)).is_found());
+    assert!(!snapshot.is_wall(1, 0), "earlier handles keep their map");
+
+    let err = install_runtime_map(open.clone()).expect_err("a map is installed");
+    assert!(err.is_compatible_with(&open));
+    install_runtime_map_compatible(blocked).expect("same map is compatible");
+    install_runtime_map_compatible(open.clone()).expect_err("different map is not");
+
+    assert!(clear_runtime_map().is_some());
+    assert_eq!(try_is_wall(1, 0), None);
+    install_runtime_map(open).expect("nothing is installed after clearing");
+    assert_eq!(try_is_wall(1, 0), Some(false));
+}
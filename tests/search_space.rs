@@ -0,0 +1,214 @@
+use std::path::PathBuf;
+
+use enemy_pathfinder::map::RuntimeMapAdapter;
+use enemy_pathfinder::pathfinding::{
+    DiagonalMovement, GridSpace, PathFailure, PathOptions, SearchSpace, astar_over_space,
+    astar_with_map_options,
+};
+
+mod common;
+
+use common::grid_from_rows;
+
+fn assets_map_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("assets")
+        .join("map.json")
+}
+
+fn load_runtime_map() -> RuntimeMapAdapter {
+    RuntimeMapAdapter::from_tiled_json_wall_layer(assets_map_path())
+        .expect("assets/map.json Wall_Layer should parse")
+}
+
+/// Weighted directed graph given as an edge list, searched without a
+/// heuristic.
+struct EdgeGraph {
+    node_count: usize,
+    closed: Vec<usize>,
+    edges: Vec<(usize, usize, i32)>,
+}
+
+impl SearchSpace for EdgeGraph {
+    type Node = usize;
+
+    fn node_count(&self) -> usize {
+        self.node_count
+    }
+
+    fn index(&self, node: usize) -> usize {
+        node
+    }
+
+    fn node(&self, index: usize) -> usize {
+        index
+    }
+
+    fn passable(&self, node: usize) -> bool {
+        !self.closed.contains(&node)
+    }
+
+    fn neighbors(&self, node: usize, out: &mut Vec<usize>) {
+        out.extend(
+            self.edges
+                .iter()
+                .filter(|&&(from, to, _)| from == node && self.passable(to))
+                .map(|&(_, to, _)| to),
+        );
+    }
+
+    fn step_cost(&self, from: usize, to: usize) -> i32 {
+        self.edges
+            .iter()
+            .find(|&&(a, b, _)| a == from && b == to)
+            .map(|&(_, _, cost)| cost)
+            .expect("step along a known edge")
+    }
+
+    fn heuristic(&self, _from: usize, _to: usize) -> i32 {
+        0
+    }
+}
+
+/// A grid with a one-way portal from `entry` to `exit`, layered on top of
+/// the stock grid space.
+struct PortalGrid<'a> {
+    grid: GridSpace<'a>,
+    entry: (usize, usize),
+    exit: (usize, usize),
+}
+
+impl SearchSpace for PortalGrid<'_> {
+    type Node = (usize, usize);
+
+    fn node_count(&self) -> usize {
+        self.grid.node_count()
+    }
+
+    fn index(&self, node: (usize, usize)) -> usize {
+        self.grid.index(node)
+    }
+
+    fn node(&self, index: usize) -> (usize, usize) {
+        self.grid.node(index)
+    }
+
+    fn passable(&self, node: (usize, usize)) -> bool {
+        self.grid.passable(node)
+    }
+
+    fn neighbors(&self, node: (usize, usize), out: &mut Vec<(usize, usize)>) {
+        self.grid.neighbors(node, out);
+        if node == self.entry {
+            out.push(self.exit);
+        }
+    }
+
+    fn step_cost(&self, from: (usize, usize), to: (usize, usize)) -> i32 {
+        if (from, to) == (self.entry, self.exit) {
+            1
+        } else {
+            self.grid.step_cost(from, to)
+        }
+    }
+
+    // The portal makes any distance estimate unsafe.
+    fn heuristic(&self, _from: (usize, usize), _to: (usize, usize)) -> i32 {
+        0
+    }
+}
+
+#[test]
+fn grid_space_matches_grid_astar() {
+    let map = load_runtime_map();
+    let queries = [
+        ((2, 18), (26, 10)),
+        ((1, 3), (12, 3)),
+        ((26, 10), (2, 18)),
+        ((1, 1), (5, 1)),
+    ];
+
+    for diagonal in [
+        DiagonalMovement::Never,
+        DiagonalMovement::OnlyWhenNoObstacles,
+    ] {
+        let options = PathOptions::eight_way(diagonal).with_agent_radius(10.0);
+        let space = GridSpace::new(&map, &options);
+        for &(start, goal) in &queries {
+            let expected = astar_with_map_options(&map, start, goal, &options);
+            let result = astar_over_space(&space, start, goal, false);
+            assert_eq!(
+                result.cost, expected.cost,
+                "{diagonal:?} {start:?} -> {goal:?}"
+            );
+            assert_eq!(result.failure, expected.failure);
+            assert_eq!(result.cells.first(), expected.cells.first());
+            assert_eq!(result.cells.last(), expected.cells.last());
+        }
+    }
+}
+
+#[test]
+fn custom_graphs_take_the_cheapest_route() {
+    // 0 -> 3 directly is one hop but costs more than going around.
+    let graph = EdgeGraph {
+        node_count: 5,
+        closed: vec![],
+        edges: vec![(0, 3, 50), (0, 1, 10), (1, 2, 10), (2, 3, 10), (3, 4, 5)],
+    };
+    let result = astar_over_space(&graph, 0, 4, false);
+    assert!(result.is_found());
+    assert_eq!(result.cells, vec![0, 1, 2, 3, 4]);
+    assert_eq!(result.cost, 35);
+
+    // Nothing leads back to 0.
+    let back = astar_over_space(&graph, 4, 0, false);
+    assert_eq!(back.failure, Some(PathFailure::Unreachable));
+    assert!(back.cells.is_empty());
+}
+
+#[test]
+fn endpoint_checks_follow_passable() {
+    let graph = EdgeGraph {
+        node_count: 3,
+        closed: vec![2],
+        edges: vec![(0, 1, 1), (1, 2, 1)],
+    };
+    assert_eq!(
+        astar_over_space(&graph, 2, 0, false).failure,
+        Some(PathFailure::StartInWall)
+    );
+    assert_eq!(
+        astar_over_space(&graph, 0, 2, false).failure,
+        Some(PathFailure::GoalInWall)
+    );
+
+    let empty = EdgeGraph {
+        node_count: 0,
+        closed: vec![],
+        edges: vec![],
+    };
+    assert_eq!(
+        astar_over_space(&empty, 0, 0, false).failure,
+        Some(PathFailure::EmptyMap)
+    );
+    assert_eq!(astar_over_space(&graph, 1, 1, false).cells, vec![1]);
+}
+
+#[test]
+fn decorated_grid_spaces_can_add_edges() {
+    let map = grid_from_rows(&["........", "#######.", "........"]);
+    let options = PathOptions::four_way();
+    let plain = GridSpace::new(&map, &options);
+    let walked = astar_over_space(&plain, (0, 0), (0, 2), false);
+    assert_eq!(walked.cost, 16 * 100);
+
+    let portals = PortalGrid {
+        grid: plain,
+        entry: (1, 0),
+        exit: (1, 2),
+    };
+    let jumped = astar_over_space(&portals, (0, 0), (0, 2), false);
+    assert_eq!(jumped.cells, vec![(0, 0), (1, 0), (1, 2), (0, 2)]);
+    assert_eq!(jumped.cost, 100 + 1 + 100);
+}
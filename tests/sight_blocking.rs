@@ -0,0 +1,149 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use enemy_pathfinder::map::{CellRect, RuntimeMapAdapter};
+use enemy_pathfinder::pathfinding::astar_with_map;
+use enemy_pathfinder::visibility::{
+    furthest_visible_waypoint_with_map, has_line_of_sight_with_map, has_walkable_line_with_map,
+    los_grid_with_map,
+};
+
+mod common;
+
+use common::temp_dir;
+
+/// 5x3 map with a window at (1, 1) and smoke at (3, 1). The sight layer
+/// darkens the open cell (2, 2) and clears the wall at (4, 2), a chasm.
+fn write_sight_map(dir: &Path) -> PathBuf {
+    let map_path = dir.join("map.json");
+    let json = r#"{
+  "width": 5,
+  "height": 3,
+  "tilewidth": 32,
+  "tileheight": 32,
+  "tilesets": [
+    {
+      "firstgid": 1,
+      "tiles": [
+        { "id": 0, "properties": [{ "name": "solid", "type": "bool", "value": true }] },
+        { "id": 1, "properties": [
+          { "name": "solid", "type": "bool", "value": true },
+          { "name": "blocks_sight", "type": "bool", "value": false }
+        ] },
+        { "id": 2, "properties": [
+          { "name": "solid", "type": "bool", "value": false },
+          { "name": "blocks_sight", "type": "bool", "value": true }
+        ] }
+      ]
+    }
+  ],
+  "layers": [
+    {
+      "type": "tilelayer",
+      "name": "Wall_Layer",
+      "width": 5,
+      "height": 3,
+      "data": [0, 0, 0, 0, 0,
+               0, 2, 0, 3, 0,
+               0, 0, 0, 0, 1]
+    },
+    {
+      "type": "tilelayer",
+      "name": "Sight_Layer",
+      "width": 5,
+      "height": 3,
+      "data": [0, 0, 0, 0, 0,
+               0, 0, 0, 0, 0,
+               0, 0, 1, 0, 2]
+    }
+  ]
+}"#;
+
+    fs::write(&map_path, json).expect("failed to write map fixture");
+    map_path
+}
+
+fn load_sight_map(tag: &str) -> RuntimeMapAdapter {
+    let dir = temp_dir(tag);
+    RuntimeMapAdapter::from_tiled_json_wall_layer(write_sight_map(&dir))
+        .expect("sight map should load")
+}
+
+#[test]
+fn movement_and_sight_are_loaded_separately() {
+    let map = load_sight_map("load");
+
+    assert!(map.is_wall(1, 1) && !map.blocks_sight(1, 1), "window");
+    assert!(!map.is_wall(3, 1) && map.blocks_sight(3, 1), "smoke");
+    assert!(!map.is_wall(2, 2) && map.blocks_sight(2, 2), "sight layer");
+    assert!(map.is_wall(4, 2) && !map.blocks_sight(4, 2), "chasm");
+    assert!(!map.is_wall(0, 0) && !map.blocks_sight(0, 0));
+    assert!(map.blocks_sight(5, 0), "out of bounds blocks sight");
+}
+
+#[test]
+fn sight_uses_opacity_and_movement_uses_walls() {
+    let map = load_sight_map("los");
+
+    assert!(
+        los_grid_with_map(&map, (0, 1), (2, 1)),
+        "through the window"
+    );
+    assert!(!los_grid_with_map(&map, (2, 1), (4, 1)), "into the smoke");
+
+    let (a, b) = (map.grid_to_world(0, 1), map.grid_to_world(2, 1));
+    assert!(has_line_of_sight_with_map(&map, a, b));
+    assert!(!has_walkable_line_with_map(&map, a, b));
+
+    let around_window = astar_with_map(&map, (0, 1), (2, 1));
+    assert!(around_window.is_found());
+    assert!(!around_window.cells.contains(&(1, 1)));
+    assert!(around_window.cost > 200);
+
+    let through_smoke = astar_with_map(&map, (2, 1), (4, 1));
+    assert_eq!(through_smoke.cells, vec![(2, 1), (3, 1), (4, 1)]);
+}
+
+#[test]
+fn waypoints_are_picked_by_walkability() {
+    let map = load_sight_map("waypoints");
+
+    // The window is visible from (0, 1) but cannot be walked through.
+    let path = [(0, 1), (0, 0), (1, 0), (2, 0), (2, 1)];
+    let enemy = map.grid_to_world(0, 1);
+    let best = furthest_visible_waypoint_with_map(&map, enemy, &path, 0);
+    assert_ne!(path[best], (2, 1));
+}
+
+#[test]
+fn opacity_can_change_at_runtime() {
+    let mut map = RuntimeMapAdapter::from_solid_cells(32.0, 3, 1, vec![false, true, false]);
+    assert!(map.blocks_sight(1, 0), "opacity follows solid by default");
+
+    map.set_opaque(1, 0, false);
+    assert!(map.is_wall(1, 0));
+    assert!(los_grid_with_map(&map, (0, 0), (2, 0)));
+
+    map.set_solid(1, 0, false);
+    map.set_solid(1, 0, true);
+    assert!(!map.blocks_sight(1, 0), "set_solid leaves opacity alone");
+    map.set_cell_shapes(
+        1,
+        0,
+        vec![CellRect {
+            x: 0.0,
+            y: 0.0,
+            width: 32.0,
+            height: 32.0,
+        }],
+    );
+    assert!(
+        !map.blocks_sight(1, 0),
+        "set_cell_shapes leaves opacity alone"
+    );
+
+    let smoky = RuntimeMapAdapter::from_solid_cells(32.0, 3, 1, vec![false; 3])
+        .with_opacity(vec![false, true, false]);
+    assert!(!los_grid_with_map(&smoky, (0, 0), (2, 0)));
+    assert!(astar_with_map(&smoky, (0, 0), (2, 0)).is_found());
+}
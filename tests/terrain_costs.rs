@@ -0,0 +1,154 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use enemy_pathfinder::actor::Actor;
+use enemy_pathfinder::map::{RuntimeMapAdapter, RuntimeMapAdapterError};
+use enemy_pathfinder::pathfinding::{
+    DiagonalMovement, FlowField, PathOptions, astar_with_map, astar_with_map_options,
+    path_cost_with_map,
+};
+use enemy_pathfinder::physics::terrain_speed_with_map;
+
+mod common;
+
+use common::temp_dir;
+
+fn assets_map_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("assets")
+        .join("map.json")
+}
+
+/// 5x3 map, no walls. The middle row of the cost layer is painted with
+/// gid 2 (mud, cost 4.0) except at the ends; gid 3 has no tile property and
+/// falls back to the layer property.
+fn write_cost_map(dir: &Path, mud_cost: &str) -> PathBuf {
+    let map_path = dir.join("map.json");
+    let json = format!(
+        r#"{{
+  "width": 5,
+  "height": 3,
+  "tilewidth": 32,
+  "tileheight": 32,
+  "tilesets": [
+    {{
+      "firstgid": 1,
+      "tiles": [
+        {{ "id": 1, "properties": [{{ "name": "cost", "type": "float", "value": {mud_cost} }}] }}
+      ]
+    }}
+  ],
+  "layers": [
+    {{
+      "type": "tilelayer",
+      "name": "Wall_Layer",
+      "width": 5,
+      "height": 3,
+      "data": [0, 0, 0, 0, 0,
+               0, 0, 0, 0, 0,
+               0, 0, 0, 0, 0]
+    }},
+    {{
+      "type": "tilelayer",
+      "name": "Cost_Layer",
+      "width": 5,
+      "height": 3,
+      "properties": [{{ "name": "cost", "type": "float", "value": 2.5 }}],
+      "data": [0, 0, 0, 0, 0,
+               0, 2, 2, 2, 0,
+               0, 0, 0, 0, 3]
+    }}
+  ]
+}}"#
+    );
+
+    fs::write(&map_path, json).expect("failed to write map fixture");
+    map_path
+}
+
+#[test]
+fn cost_layer_reads_tile_properties_with_layer_fallback() {
+    let dir = temp_dir("terrain_props");
+    let map = RuntimeMapAdapter::from_tiled_json_wall_layer(write_cost_map(&dir, "4.0"))
+        .expect("cost fixture should parse");
+
+    assert_eq!(map.traversal_cost(0, 0), 1.0);
+    assert_eq!(map.traversal_cost(2, 1), 4.0);
+    assert_eq!(map.traversal_cost(4, 2), 2.5);
+    assert_eq!(map.min_traversal_cost(), 1.0);
+    assert!(!map.has_uniform_cost());
+}
+
+#[test]
+fn bundled_map_without_cost_layer_is_uniform() {
+    let map = RuntimeMapAdapter::from_tiled_json_wall_layer(assets_map_path())
+        .expect("assets/map.json Wall_Layer should parse");
+
+    assert!(map.has_uniform_cost());
+}
+
+#[test]
+fn astar_detours_around_expensive_cells() {
+    let dir = temp_dir("terrain_detour");
+    let map = RuntimeMapAdapter::from_tiled_json_wall_layer(write_cost_map(&dir, "4.0"))
+        .expect("cost fixture should parse");
+
+    let path = astar_with_map(&map, (0, 1), (4, 1)).cells;
+
+    assert_eq!(path.first().copied(), Some((0, 1)));
+    assert_eq!(path.last().copied(), Some((4, 1)));
+    assert!(!path.contains(&(2, 1)));
+}
+
+/// Two lanes: the far one is a hair cheaper per step, which only pays off
+/// over the whole width. A heuristic that overestimates by a fraction of a
+/// unit per step is enough to lose it.
+#[test]
+fn fractional_costs_keep_astar_optimal() {
+    let width = 41;
+    let cost = [vec![0.345; width], vec![0.334; width]].concat();
+    let map = RuntimeMapAdapter::from_solid_cells(32.0, width, 2, vec![false; width * 2])
+        .with_traversal_costs(cost);
+    let goal = (width - 1, 0);
+
+    for options in [
+        PathOptions::four_way(),
+        PathOptions::eight_way(DiagonalMovement::Always),
+    ] {
+        let field = FlowField::from_map(&map, goal, &options);
+        for y in 0..2 {
+            for x in 0..width {
+                let path = astar_with_map_options(&map, (x, y), goal, &options).cells;
+                assert_eq!(
+                    Some(path_cost_with_map(&map, &path)),
+                    field.distance(x, y),
+                    "cell ({x}, {y})"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn non_positive_cost_is_rejected() {
+    let dir = temp_dir("terrain_invalid");
+    let err = RuntimeMapAdapter::from_tiled_json_wall_layer(write_cost_map(&dir, "0.0"))
+        .expect_err("zero cost should be rejected");
+
+    assert!(matches!(
+        err,
+        RuntimeMapAdapterError::InvalidTraversalCost { x: 1, y: 1, .. }
+    ));
+}
+
+#[test]
+fn terrain_slows_actor_speed() {
+    let map = RuntimeMapAdapter::from_solid_cells(32.0, 2, 1, vec![false, false])
+        .with_traversal_costs(vec![1.0, 2.0]);
+
+    let on_floor = Actor::new_world(map.grid_to_world(0, 0), 180.0);
+    let in_mud = Actor::new_world(map.grid_to_world(1, 0), 180.0);
+
+    assert_eq!(terrain_speed_with_map(&map, &on_floor), 180.0);
+    assert_eq!(terrain_speed_with_map(&map, &in_mud), 90.0);
+}
@@ -0,0 +1,228 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use enemy_pathfinder::actor::Actor;
+use enemy_pathfinder::map::{CellRect, RuntimeMapAdapter, RuntimeMapAdapterError};
+use enemy_pathfinder::physics::resolve_circle_map_with_map;
+
+mod common;
+
+use common::temp_dir;
+
+/// External tileset describing collision per tile:
+/// - id 0: decoration, no collision info;
+/// - id 1: `solid: true`;
+/// - id 2: a collision rect over the bottom half of the tile;
+/// - id 3: a collision rect, overridden by `solid: false`;
+/// - id 4: walkable mud with `cost: 3`.
+fn write_json_tileset(dir: &Path) {
+    let json = r#"{
+  "type": "tileset",
+  "name": "props",
+  "tilewidth": 32,
+  "tileheight": 32,
+  "tilecount": 5,
+  "tiles": [
+    { "id": 1, "properties": [{ "name": "solid", "type": "bool", "value": true }] },
+    { "id": 2, "objectgroup": { "type": "objectgroup", "name": "", "objects": [
+        { "id": 1, "x": 0, "y": 16, "width": 32, "height": 16 }
+    ] } },
+    { "id": 3,
+      "properties": [{ "name": "solid", "type": "bool", "value": false }],
+      "objectgroup": { "type": "objectgroup", "name": "", "objects": [
+        { "id": 1, "x": 0, "y": 0, "width": 32, "height": 32 }
+    ] } },
+    { "id": 4, "properties": [{ "name": "cost", "type": "float", "value": 3 }] }
+  ]
+}"#;
+    fs::write(dir.join("props.json"), json).expect("failed to write tileset fixture");
+}
+
+/// The same tileset as TSX, with the half-tile shape as a polygon.
+fn write_tsx_tileset(dir: &Path) {
+    let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" name="props" tilewidth="32" tileheight="32" tilecount="5" columns="5">
+ <tile id="1">
+  <properties>
+   <property name="solid" type="bool" value="true"/>
+  </properties>
+ </tile>
+ <tile id="2">
+  <objectgroup draworder="index">
+   <object id="1" x="0" y="16">
+    <polygon points="0,0 32,0 32,16 0,16"/>
+   </object>
+  </objectgroup>
+ </tile>
+ <tile id="3">
+  <properties>
+   <property name="solid" type="bool" value="false"/>
+  </properties>
+  <objectgroup draworder="index">
+   <object id="1" x="0" y="0" width="32" height="32"/>
+  </objectgroup>
+ </tile>
+ <tile id="4">
+  <properties>
+   <property name="cost" type="float" value="3"/>
+  </properties>
+ </tile>
+</tileset>
+"#;
+    fs::write(dir.join("props.tsx"), xml).expect("failed to write tileset fixture");
+}
+
+/// 5x3 map drawing every tile of `source` plus a tile from an inline
+/// tileset without collision info (gid 10). Cell (4, 1) holds the half-tile
+/// flipped vertically.
+fn write_map(dir: &Path, source: &str) -> PathBuf {
+    let flipped_half = 0x4000_0000u32 | 3;
+    let map_path = dir.join(format!("map_{}.json", source.replace('.', "_")));
+    let json = format!(
+        r#"{{
+  "width": 5,
+  "height": 3,
+  "tilewidth": 32,
+  "tileheight": 32,
+  "tilesets": [
+    {{ "firstgid": 1, "source": "{source}" }},
+    {{ "firstgid": 10, "tiles": [] }}
+  ],
+  "layers": [
+    {{
+      "type": "tilelayer",
+      "name": "Wall_Layer",
+      "width": 5,
+      "height": 3,
+      "data": [1, 2, 0, 10, 5,
+               0, 3, 0, 4, {flipped_half},
+               0, 0, 0, 0, 0]
+    }}
+  ]
+}}"#
+    );
+
+    fs::write(&map_path, json).expect("failed to write map fixture");
+    map_path
+}
+
+fn load_map(tag: &str) -> RuntimeMapAdapter {
+    let dir = temp_dir(tag);
+    write_json_tileset(&dir);
+    RuntimeMapAdapter::from_tiled_json_wall_layer(write_map(&dir, "props.json"))
+        .expect("map with external tileset should load")
+}
+
+#[test]
+fn tile_properties_decide_what_blocks() {
+    let map = load_map("classes");
+
+    assert!(!map.is_wall(0, 0), "decoration tiles stay walkable");
+    assert!(map.is_wall(1, 0), "solid: true");
+    assert!(
+        map.is_wall(3, 0),
+        "tilesets without collision info stay solid"
+    );
+    assert!(!map.is_wall(3, 1), "solid: false overrides shapes");
+    assert!(map.cell_shapes(3, 1).is_empty());
+
+    assert!(!map.is_wall(4, 0));
+    assert_eq!(map.traversal_cost(4, 0), 3.0);
+    assert_eq!(map.summary().solid_cells, 2);
+}
+
+#[test]
+fn collision_shapes_make_half_tiles() {
+    let map = load_map("shapes");
+    let bottom_half = CellRect {
+        x: 0.0,
+        y: 16.0,
+        width: 32.0,
+        height: 16.0,
+    };
+    assert!(!map.is_wall(1, 1), "the half-tile leaves the center open");
+    assert_eq!(map.cell_shapes(1, 1), &[bottom_half]);
+    assert_eq!(
+        map.cell_shapes(4, 1),
+        &[CellRect {
+            y: 0.0,
+            ..bottom_half
+        }],
+        "vertical flips mirror the shape"
+    );
+
+    // Cell (2, 1) sits 16 units right of the half-tile's edge.
+    assert_eq!(map.clearance(2, 1), 16.0);
+    assert_eq!(map.clearance(1, 1), 0.0);
+}
+
+#[test]
+fn physics_collides_with_the_shape_only() {
+    let map = load_map("physics");
+
+    // The half-tile in cell (1, 1) spans y 48..64.
+    let mut clear = Actor::new_world((48.0, 40.0), 0.0);
+    clear.radius = 6.0;
+    resolve_circle_map_with_map(&map, &mut clear);
+    assert_eq!(clear.pos, (48.0, 40.0));
+
+    let mut touching = Actor::new_world((48.0, 44.0), 0.0);
+    touching.radius = 6.0;
+    resolve_circle_map_with_map(&map, &mut touching);
+    assert!((touching.pos.1 - 42.0).abs() < 1e-4, "{:?}", touching.pos);
+
+    // Decoration in cell (0, 0) does not push at all.
+    let mut decorated = Actor::new_world((16.0, 16.0), 0.0);
+    decorated.radius = 6.0;
+    resolve_circle_map_with_map(&map, &mut decorated);
+    assert_eq!(decorated.pos, (16.0, 16.0));
+}
+
+#[test]
+fn tsx_tilesets_match_json_tilesets() {
+    let dir = temp_dir("tiles_tsx");
+    write_json_tileset(&dir);
+    write_tsx_tileset(&dir);
+    let json = RuntimeMapAdapter::from_tiled_json_wall_layer(write_map(&dir, "props.json"))
+        .expect("json tileset should load");
+    let tsx = RuntimeMapAdapter::from_tiled_json_wall_layer(write_map(&dir, "props.tsx"))
+        .expect("tsx tileset should load");
+
+    assert!(tsx.is_compatible_with(&json));
+    for y in 0..json.height {
+        for x in 0..json.width {
+            assert_eq!(tsx.cell_shapes(x, y), json.cell_shapes(x, y), "({x}, {y})");
+            assert_eq!(tsx.clearance(x, y), json.clearance(x, y), "({x}, {y})");
+        }
+    }
+}
+
+#[test]
+fn missing_tileset_files_are_reported() {
+    let dir = temp_dir("tiles_missing");
+    let err = RuntimeMapAdapter::from_tiled_json_wall_layer(write_map(&dir, "props.json"))
+        .expect_err("props.json was never written");
+    assert!(matches!(
+        err,
+        RuntimeMapAdapterError::Io { ref path, .. } if path.ends_with("props.json")
+    ));
+}
+
+#[test]
+fn cell_shapes_can_be_set_at_runtime() {
+    let mut map = RuntimeMapAdapter::from_solid_cells(32.0, 5, 5, vec![false; 25]);
+    let pillar = CellRect {
+        x: 4.0,
+        y: 4.0,
+        width: 24.0,
+        height: 24.0,
+    };
+    map.set_cell_shapes(2, 2, vec![pillar]);
+    assert!(map.is_wall(2, 2), "the pillar covers the center");
+    // 20 units of floor between cell (1, 2)'s center and the pillar.
+    assert_eq!(map.clearance(1, 2), 20.0);
+
+    map.set_solid(2, 2, false);
+    assert!(map.cell_shapes(2, 2).is_empty());
+    assert_eq!(map.clearance(1, 2), 48.0);
+}
@@ -15,7 +15,8 @@ fn wall_layer_builds_runtime_solid_grid_and_preserves_oob_wall_behavior() {
 
     assert_eq!(adapter.width, 30);
     assert_eq!(adapter.height, 20);
-    assert_eq!(adapter.tile_size, 32.0);
+    assert_eq!(adapter.tile_width, 32.0);
+    assert_eq!(adapter.tile_height, 32.0);
 
     assert!(adapter.is_wall(0, 0));
     assert!(adapter.is_wall(29, 19));
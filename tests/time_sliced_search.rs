@@ -0,0 +1,117 @@
+use std::path::PathBuf;
+
+use enemy_pathfinder::map::RuntimeMapAdapter;
+use enemy_pathfinder::pathfinding::{
+    DiagonalMovement, PathFailure, PathOptions, PathSearch, PathfinderWorkspace, SearchAlgorithm,
+    SearchStatus, astar_with_map_options,
+};
+
+mod common;
+
+use common::grid_from_rows;
+
+fn assets_map_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("assets")
+        .join("map.json")
+}
+
+fn load_runtime_map() -> RuntimeMapAdapter {
+    RuntimeMapAdapter::from_tiled_json_wall_layer(assets_map_path())
+        .expect("assets/map.json Wall_Layer should parse")
+}
+
+/// Steps `search` until it finishes, returning the outcome and the number
+/// of steps it took.
+fn run_to_end(
+    search: &mut PathSearch,
+    map: &RuntimeMapAdapter,
+    budget: usize,
+) -> (SearchStatus, usize) {
+    let mut steps = 0;
+    loop {
+        steps += 1;
+        let status = search.step(map, budget);
+        if status != SearchStatus::InProgress {
+            return (status, steps);
+        }
+        assert!(search.expanded() <= steps * budget);
+        assert!(steps <= map.width * map.height);
+    }
+}
+
+#[test]
+fn sliced_search_matches_a_single_shot_search() {
+    let map = load_runtime_map();
+    let options = PathOptions::eight_way(DiagonalMovement::OnlyWhenNoObstacles);
+    let expected = astar_with_map_options(&map, (2, 18), (26, 10), &options);
+
+    for budget in [1, 7, 64, usize::MAX] {
+        let mut search = PathSearch::new(&map, (2, 18), (26, 10), &options);
+        let (status, steps) = run_to_end(&mut search, &map, budget);
+
+        assert_eq!(
+            status,
+            SearchStatus::Found(expected.clone()),
+            "budget {budget}"
+        );
+        if budget < expected.expanded {
+            assert!(steps >= expected.expanded.div_ceil(budget));
+        }
+    }
+}
+
+#[test]
+fn finished_searches_keep_reporting_their_outcome() {
+    let map = grid_from_rows(&["..#..", "..#..", "..#.."]);
+    let options = PathOptions::four_way();
+    let mut search = PathSearch::new(&map, (0, 0), (4, 2), &options);
+
+    let (status, _) = run_to_end(&mut search, &map, 2);
+    let SearchStatus::Failed(result) = status else {
+        panic!("walled-off goal should fail, got {status:?}");
+    };
+    assert_eq!(result.failure, Some(PathFailure::Unreachable));
+    assert_eq!(search.step(&map, 2), SearchStatus::Failed(result));
+}
+
+#[test]
+#[should_panic(expected = "PathSearch only supports A*")]
+fn non_astar_algorithms_are_rejected() {
+    let map = grid_from_rows(&["...", "..."]);
+    let options = PathOptions::four_way().with_algorithm(SearchAlgorithm::JumpPoint);
+    let _ = PathSearch::new(&map, (0, 0), (2, 1), &options);
+}
+
+#[test]
+fn trivial_queries_finish_on_the_first_step() {
+    let map = grid_from_rows(&["#..", "..."]);
+    let options = PathOptions::four_way();
+
+    let mut same_cell = PathSearch::new(&map, (1, 1), (1, 1), &options);
+    assert!(matches!(same_cell.step(&map, 1), SearchStatus::Found(r) if r.cells == [(1, 1)]));
+
+    let mut walled = PathSearch::new(&map, (0, 0), (2, 1), &options);
+    assert!(matches!(
+        walled.step(&map, 1),
+        SearchStatus::Failed(r) if r.failure == Some(PathFailure::StartInWall)
+    ));
+}
+
+#[test]
+fn workspace_round_trips_through_a_search() {
+    let map = load_runtime_map();
+    let options = PathOptions::four_way();
+    let workspace = PathfinderWorkspace::new();
+
+    let mut search = PathSearch::with_workspace(workspace, &map, (1, 3), (12, 3), &options);
+    let (status, _) = run_to_end(&mut search, &map, 16);
+    assert!(matches!(status, SearchStatus::Found(_)));
+
+    let mut workspace = search.into_workspace();
+    assert_eq!(workspace.capacity(), map.width * map.height);
+    assert_eq!(
+        workspace.find_path_with_map(&map, (1, 3), (12, 3), &options),
+        astar_with_map_options(&map, (1, 3), (12, 3), &options)
+    );
+}
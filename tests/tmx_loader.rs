@@ -0,0 +1,130 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use enemy_pathfinder::map::{
+    RuntimeMapAdapter, RuntimeMapAdapterError, actor_spawn_from_tiled_json, actor_spawn_from_tmx,
+    end_zone_from_tiled_json, end_zone_from_tmx,
+};
+
+mod common;
+
+use common::temp_dir;
+
+fn assets_map_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("assets")
+        .join("map.json")
+}
+
+fn fixture_path(dir: &str, file: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(dir)
+        .join(file)
+}
+
+fn write_tmx(dir: &Path, body: &str) -> PathBuf {
+    let map_path = dir.join("map.tmx");
+    fs::write(&map_path, body).expect("failed to write map fixture");
+    map_path
+}
+
+#[test]
+fn tmx_level_matches_its_json_export() {
+    let json = RuntimeMapAdapter::from_tiled_json_wall_layer(assets_map_path())
+        .expect("assets/map.json should load");
+    let tmx_path = fixture_path("tmx", "map.tmx");
+    let tmx = RuntimeMapAdapter::from_tmx_wall_layer(&tmx_path).expect("map.tmx should load");
+
+    assert_eq!(tmx.summary(), json.summary());
+    assert_eq!(tmx.solid, json.solid);
+    assert_eq!(tmx.cost, json.cost);
+
+    for actor_type in ["Enemy", "Player"] {
+        let expected =
+            actor_spawn_from_tiled_json(assets_map_path(), actor_type).expect("json spawn lookup");
+        assert!(expected.is_some());
+        assert_eq!(
+            actor_spawn_from_tmx(&tmx_path, actor_type).expect("tmx spawn lookup"),
+            expected,
+            "{actor_type}"
+        );
+    }
+
+    let end_zone = end_zone_from_tiled_json(assets_map_path()).expect("json end zone lookup");
+    assert_eq!(end_zone, Some((32.0, 32.0, 96.0, 64.0)));
+    assert_eq!(
+        end_zone_from_tmx(&tmx_path).expect("tmx end zone lookup"),
+        end_zone
+    );
+}
+
+#[test]
+fn compressed_tmx_layers_match_json() {
+    let json =
+        RuntimeMapAdapter::from_tiled_json_wall_layer(fixture_path("layer_encodings", "csv.json"))
+            .expect("csv fixture should load");
+    let tmx = RuntimeMapAdapter::from_tmx_wall_layer(fixture_path("tmx", "weighted.tmx"))
+        .expect("weighted.tmx should load");
+
+    assert_eq!(tmx.summary(), json.summary());
+    assert_eq!(tmx.solid, json.solid);
+    assert_eq!(tmx.cost, json.cost);
+    assert_eq!(tmx.traversal_cost(1, 0), 2.5);
+}
+
+#[test]
+fn tile_elements_and_tileset_properties_are_read() {
+    let dir = temp_dir("tmx_elements");
+    let map_path = write_tmx(
+        &dir,
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="2" height="2" tilewidth="16" tileheight="16" infinite="0">
+ <tileset firstgid="1" name="terrain" tilewidth="16" tileheight="16" tilecount="2" columns="2">
+  <tile id="1">
+   <properties>
+    <property name="cost" type="float" value="4"/>
+   </properties>
+  </tile>
+ </tileset>
+ <layer id="1" name="Wall_Layer" width="2" height="2">
+  <data>
+   <tile gid="1"/>
+   <tile/>
+   <tile/>
+   <tile/>
+  </data>
+ </layer>
+ <layer id="2" name="Cost_Layer" width="2" height="2">
+  <data encoding="csv">0,0,2,0</data>
+ </layer>
+</map>
+"#,
+    );
+
+    let map = RuntimeMapAdapter::from_tmx_wall_layer(&map_path).expect("tmx should load");
+    assert_eq!((map.tile_width, map.tile_height), (16.0, 16.0));
+    assert_eq!(map.solid, vec![true, false, false, false]);
+    assert_eq!(map.traversal_cost(0, 1), 4.0);
+    assert_eq!(map.traversal_cost(1, 1), 1.0);
+}
+
+#[test]
+fn broken_tmx_files_are_rejected() {
+    let dir = temp_dir("tmx_broken");
+    let unclosed = write_tmx(&dir, "<map width=\"1\"");
+    assert!(matches!(
+        RuntimeMapAdapter::from_tmx_wall_layer(&unclosed),
+        Err(RuntimeMapAdapterError::Xml { .. })
+    ));
+
+    let missing_size = write_tmx(
+        &dir,
+        r#"<map width="1" height="1" tilewidth="32"><layer name="Wall_Layer"/></map>"#,
+    );
+    assert!(matches!(
+        RuntimeMapAdapter::from_tmx_wall_layer(&missing_size),
+        Err(RuntimeMapAdapterError::MalformedTmx { ref detail, .. }) if detail.contains("tileheight")
+    ));
+}